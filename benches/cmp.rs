@@ -0,0 +1,109 @@
+//! Compares the comparison operators of `SerialNumber` to deriving them
+//! from `partial_cmp`, as the default methods of `PartialOrd` do, and to
+//! the comparison with four range checks that `partial_cmp` used before.
+//!
+//! The serial numbers are pseudo-random, so branches can not be predicted.
+
+#[macro_use]
+extern crate criterion;
+extern crate sna;
+
+use std::cmp::Ordering;
+use std::hint::black_box;
+use std::ops::Sub;
+
+use criterion::{Criterion, Throughput};
+use sna::SerialNumber;
+
+const PAIRS: usize = 1024;
+
+/// The previous comparison of two serial numbers (of `SERIAL_BITS` bits,
+/// where `half` is `2^(SERIAL_BITS - 1)`) with four range checks.
+#[inline]
+fn four_range_checks<T: Copy + Ord + Sub<Output = T>>(a: T, b: T, half: T) -> Option<Ordering> {
+    if a == b {
+        Some(Ordering::Equal)
+    } else if (a < b && (b - a) < half) || (a > b && (a - b) > half) {
+        Some(Ordering::Less)
+    } else if (a < b && (b - a) > half) || (a > b && (a - b) < half) {
+        Some(Ordering::Greater)
+    } else {
+        None
+    }
+}
+
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Draw 128 pseudo-random bits, so `u128` serial numbers use the whole
+/// width (narrower ones are truncated).
+fn random(state: &mut u64) -> u128 {
+    let high = xorshift(state);
+    (u128::from(high) << 64) | u128::from(xorshift(state))
+}
+
+macro_rules! bench_width {
+    ($c:expr, $T:ty) => {{
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        let pairs: Vec<(SerialNumber<$T>, SerialNumber<$T>)> = (0..PAIRS)
+            .map(|_| (SerialNumber(random(&mut state) as $T), SerialNumber(random(&mut state) as $T)))
+            .collect();
+        let half: $T = (<$T>::MAX >> 1) + 1;
+
+        let mut group = $c.benchmark_group(concat!("lt/", stringify!($T)));
+        group.throughput(Throughput::Elements(PAIRS as u64));
+        group.bench_function("four_range_checks", |b| b.iter(|| {
+            black_box(&pairs).iter()
+                .filter(|&&(a, b)| four_range_checks(a.0, b.0, half) == Some(Ordering::Less))
+                .count()
+        }));
+        group.bench_function("partial_cmp", |b| b.iter(|| {
+            black_box(&pairs).iter()
+                .filter(|&&(a, b)| a.partial_cmp(&b) == Some(Ordering::Less))
+                .count()
+        }));
+        group.bench_function("operator", |b| b.iter(|| {
+            black_box(&pairs).iter().filter(|&&(a, b)| a < b).count()
+        }));
+        group.finish();
+
+        let mut group = $c.benchmark_group(concat!("ge/", stringify!($T)));
+        group.throughput(Throughput::Elements(PAIRS as u64));
+        group.bench_function("four_range_checks", |b| b.iter(|| {
+            black_box(&pairs).iter()
+                .filter(|&&(a, b)| match four_range_checks(a.0, b.0, half) {
+                    Some(Ordering::Greater) | Some(Ordering::Equal) => true,
+                    _ => false,
+                })
+                .count()
+        }));
+        group.bench_function("partial_cmp", |b| b.iter(|| {
+            black_box(&pairs).iter()
+                .filter(|&&(a, b)| match a.partial_cmp(&b) {
+                    Some(Ordering::Greater) | Some(Ordering::Equal) => true,
+                    _ => false,
+                })
+                .count()
+        }));
+        group.bench_function("operator", |b| b.iter(|| {
+            black_box(&pairs).iter().filter(|&&(a, b)| a >= b).count()
+        }));
+        group.finish();
+    }};
+}
+
+fn operators(c: &mut Criterion) {
+    bench_width!(c, u8);
+    bench_width!(c, u16);
+    bench_width!(c, u32);
+    bench_width!(c, u64);
+    bench_width!(c, u128);
+    bench_width!(c, usize);
+}
+
+criterion_group!(benches, operators);
+criterion_main!(benches);
@@ -0,0 +1,124 @@
+#ifndef SNA_H
+#define SNA_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The ordering between two serial numbers.
+ */
+typedef enum SnaOrdering {
+  /**
+   * The first serial number precedes the second one.
+   */
+  SNA_ORDERING_LESS = -1,
+  /**
+   * Both serial numbers are equal.
+   */
+  SNA_ORDERING_EQUAL = 0,
+  /**
+   * The first serial number follows the second one.
+   */
+  SNA_ORDERING_GREATER = 1,
+  /**
+   * Both serial numbers are exactly half of the serial number space
+   * apart, for which RFC 1982 leaves the ordering undefined.
+   */
+  SNA_ORDERING_UNDEFINED = 2,
+} SnaOrdering;
+
+/**
+ * The outcome of checking a serial number against a replay window.
+ */
+typedef enum SnaReplay {
+  /**
+   * The serial number has not been seen, yet.
+   */
+  SNA_REPLAY_ACCEPTED = 0,
+  /**
+   * The serial number has already been seen.
+   */
+  SNA_REPLAY_DUPLICATE = 1,
+  /**
+   * The serial number is older than the window.
+   */
+  SNA_REPLAY_TOO_OLD = 2,
+  /**
+   * The window pointer is null.
+   */
+  SNA_REPLAY_INVALID = 3,
+} SnaReplay;
+
+/**
+ * An opaque replay window of 32-bit serial numbers.
+ */
+typedef struct SnaReplayWindow SnaReplayWindow;
+
+/**
+ * Return the ordering between the 32-bit serial numbers `a` and `b`.
+ */
+enum SnaOrdering sna_cmp_u32(uint32_t a, uint32_t b);
+
+/**
+ * Add `addend` to the 32-bit serial number `serial` and store the sum in
+ * `result`.
+ *
+ * Returns `false` without touching `result` if `addend` exceeds the range
+ * for which RFC 1982 defines addition (`2^31 - 1`) or `result` is null.
+ *
+ * # Safety
+ *
+ * `result` must be null or valid for writes.
+ */
+bool sna_add_u32(uint32_t serial, uint32_t addend, uint32_t *result);
+
+/**
+ * Create a replay window of 32-bit serial numbers that tracks the `size`
+ * most recent serial numbers (see `ReplayWindow::new`).
+ *
+ * Returns null if `size` is zero or exceeds half of the serial number
+ * space. The window must be freed with `sna_replay_window_free`.
+ */
+struct SnaReplayWindow *sna_replay_window_new(size_t size);
+
+/**
+ * Check whether `serial` would be accepted by `window` without updating
+ * it.
+ *
+ * # Safety
+ *
+ * `window` must be null or returned by `sna_replay_window_new` and not
+ * freed, yet.
+ */
+enum SnaReplay sna_replay_window_check(const struct SnaReplayWindow *window, uint32_t serial);
+
+/**
+ * Check whether `serial` is accepted by `window` and, if so, mark it as
+ * seen.
+ *
+ * Only call this once the packet has been authenticated.
+ *
+ * # Safety
+ *
+ * `window` must be null or returned by `sna_replay_window_new` and not
+ * freed, yet. It must not be accessed concurrently.
+ */
+enum SnaReplay sna_replay_window_check_and_update(struct SnaReplayWindow *window,
+                                                  uint32_t serial);
+
+/**
+ * Free a replay window. Does nothing if `window` is null.
+ *
+ * # Safety
+ *
+ * `window` must be null or returned by `sna_replay_window_new` and not
+ * freed, yet.
+ */
+void sna_replay_window_free(struct SnaReplayWindow *window);
+
+#endif  /* SNA_H */
@@ -0,0 +1,219 @@
+//! Assertion macros for serial numbers.
+//!
+//! Unlike `assert!(a > b)`, these macros report both values, their signed
+//! difference and whether the comparison was ambiguous when they fail.
+
+use std::fmt;
+
+#[doc(hidden)]
+pub use std::cmp::Ordering;
+
+use SerialNumber;
+use uint::Uint;
+
+/// Assert that a serial number is greater than another one.
+///
+/// On failure, the panic message contains both values, their signed
+/// difference and whether the comparison was ambiguous (which is the case
+/// if the values are exactly half of the serial number space apart).
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate sna;
+/// use sna::SerialNumber;
+///
+/// # fn main() {
+/// assert_serial_gt!(SerialNumber(0u8), SerialNumber(255u8));
+/// assert_serial_gt!(SerialNumber(0u8), 255u8, "zero must follow {}", 255);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_serial_gt {
+    ($left:expr, $right:expr) => (
+        $crate::assert::compare(&$left, &$right, $crate::assert::Ordering::Greater, None)
+    );
+    ($left:expr, $right:expr, $($arg:tt)+) => (
+        $crate::assert::compare(&$left, &$right, $crate::assert::Ordering::Greater,
+                                Some(format_args!($($arg)+)))
+    );
+}
+
+/// Assert that a serial number is less than another one.
+///
+/// On failure, the panic message contains both values, their signed
+/// difference and whether the comparison was ambiguous.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate sna;
+/// use sna::SerialNumber;
+///
+/// # fn main() {
+/// assert_serial_lt!(SerialNumber(65535u16), SerialNumber(0u16));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_serial_lt {
+    ($left:expr, $right:expr) => (
+        $crate::assert::compare(&$left, &$right, $crate::assert::Ordering::Less, None)
+    );
+    ($left:expr, $right:expr, $($arg:tt)+) => (
+        $crate::assert::compare(&$left, &$right, $crate::assert::Ordering::Less,
+                                Some(format_args!($($arg)+)))
+    );
+}
+
+/// Assert that a serial number lies between a lower and an upper bound
+/// (both inclusive).
+///
+/// On failure, the panic message contains the value, both bounds and the
+/// signed differences of the value to each bound.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate sna;
+/// use sna::SerialNumber;
+///
+/// # fn main() {
+/// assert_serial_between!(SerialNumber(2u8), SerialNumber(250u8), SerialNumber(10u8));
+/// assert_serial_between!(SerialNumber(10u8), 250u8, 10u8);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_serial_between {
+    ($value:expr, $lower:expr, $upper:expr) => (
+        $crate::assert::between(&$value, &$lower, &$upper, None)
+    );
+    ($value:expr, $lower:expr, $upper:expr, $($arg:tt)+) => (
+        $crate::assert::between(&$value, &$lower, &$upper, Some(format_args!($($arg)+)))
+    );
+}
+
+/// The signed difference between two serial numbers, used to describe a
+/// failed comparison.
+struct Difference<T>(SerialNumber<T>, SerialNumber<T>);
+
+impl<T: Uint> fmt::Display for Difference<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let difference = (self.0).0.wrapping_sub((self.1).0);
+        if difference == T::ZERO {
+            write!(f, "0")
+        } else if difference < T::HALF {
+            write!(f, "+{:?}", difference)
+        } else if difference > T::HALF {
+            write!(f, "-{:?}", T::ZERO.wrapping_sub(difference))
+        } else {
+            write!(f, "{:?}, ambiguous", difference)
+        }
+    }
+}
+
+fn difference<T, L, R>(left: &L, right: &R) -> Difference<T>
+    where T: Uint, L: Copy + Into<SerialNumber<T>>, R: Copy + Into<SerialNumber<T>>
+{
+    Difference((*left).into(), (*right).into())
+}
+
+/// The optional custom message of a failed assertion.
+struct Suffix<'a>(Option<fmt::Arguments<'a>>);
+
+impl<'a> fmt::Display for Suffix<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(args) => write!(f, ": {}", args),
+            None => Ok(()),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn compare<T, L, R>(left: &L, right: &R, expected: Ordering, args: Option<fmt::Arguments>)
+    where T: Uint,
+          L: Copy + fmt::Debug + PartialOrd<R> + Into<SerialNumber<T>>,
+          R: Copy + fmt::Debug + Into<SerialNumber<T>>
+{
+    if left.partial_cmp(right) != Some(expected) {
+        let operator = if expected == Ordering::Less { "<" } else { ">" };
+        panic!("assertion failed: `(left {} right)` for serial numbers\n  \
+                left: `{:?}`,\n right: `{:?}`,\n  diff: `{}`{}",
+               operator, left, right, difference(left, right), Suffix(args))
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn between<T, V, L, U>(value: &V, lower: &L, upper: &U, args: Option<fmt::Arguments>)
+    where T: Uint,
+          V: Copy + fmt::Debug + PartialOrd<L> + PartialOrd<U> + Into<SerialNumber<T>>,
+          L: Copy + fmt::Debug + Into<SerialNumber<T>>,
+          U: Copy + fmt::Debug + Into<SerialNumber<T>>
+{
+    let above_lower = match value.partial_cmp(lower) {
+        Some(Ordering::Equal) | Some(Ordering::Greater) => true,
+        Some(Ordering::Less) | None => false,
+    };
+    let below_upper = match value.partial_cmp(upper) {
+        Some(Ordering::Equal) | Some(Ordering::Less) => true,
+        Some(Ordering::Greater) | None => false,
+    };
+    if !above_lower || !below_upper {
+        panic!("assertion failed: `(lower <= value <= upper)` for serial numbers\n \
+                value: `{:?}`,\n lower: `{:?}` (diff: `{}`),\n upper: `{:?}` (diff: `{}`){}",
+               value, lower, difference(value, lower), upper, difference(value, upper),
+               Suffix(args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test]
+    fn difference() {
+        assert_eq!(super::difference(&SerialNumber(2u8), &SerialNumber(250u8)).to_string(), "+8");
+        assert_eq!(super::difference(&SerialNumber(250u8), &2u8).to_string(), "-8");
+        assert_eq!(super::difference(&7u16, &7u16).to_string(), "0");
+        assert_eq!(super::difference(&0u32, &(1u32 << 31)).to_string(), "2147483648, ambiguous");
+    }
+
+    #[test]
+    fn passing() {
+        assert_serial_gt!(SerialNumber(1u32), 0u32);
+        assert_serial_lt!(u64::MAX, SerialNumber(0u64));
+        assert_serial_between!(SerialNumber(0u16), SerialNumber(0u16), SerialNumber(0u16));
+        assert_serial_between!(SerialNumber(0u16), u16::MAX, 1u16);
+    }
+
+    #[test]
+    #[should_panic(expected = "diff: `-1`: custom message 42")]
+    fn gt_message() {
+        assert_serial_gt!(SerialNumber(255u8), SerialNumber(0u8), "custom message {}", 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "diff: `128, ambiguous`")]
+    fn gt_ambiguous() {
+        assert_serial_gt!(SerialNumber(128u8), SerialNumber(0u8));
+    }
+
+    #[test]
+    #[should_panic(expected = "diff: `0`")]
+    fn lt_equal() {
+        assert_serial_lt!(SerialNumber(3u8), SerialNumber(3u8));
+    }
+
+    #[test]
+    #[should_panic(expected = "upper: `5` (diff: `+1`)")]
+    fn between_above() {
+        assert_serial_between!(SerialNumber(6u8), SerialNumber(250u8), SerialNumber(5u8));
+    }
+}
@@ -0,0 +1,211 @@
+//! Atomic serial numbers to allocate serial numbers from multiple threads.
+
+use std::fmt;
+use std::sync::atomic::Ordering;
+#[cfg(target_has_atomic = "16")]
+use std::sync::atomic::AtomicU16;
+#[cfg(target_has_atomic = "32")]
+use std::sync::atomic::AtomicU32;
+#[cfg(target_has_atomic = "64")]
+use std::sync::atomic::AtomicU64;
+
+use SerialNumber;
+use uint::Uint;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// The unsigned integer types that have an atomic counterpart usable by
+/// `AtomicSerialNumber`.
+///
+/// This trait is sealed and can not be implemented outside of this crate.
+pub trait HasAtomic: private::Sealed + Sized {
+    #[doc(hidden)]
+    type Atomic: Default;
+
+    #[doc(hidden)]
+    fn new(value: Self) -> Self::Atomic;
+    #[doc(hidden)]
+    fn load(atomic: &Self::Atomic, order: Ordering) -> Self;
+    #[doc(hidden)]
+    fn store(atomic: &Self::Atomic, value: Self, order: Ordering);
+    #[doc(hidden)]
+    fn swap(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self;
+    #[doc(hidden)]
+    fn fetch_add(atomic: &Self::Atomic, value: Self, order: Ordering) -> Self;
+    #[doc(hidden)]
+    fn into_inner(atomic: Self::Atomic) -> Self;
+}
+
+/// A serial number that can be shared between threads.
+///
+/// All operations wrap at the end of the serial number space. Use the
+/// `SerialNumber` returned by `load` or `fetch_next` for comparisons.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::sync::atomic::Ordering;
+/// use std::thread;
+///
+/// use sna::SerialNumber;
+/// use sna::atomic::AtomicSerialNumber;
+///
+/// let next = Arc::new(AtomicSerialNumber::new(SerialNumber(u16::MAX)));
+/// let threads: Vec<_> = (0..4).map(|_| {
+///     let next = next.clone();
+///     thread::spawn(move || next.fetch_next(Ordering::Relaxed))
+/// }).collect();
+/// let mut allocated: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap().0).collect();
+/// allocated.sort();
+///
+/// assert_eq!(allocated, vec![0, 1, 2, u16::MAX]);
+/// assert_eq!(next.load(Ordering::Relaxed), SerialNumber(3));
+/// ```
+pub struct AtomicSerialNumber<T: HasAtomic> {
+    inner: T::Atomic,
+}
+
+impl<T: HasAtomic> AtomicSerialNumber<T> {
+    /// Create an atomic serial number starting at `serial`.
+    #[inline]
+    pub fn new(serial: SerialNumber<T>) -> Self {
+        AtomicSerialNumber { inner: T::new(serial.0) }
+    }
+
+    /// Load the serial number that will be allocated next.
+    #[inline]
+    pub fn load(&self, order: Ordering) -> SerialNumber<T> {
+        SerialNumber(T::load(&self.inner, order))
+    }
+
+    /// Store the serial number that will be allocated next.
+    #[inline]
+    pub fn store(&self, serial: SerialNumber<T>, order: Ordering) {
+        T::store(&self.inner, serial.0, order)
+    }
+
+    /// Store the serial number that will be allocated next, returning the
+    /// previous one.
+    #[inline]
+    pub fn swap(&self, serial: SerialNumber<T>, order: Ordering) -> SerialNumber<T> {
+        SerialNumber(T::swap(&self.inner, serial.0, order))
+    }
+
+    /// Allocate `count` consecutive serial numbers, returning the first one.
+    #[inline]
+    pub fn fetch_add(&self, count: T, order: Ordering) -> SerialNumber<T> {
+        SerialNumber(T::fetch_add(&self.inner, count, order))
+    }
+
+    /// Consume the atomic, returning the serial number that would have been
+    /// allocated next.
+    #[inline]
+    pub fn into_inner(self) -> SerialNumber<T> {
+        SerialNumber(T::into_inner(self.inner))
+    }
+}
+
+impl<T: HasAtomic + Uint> AtomicSerialNumber<T> {
+    /// Allocate a serial number, advancing by one.
+    #[inline]
+    pub fn fetch_next(&self, order: Ordering) -> SerialNumber<T> {
+        self.fetch_add(T::ONE, order)
+    }
+}
+
+impl<T: HasAtomic> Default for AtomicSerialNumber<T> {
+    /// Create an atomic serial number starting at zero.
+    #[inline]
+    fn default() -> Self {
+        AtomicSerialNumber { inner: T::Atomic::default() }
+    }
+}
+
+impl<T: HasAtomic> From<SerialNumber<T>> for AtomicSerialNumber<T> {
+    #[inline]
+    fn from(serial: SerialNumber<T>) -> Self {
+        AtomicSerialNumber::new(serial)
+    }
+}
+
+impl<T: HasAtomic + fmt::Debug> fmt::Debug for AtomicSerialNumber<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(Ordering::Relaxed), f)
+    }
+}
+
+macro_rules! atomic_impl {
+    ($T:ty, $A:ty, $width:tt) => {
+        #[cfg(target_has_atomic = $width)]
+        impl private::Sealed for $T {}
+
+        #[cfg(target_has_atomic = $width)]
+        impl HasAtomic for $T {
+            type Atomic = $A;
+
+            #[inline]
+            fn new(value: $T) -> $A {
+                <$A>::new(value)
+            }
+
+            #[inline]
+            fn load(atomic: &$A, order: Ordering) -> $T {
+                atomic.load(order)
+            }
+
+            #[inline]
+            fn store(atomic: &$A, value: $T, order: Ordering) {
+                atomic.store(value, order)
+            }
+
+            #[inline]
+            fn swap(atomic: &$A, value: $T, order: Ordering) -> $T {
+                atomic.swap(value, order)
+            }
+
+            #[inline]
+            fn fetch_add(atomic: &$A, value: $T, order: Ordering) -> $T {
+                atomic.fetch_add(value, order)
+            }
+
+            #[inline]
+            fn into_inner(atomic: $A) -> $T {
+                atomic.into_inner()
+            }
+        }
+    };
+}
+
+atomic_impl!(u16, AtomicU16, "16");
+atomic_impl!(u32, AtomicU32, "32");
+atomic_impl!(u64, AtomicU64, "64");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::format;
+
+    #[test]
+    fn wrapping() {
+        let next = AtomicSerialNumber::new(SerialNumber(u32::MAX - 1));
+        assert_eq!(next.fetch_next(Ordering::SeqCst), SerialNumber(u32::MAX - 1));
+        assert_eq!(next.fetch_add(3, Ordering::SeqCst), SerialNumber(u32::MAX));
+        assert_eq!(next.load(Ordering::SeqCst), SerialNumber(2));
+        assert!(next.load(Ordering::SeqCst) > SerialNumber(u32::MAX));
+
+        next.store(SerialNumber(7), Ordering::SeqCst);
+        assert_eq!(next.swap(SerialNumber(9), Ordering::SeqCst), SerialNumber(7));
+        assert_eq!(next.into_inner(), SerialNumber(9));
+    }
+
+    #[test]
+    fn default() {
+        let next = AtomicSerialNumber::<u64>::default();
+        assert_eq!(next.fetch_next(Ordering::SeqCst), SerialNumber(0));
+        assert_eq!(format!("{:?}", next), "1");
+    }
+}
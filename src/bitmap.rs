@@ -0,0 +1,146 @@
+//! The bitmap of replay windows, shared by `replay::ReplayWindow` and
+//! `fixed::ReplayWindow`.
+
+use std::cmp::Ordering;
+
+use SerialNumber;
+use uint::Uint;
+
+/// The outcome of checking a serial number against a `ReplayWindow`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Replay {
+    /// The serial number has not been seen, yet.
+    Accepted,
+    /// The serial number has already been seen.
+    Duplicate,
+    /// The serial number is older than the window, so it can not be
+    /// determined whether it has already been seen.
+    TooOld,
+}
+
+/// The position of a serial number relative to the highest one seen.
+pub enum Offset {
+    /// Ahead of the highest serial number by the given distance, capped at
+    /// the window size.
+    Ahead(usize),
+    /// At the given index of the bitmap.
+    Behind(usize),
+    TooOld,
+}
+
+/// A window of `size` serial numbers below and including `highest`, whose
+/// bits are stored in a slice of words.
+#[derive(Clone, Copy, Debug)]
+pub struct Window<T> {
+    pub size: usize,
+    pub highest: Option<SerialNumber<T>>,
+}
+
+impl<T: Uint> Window<T> where SerialNumber<T>: PartialOrd {
+    pub fn check(&self, bitmap: &[u64], serial: SerialNumber<T>) -> Replay {
+        match self.offset(serial) {
+            Offset::Ahead(_) => Replay::Accepted,
+            Offset::Behind(index) if is_set(bitmap, index) => Replay::Duplicate,
+            Offset::Behind(_) => Replay::Accepted,
+            Offset::TooOld => Replay::TooOld,
+        }
+    }
+
+    pub fn check_batch(&self, bitmap: &[u64], serials: &[SerialNumber<T>]) -> u64 {
+        assert!(serials.len() <= 64, "batch exceeds 64 serial numbers");
+        serials.iter().enumerate()
+            .filter(|&(index, &serial)| {
+                self.check(bitmap, serial) == Replay::Accepted && !serials[..index].contains(&serial)
+            })
+            .fold(0, |accepted, (index, _)| accepted | 1 << index)
+    }
+
+    pub fn check_and_update(&mut self, bitmap: &mut [u64], serial: SerialNumber<T>) -> Replay {
+        let replay = self.check(bitmap, serial);
+        if replay == Replay::Accepted {
+            match self.offset(serial) {
+                Offset::Ahead(shift) => {
+                    self.shift(bitmap, shift);
+                    self.highest = Some(serial);
+                    set(bitmap, 0);
+                }
+                Offset::Behind(index) => set(bitmap, index),
+                Offset::TooOld => unreachable!(),
+            }
+        }
+        replay
+    }
+
+    /// Consider `highest` and every serial number of the window below it
+    /// seen.
+    pub fn fill(&mut self, bitmap: &mut [u64], highest: SerialNumber<T>) {
+        self.highest = Some(highest);
+        for word in bitmap.iter_mut() {
+            *word = u64::MAX;
+        }
+        self.clear_unused(bitmap);
+    }
+
+    pub fn reset(&mut self, bitmap: &mut [u64]) {
+        self.highest = None;
+        for word in bitmap {
+            *word = 0;
+        }
+    }
+
+    fn offset(&self, serial: SerialNumber<T>) -> Offset {
+        let highest = match self.highest {
+            Some(highest) => highest,
+            None => return Offset::Ahead(self.size),
+        };
+        match serial.partial_cmp(&highest) {
+            Some(Ordering::Greater) => {
+                let distance = serial.0.wrapping_sub(highest.0).to_u128();
+                Offset::Ahead(distance.min(self.size as u128) as usize)
+            }
+            Some(Ordering::Equal) | Some(Ordering::Less) => {
+                let distance = highest.0.wrapping_sub(serial.0).to_u128();
+                if distance < self.size as u128 {
+                    Offset::Behind(distance as usize)
+                } else {
+                    Offset::TooOld
+                }
+            }
+            None => Offset::TooOld,
+        }
+    }
+
+    /// Move every bit `shift` positions towards older serial numbers.
+    fn shift(&self, bitmap: &mut [u64], shift: usize) {
+        let (words, bits) = (shift / 64, (shift % 64) as u32);
+        for index in (0..bitmap.len()).rev() {
+            let high = index.checked_sub(words).map_or(0, |source| bitmap[source]);
+            let low = index.checked_sub(words + 1).map_or(0, |source| bitmap[source]);
+            bitmap[index] = if bits == 0 {
+                high
+            } else {
+                (high << bits) | (low >> (64 - bits))
+            };
+        }
+        self.clear_unused(bitmap);
+    }
+
+    /// Clear the bits beyond the window, which may span whole words.
+    fn clear_unused(&self, bitmap: &mut [u64]) {
+        let used = self.size.div_ceil(64);
+        for word in &mut bitmap[used..] {
+            *word = 0;
+        }
+        if !self.size.is_multiple_of(64) {
+            bitmap[used - 1] &= u64::MAX >> (64 - self.size % 64);
+        }
+    }
+}
+
+fn is_set(bitmap: &[u64], index: usize) -> bool {
+    bitmap[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn set(bitmap: &mut [u64], index: usize) {
+    bitmap[index / 64] |= 1 << (index % 64);
+}
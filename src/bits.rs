@@ -0,0 +1,263 @@
+//! Serial numbers of arbitrary bit width, e.g. 12-bit 802.11 sequence
+//! numbers, 33-bit MPEG timestamps or 48-bit extended RTP indices.
+//!
+//! `Serial48` covers the 48-bit record sequence numbers of DTLS and the
+//! 48-bit packet indices of SRTP.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
+use uint::Uint;
+
+/// A serial number of `BITS` bits (`SERIAL_BITS`) stored in the unsigned
+/// integer type `T`.
+///
+/// Addition wraps modulo `2^BITS` and comparisons use `2^(BITS - 1)` as
+/// the half of the serial number space, so this behaves like a
+/// `SerialNumber` of a hypothetical `BITS`-bit integer type.
+///
+/// # Examples
+///
+/// ```
+/// use sna::Serial;
+///
+/// let max = Serial::<u16, 12>::new(4095).unwrap();
+/// let zero = max + 1;
+///
+/// assert_eq!(zero.get(), 0);
+/// assert!(zero > max);
+/// assert_eq!(Serial::<u16, 12>::new(4096), None);
+/// assert_eq!(Serial::<u16, 12>::new_masked(4097).get(), 1);
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Serial<T, const BITS: u32>(T);
+
+impl<T: Uint, const BITS: u32> Serial<T, BITS> {
+    const VALID: () = assert!(BITS > 0 && BITS <= T::BITS, "BITS must be in 1..=T::BITS");
+
+    /// The largest representable number (`2^BITS - 1`).
+    #[inline]
+    pub fn max_value() -> T {
+        let () = Self::VALID;
+        T::mask(BITS)
+    }
+
+    /// Create a serial number from `value`.
+    ///
+    /// Returns `None` if `value` does not fit into `BITS` bits.
+    #[inline]
+    pub fn new(value: T) -> Option<Self> {
+        if value <= Self::max_value() {
+            Some(Serial(value))
+        } else {
+            None
+        }
+    }
+
+    /// Create a serial number from the `BITS` least significant bits of
+    /// `value`.
+    #[inline]
+    pub fn new_masked(value: T) -> Self {
+        Serial(value & Self::max_value())
+    }
+
+    /// Return the value of the serial number.
+    #[inline]
+    pub fn get(self) -> T {
+        self.0
+    }
+
+    /// Return the successor of the serial number, wrapping at `2^BITS`.
+    #[inline]
+    pub fn succ(self) -> Self {
+        self + T::ONE
+    }
+
+    /// Return the predecessor of the serial number, wrapping at `2^BITS`.
+    #[inline]
+    pub fn pred(self) -> Self {
+        Serial::new_masked(self.0.wrapping_sub(T::ONE))
+    }
+
+    /// Advance the serial number by `n`, like `self + n`.
+    ///
+    /// The result only follows `self` if `n` is less than `2^(BITS - 1)`
+    /// (see `SerialNumber::advance`).
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        self + n
+    }
+
+    fn half() -> T {
+        T::mask(BITS - 1).wrapping_add(T::ONE)
+    }
+}
+
+/// A 48-bit serial number, e.g. a DTLS record sequence number or an SRTP
+/// packet index.
+///
+/// # Examples
+///
+/// ```
+/// use sna::Serial48;
+///
+/// let serial = Serial48::new(0x0102_0304_0506).unwrap();
+/// assert_eq!(serial.to_be_bytes(), [1, 2, 3, 4, 5, 6]);
+/// assert_eq!(Serial48::from_be_bytes([1, 2, 3, 4, 5, 6]), serial);
+/// assert_eq!(Serial48::new(1 << 48), None);
+/// ```
+pub type Serial48 = Serial<u64, 48>;
+
+impl Serial<u64, 48> {
+    /// Create a serial number from its big-endian representation.
+    #[inline]
+    pub fn from_be_bytes(bytes: [u8; 6]) -> Self {
+        let mut value = [0; 8];
+        value[2..].copy_from_slice(&bytes);
+        Serial(u64::from_be_bytes(value))
+    }
+
+    /// Return the big-endian representation of the serial number.
+    #[inline]
+    pub fn to_be_bytes(self) -> [u8; 6] {
+        let mut bytes = [0; 6];
+        bytes.copy_from_slice(&self.0.to_be_bytes()[2..]);
+        bytes
+    }
+
+    /// XOR the serial number, left-padded with zeros to the length of `iv`,
+    /// into `iv` to form the per-record nonce of an AEAD cipher, see
+    /// [chapter 5.3 of RFC 8446](https://tools.ietf.org/html/rfc8446#section-5.3).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `iv` is shorter than 6 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::Serial48;
+    ///
+    /// let mut nonce = [0xff; 12];
+    /// Serial48::new(0x0102).unwrap().xor_nonce(&mut nonce);
+    /// assert_eq!(nonce, [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xfd]);
+    /// ```
+    pub fn xor_nonce(self, iv: &mut [u8]) {
+        assert!(iv.len() >= 6, "iv must be at least 6 bytes long");
+        let offset = iv.len() - 6;
+        for (byte, serial) in iv[offset..].iter_mut().zip(&self.to_be_bytes()) {
+            *byte ^= serial;
+        }
+    }
+}
+
+impl<T: fmt::Debug, const BITS: u32> fmt::Debug for Serial<T, BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display, const BITS: u32> fmt::Display for Serial<T, BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Uint, const BITS: u32> Add<T> for Serial<T, BITS> {
+    type Output = Serial<T, BITS>;
+
+    /// Apply addition of a positive integer modulo `2^BITS` (e.g. it will
+    /// *wrap* when overflowing).
+    #[inline]
+    fn add(self, other: T) -> Serial<T, BITS> {
+        Serial::new_masked(self.0.wrapping_add(other))
+    }
+}
+
+impl<T: Uint, const BITS: u32> AddAssign<T> for Serial<T, BITS> {
+    /// Performs the `+=` operation modulo `2^BITS` (e.g. it will *wrap*
+    /// when overflowing).
+    #[inline]
+    fn add_assign(&mut self, other: T) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Uint, const BITS: u32> PartialOrd for Serial<T, BITS> {
+    /// Return an ordering between `self` and `other`, which is undefined if
+    /// both are exactly `2^(BITS - 1)` apart.
+    fn partial_cmp(&self, other: &Serial<T, BITS>) -> Option<Ordering> {
+        let distance = other.0.wrapping_sub(self.0) & Self::max_value();
+        let half = Self::half();
+        if distance == T::ZERO {
+            Some(Ordering::Equal)
+        } else if distance < half {
+            Some(Ordering::Less)
+        } else if distance > half {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Serial33 = Serial<u64, 33>;
+
+    #[test]
+    fn new() {
+        assert_eq!(Serial33::max_value(), (1 << 33) - 1);
+        assert_eq!(Serial33::new(1 << 33), None);
+        assert_eq!(Serial33::new((1 << 33) - 1).map(Serial::get), Some((1 << 33) - 1));
+        assert_eq!(Serial33::new_masked(u64::MAX).get(), (1 << 33) - 1);
+    }
+
+    #[test]
+    fn add() {
+        let mut serial = Serial33::new_masked(u64::MAX);
+        serial += 2;
+        assert_eq!(serial.get(), 1);
+        assert_eq!((Serial::<u8, 1>::new_masked(1) + 1).get(), 0);
+        assert_eq!((Serial::<u32, 32>::new_masked(u32::MAX) + 1).get(), 0);
+    }
+
+    #[test]
+    fn succ_pred() {
+        let max = Serial33::new_masked(u64::MAX);
+        assert_eq!(max.succ().get(), 0);
+        assert_eq!(max.succ().pred(), max);
+        assert_eq!(Serial::<u8, 3>::new_masked(0).pred().get(), 7);
+        assert_eq!(max.advance(3).get(), 2);
+    }
+
+    #[test]
+    fn partial_cmp() {
+        let zero = Serial::<u32, 24>::new_masked(0);
+        let half = Serial::<u32, 24>::new_masked(1 << 23);
+        let max = Serial::<u32, 24>::new_masked(u32::MAX);
+        assert_eq!(zero.partial_cmp(&zero), Some(Ordering::Equal));
+        assert_eq!(max.partial_cmp(&zero), Some(Ordering::Less));
+        assert_eq!(zero.partial_cmp(&max), Some(Ordering::Greater));
+        assert_eq!(zero.partial_cmp(&half), None);
+        assert_eq!((half + 1).partial_cmp(&zero), Some(Ordering::Less));
+        assert!(Serial::<u64, 64>::new_masked(0) > Serial::new_masked(u64::MAX));
+    }
+
+    #[test]
+    fn serial48() {
+        let max = Serial48::new_masked(u64::MAX);
+        assert_eq!(max.get(), (1 << 48) - 1);
+        assert_eq!(max.to_be_bytes(), [0xff; 6]);
+        assert_eq!((max + 1).to_be_bytes(), [0; 6]);
+        assert!(max + 1 > max);
+        assert_eq!(Serial48::new_masked(1 << 47).partial_cmp(&Serial48::new_masked(0)), None);
+
+        let mut nonce = [0; 6];
+        max.xor_nonce(&mut nonce);
+        assert_eq!(nonce, [0xff; 6]);
+    }
+}
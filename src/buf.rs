@@ -0,0 +1,228 @@
+//! Extension methods to read and write serial numbers with the `bytes`
+//! crate.
+//!
+//! Requires the `bytes` feature.
+
+use bytes::{Buf, BufMut};
+
+use {Serial48, SerialNumber};
+
+/// Read serial numbers from a `Buf`.
+///
+/// The methods without suffix read in network byte order (big-endian), the
+/// `_le` methods in little-endian byte order. Like the methods of `Buf`,
+/// they panic if there are not enough remaining bytes.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate sna;
+/// use sna::SerialNumber;
+/// use sna::buf::SerialBufExt;
+///
+/// let mut buf = &b"\x00\x01\x02\x03\x04\x05"[..];
+///
+/// assert_eq!(buf.get_serial_u16(), SerialNumber(0x0001u16));
+/// assert_eq!(buf.get_serial_u32_le(), SerialNumber(0x05040302u32));
+///
+/// let mut record = &b"\x00\x00\x00\x00\x01\x00"[..];
+/// assert_eq!(record.get_serial_u48().get(), 0x0100);
+/// ```
+pub trait SerialBufExt: Buf {
+    /// Read a serial number from a single byte.
+    #[inline]
+    fn get_serial_u8(&mut self) -> SerialNumber<u8> {
+        SerialNumber(self.get_u8())
+    }
+
+    /// Read a 16-bit serial number in big-endian byte order.
+    #[inline]
+    fn get_serial_u16(&mut self) -> SerialNumber<u16> {
+        SerialNumber(self.get_u16())
+    }
+
+    /// Read a 16-bit serial number in little-endian byte order.
+    #[inline]
+    fn get_serial_u16_le(&mut self) -> SerialNumber<u16> {
+        SerialNumber(self.get_u16_le())
+    }
+
+    /// Read a 32-bit serial number in big-endian byte order.
+    #[inline]
+    fn get_serial_u32(&mut self) -> SerialNumber<u32> {
+        SerialNumber(self.get_u32())
+    }
+
+    /// Read a 32-bit serial number in little-endian byte order.
+    #[inline]
+    fn get_serial_u32_le(&mut self) -> SerialNumber<u32> {
+        SerialNumber(self.get_u32_le())
+    }
+
+    /// Read a 48-bit serial number in big-endian byte order, e.g. the
+    /// record sequence number of DTLS.
+    #[inline]
+    fn get_serial_u48(&mut self) -> Serial48 {
+        Serial48::new_masked(self.get_uint(6))
+    }
+
+    /// Read a 48-bit serial number in little-endian byte order.
+    #[inline]
+    fn get_serial_u48_le(&mut self) -> Serial48 {
+        Serial48::new_masked(self.get_uint_le(6))
+    }
+
+    /// Read a 64-bit serial number in big-endian byte order.
+    #[inline]
+    fn get_serial_u64(&mut self) -> SerialNumber<u64> {
+        SerialNumber(self.get_u64())
+    }
+
+    /// Read a 64-bit serial number in little-endian byte order.
+    #[inline]
+    fn get_serial_u64_le(&mut self) -> SerialNumber<u64> {
+        SerialNumber(self.get_u64_le())
+    }
+}
+
+impl<B: Buf + ?Sized> SerialBufExt for B {}
+
+/// Write serial numbers into a `BufMut`.
+///
+/// The methods without suffix write in network byte order (big-endian), the
+/// `_le` methods in little-endian byte order. Like the methods of `BufMut`,
+/// they panic if there is not enough remaining capacity.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate sna;
+/// use sna::SerialNumber;
+/// use sna::buf::SerialBufMutExt;
+///
+/// let mut buf = Vec::new();
+/// buf.put_serial_u16(SerialNumber(0x0102u16));
+/// buf.put_serial_u32_le(SerialNumber(0x03040506u32));
+///
+/// assert_eq!(buf, b"\x01\x02\x06\x05\x04\x03");
+/// ```
+pub trait SerialBufMutExt: BufMut {
+    /// Write a serial number as a single byte.
+    #[inline]
+    fn put_serial_u8(&mut self, serial: SerialNumber<u8>) {
+        self.put_u8(serial.0)
+    }
+
+    /// Write a 16-bit serial number in big-endian byte order.
+    #[inline]
+    fn put_serial_u16(&mut self, serial: SerialNumber<u16>) {
+        self.put_u16(serial.0)
+    }
+
+    /// Write a 16-bit serial number in little-endian byte order.
+    #[inline]
+    fn put_serial_u16_le(&mut self, serial: SerialNumber<u16>) {
+        self.put_u16_le(serial.0)
+    }
+
+    /// Write a 32-bit serial number in big-endian byte order.
+    #[inline]
+    fn put_serial_u32(&mut self, serial: SerialNumber<u32>) {
+        self.put_u32(serial.0)
+    }
+
+    /// Write a 32-bit serial number in little-endian byte order.
+    #[inline]
+    fn put_serial_u32_le(&mut self, serial: SerialNumber<u32>) {
+        self.put_u32_le(serial.0)
+    }
+
+    /// Write a 48-bit serial number in big-endian byte order.
+    #[inline]
+    fn put_serial_u48(&mut self, serial: Serial48) {
+        self.put_uint(serial.get(), 6)
+    }
+
+    /// Write a 48-bit serial number in little-endian byte order.
+    #[inline]
+    fn put_serial_u48_le(&mut self, serial: Serial48) {
+        self.put_uint_le(serial.get(), 6)
+    }
+
+    /// Write a 64-bit serial number in big-endian byte order.
+    #[inline]
+    fn put_serial_u64(&mut self, serial: SerialNumber<u64>) {
+        self.put_u64(serial.0)
+    }
+
+    /// Write a 64-bit serial number in little-endian byte order.
+    #[inline]
+    fn put_serial_u64_le(&mut self, serial: SerialNumber<u64>) {
+        self.put_u64_le(serial.0)
+    }
+}
+
+impl<B: BufMut + ?Sized> SerialBufMutExt for B {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::BytesMut;
+
+    #[test]
+    fn roundtrip() {
+        let mut buf = BytesMut::new();
+        buf.put_serial_u8(SerialNumber(0xff));
+        buf.put_serial_u16(SerialNumber(0xfffe));
+        buf.put_serial_u16_le(SerialNumber(0xfffe));
+        buf.put_serial_u32(SerialNumber(u32::MAX));
+        buf.put_serial_u32_le(SerialNumber(1));
+        buf.put_serial_u64(SerialNumber(1));
+        buf.put_serial_u64_le(SerialNumber(u64::MAX - 1));
+        assert_eq!(buf.len(), 1 + 2 + 2 + 4 + 4 + 8 + 8);
+        assert_eq!(&buf[1..5], b"\xff\xfe\xfe\xff");
+
+        let mut buf = buf.freeze();
+        assert_eq!(buf.get_serial_u8(), SerialNumber(0xff));
+        assert_eq!(buf.get_serial_u16(), SerialNumber(0xfffe));
+        assert_eq!(buf.get_serial_u16_le(), SerialNumber(0xfffe));
+        assert_eq!(buf.get_serial_u32(), SerialNumber(u32::MAX));
+        assert_eq!(buf.get_serial_u32_le(), SerialNumber(1));
+        assert_eq!(buf.get_serial_u64(), SerialNumber(1));
+        assert_eq!(buf.get_serial_u64_le(), SerialNumber(u64::MAX - 1));
+        assert!(!buf.has_remaining());
+    }
+
+    #[test]
+    fn roundtrip_u48() {
+        let max = Serial48::new_masked(u64::MAX);
+        let mut buf = BytesMut::new();
+        buf.put_serial_u48(Serial48::new(0x0102_0304_0506).unwrap());
+        buf.put_serial_u48_le(Serial48::new(0x0102_0304_0506).unwrap());
+        buf.put_serial_u48(max);
+        assert_eq!(&buf[..12], b"\x01\x02\x03\x04\x05\x06\x06\x05\x04\x03\x02\x01");
+
+        let mut buf = buf.freeze();
+        assert_eq!(buf.get_serial_u48(), Serial48::new(0x0102_0304_0506).unwrap());
+        assert_eq!(buf.get_serial_u48_le(), Serial48::new(0x0102_0304_0506).unwrap());
+        assert_eq!(buf.get_serial_u48(), max);
+        assert!(!buf.has_remaining());
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_enough_bytes_u48() {
+        let mut buf = &b"\x00\x01\x02\x03\x04"[..];
+        buf.get_serial_u48();
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_enough_bytes() {
+        let mut buf = &b"\x00\x01\x02"[..];
+        buf.get_serial_u32();
+    }
+}
@@ -0,0 +1,494 @@
+//! A `tokio-util` codec that stamps outgoing frames with serial numbers and
+//! validates the serial numbers of incoming frames.
+//!
+//! `SequencedCodec` wraps a framing codec (e.g. `LengthDelimitedCodec`) and
+//! prepends a serial number in network byte order to the payload of each
+//! frame. What happens to incoming frames that are out of sequence is
+//! configured by a `Violation` policy.
+//!
+//! Requires the `codec` feature.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use SerialNumber;
+use buf::{SerialBufExt, SerialBufMutExt};
+use uint::Uint;
+
+mod wire {
+    use super::*;
+
+    /// Serial number types that can be written to and read from a frame.
+    pub trait Wire: Uint {
+        /// The number of bytes of the encoded serial number.
+        const SIZE: usize;
+
+        /// Read a serial number in network byte order.
+        fn get(buf: &mut BytesMut) -> SerialNumber<Self>;
+
+        /// Write a serial number in network byte order.
+        fn put(serial: SerialNumber<Self>, buf: &mut BytesMut);
+    }
+}
+
+use self::wire::Wire;
+
+macro_rules! wire_impl {
+    ($T:ty, $get:ident, $put:ident) => {
+        impl Wire for $T {
+            const SIZE: usize = <$T as Uint>::BITS as usize / 8;
+
+            #[inline]
+            fn get(buf: &mut BytesMut) -> SerialNumber<$T> {
+                buf.$get()
+            }
+
+            #[inline]
+            fn put(serial: SerialNumber<$T>, buf: &mut BytesMut) {
+                buf.$put(serial)
+            }
+        }
+    }
+}
+
+wire_impl!(u8, get_serial_u8, put_serial_u8);
+wire_impl!(u16, get_serial_u16, put_serial_u16);
+wire_impl!(u32, get_serial_u32, put_serial_u32);
+wire_impl!(u64, get_serial_u64, put_serial_u64);
+
+/// What to do with an incoming frame whose serial number is not the
+/// expected one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// Drop frames that are late or duplicated. Frames ahead of the expected
+    /// serial number are delivered and the frames in between are considered
+    /// lost.
+    Drop,
+    /// Hold back up to the given number of frames that are ahead of the
+    /// expected serial number and deliver them once the gap has been filled.
+    /// Late or duplicated frames are dropped. Exceeding the limit is an
+    /// error.
+    Reorder(usize),
+    /// Fail on any frame that does not carry the expected serial number.
+    Error,
+}
+
+/// The outcome of `Validator::check`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// Deliver the frame.
+    Deliver,
+    /// Hold back the frame until the preceding frames have been delivered.
+    Hold,
+    /// Silently discard the frame.
+    Discard,
+    /// Fail with a sequence error.
+    Reject,
+}
+
+/// Assigns consecutive serial numbers to outgoing frames.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::codec::Generator;
+///
+/// let mut generator = Generator::new(SerialNumber(255u8));
+/// assert_eq!(generator.next_serial(), SerialNumber(255u8));
+/// assert_eq!(generator.next_serial(), SerialNumber(0u8));
+/// assert_eq!(generator.peek(), SerialNumber(1u8));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Generator<T = u32> {
+    next: SerialNumber<T>,
+}
+
+impl<T: Uint> Generator<T> {
+    /// Create a generator whose first serial number is `first`.
+    #[inline]
+    pub fn new(first: SerialNumber<T>) -> Self {
+        Generator { next: first }
+    }
+
+    /// Return the serial number that will be assigned next.
+    #[inline]
+    pub fn peek(&self) -> SerialNumber<T> {
+        self.next
+    }
+
+    /// Assign the next serial number (modulo the largest representable
+    /// number of this type).
+    #[inline]
+    pub fn next_serial(&mut self) -> SerialNumber<T> {
+        let serial = self.next;
+        self.next = SerialNumber(serial.0.wrapping_add(T::ONE));
+        serial
+    }
+}
+
+/// Checks the serial numbers of incoming frames against the expected one.
+///
+/// Frames whose serial number is incomparable to the expected one (exactly
+/// half of the serial number space apart) are treated like late frames.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::codec::{Check, Validator, Violation};
+///
+/// let mut validator = Validator::new(SerialNumber(255u8), Violation::Drop);
+/// assert_eq!(validator.check(SerialNumber(255u8)), Check::Deliver);
+/// assert_eq!(validator.check(SerialNumber(2u8)), Check::Deliver);
+/// assert_eq!(validator.check(SerialNumber(1u8)), Check::Discard);
+/// assert_eq!(validator.expected(), SerialNumber(3u8));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Validator<T = u32> {
+    expected: SerialNumber<T>,
+    violation: Violation,
+}
+
+impl<T: Uint> Validator<T> where SerialNumber<T>: PartialOrd {
+    /// Create a validator expecting `first` and applying `violation` to
+    /// frames that are out of sequence.
+    #[inline]
+    pub fn new(first: SerialNumber<T>, violation: Violation) -> Self {
+        Validator { expected: first, violation }
+    }
+
+    /// Return the serial number that is expected next.
+    #[inline]
+    pub fn expected(&self) -> SerialNumber<T> {
+        self.expected
+    }
+
+    /// Return the policy for frames that are out of sequence.
+    #[inline]
+    pub fn violation(&self) -> Violation {
+        self.violation
+    }
+
+    /// Check the serial number of an incoming frame.
+    ///
+    /// The expected serial number moves past every frame that is to be
+    /// delivered. Frames that are held back are not accounted for until
+    /// they are passed to `check` again.
+    pub fn check(&mut self, serial: SerialNumber<T>) -> Check {
+        let check = match (serial.partial_cmp(&self.expected), self.violation) {
+            (Some(Ordering::Equal), _) => Check::Deliver,
+            (_, Violation::Error) => Check::Reject,
+            (Some(Ordering::Greater), Violation::Drop) => Check::Deliver,
+            (Some(Ordering::Greater), Violation::Reorder(_)) => Check::Hold,
+            (Some(Ordering::Less), _) | (None, _) => Check::Discard,
+        };
+        if check == Check::Deliver {
+            self.expected = SerialNumber(serial.0.wrapping_add(T::ONE));
+        }
+        check
+    }
+}
+
+/// An error when decoding a sequenced frame.
+#[derive(Debug)]
+pub enum DecodeError<E, T = u32> {
+    /// The wrapped codec failed or a frame was too short to contain a
+    /// serial number.
+    Inner(E),
+    /// A frame was out of sequence and rejected by the `Violation` policy,
+    /// or too many frames have been held back for reordering.
+    Sequence {
+        /// The serial number that was expected.
+        expected: SerialNumber<T>,
+        /// The serial number of the offending frame.
+        received: SerialNumber<T>,
+    },
+}
+
+impl<E: From<io::Error>, T> From<io::Error> for DecodeError<E, T> {
+    fn from(error: io::Error) -> Self {
+        DecodeError::Inner(E::from(error))
+    }
+}
+
+impl<E: fmt::Display, T: Uint> fmt::Display for DecodeError<E, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Inner(ref error) => error.fmt(f),
+            DecodeError::Sequence { expected, received } => {
+                write!(f, "frame out of sequence, expected {:?} but received {:?}",
+                       expected.0, received.0)
+            }
+        }
+    }
+}
+
+impl<E: error::Error + 'static, T: Uint> error::Error for DecodeError<E, T> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            DecodeError::Inner(ref error) => Some(error),
+            DecodeError::Sequence { .. } => None,
+        }
+    }
+}
+
+/// A codec that prepends a serial number to the payload of each frame of a
+/// wrapped framing codec.
+///
+/// Encoding takes the payload as `Bytes` and stamps it with the next
+/// serial number of its `Generator`. Decoding yields the serial number and
+/// the remaining payload of each frame that passes its `Validator`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate bytes;
+/// # extern crate sna;
+/// # extern crate tokio_util;
+/// use bytes::{Bytes, BytesMut};
+/// use tokio_util::codec::{Decoder, Encoder, LengthDelimitedCodec};
+///
+/// use sna::SerialNumber;
+/// use sna::codec::{SequencedCodec, Violation};
+///
+/// let mut codec: SequencedCodec<_, u16> =
+///     SequencedCodec::new(LengthDelimitedCodec::new(), SerialNumber(65535), Violation::Error);
+///
+/// let mut buf = BytesMut::new();
+/// codec.encode(Bytes::from_static(b"hello"), &mut buf).unwrap();
+/// codec.encode(Bytes::from_static(b"world"), &mut buf).unwrap();
+///
+/// let (serial, payload) = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!((serial, &payload[..]), (SerialNumber(65535), &b"hello"[..]));
+/// let (serial, payload) = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!((serial, &payload[..]), (SerialNumber(0), &b"world"[..]));
+/// ```
+#[derive(Debug)]
+pub struct SequencedCodec<C, T = u32> {
+    inner: C,
+    generator: Generator<T>,
+    validator: Validator<T>,
+    pending: HashMap<T, BytesMut>,
+}
+
+impl<C, T: Wire> SequencedCodec<C, T> where SerialNumber<T>: PartialOrd {
+    /// Wrap `inner`, using `first` as the first serial number of both
+    /// directions.
+    #[inline]
+    pub fn new(inner: C, first: SerialNumber<T>, violation: Violation) -> Self {
+        SequencedCodec::with_parts(inner, Generator::new(first), Validator::new(first, violation))
+    }
+
+    /// Wrap `inner` with a separate generator and validator, e.g. if both
+    /// directions start at different serial numbers.
+    #[inline]
+    pub fn with_parts(inner: C, generator: Generator<T>, validator: Validator<T>) -> Self {
+        SequencedCodec { inner, generator, validator, pending: HashMap::new() }
+    }
+
+    /// Return the generator of outgoing serial numbers.
+    #[inline]
+    pub fn generator(&self) -> &Generator<T> {
+        &self.generator
+    }
+
+    /// Return the validator of incoming serial numbers.
+    #[inline]
+    pub fn validator(&self) -> &Validator<T> {
+        &self.validator
+    }
+
+    /// Return the number of frames held back for reordering.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Return a reference to the wrapped codec.
+    #[inline]
+    pub fn get_ref(&self) -> &C {
+        &self.inner
+    }
+
+    /// Return a mutable reference to the wrapped codec.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// Consume the codec, returning the wrapped codec.
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn sequence(&mut self, serial: SerialNumber<T>, frame: BytesMut)
+                -> Result<Option<(SerialNumber<T>, BytesMut)>, SerialNumber<T>> {
+        let expected = self.validator.expected();
+        match self.validator.check(serial) {
+            Check::Deliver => Ok(Some((serial, frame))),
+            Check::Discard => Ok(None),
+            Check::Reject => Err(expected),
+            Check::Hold => {
+                match self.validator.violation() {
+                    Violation::Reorder(limit) if self.pending.len() >= limit
+                        && !self.pending.contains_key(&serial.0) => Err(expected),
+                    _ => {
+                        self.pending.entry(serial.0).or_insert(frame);
+                        Ok(None)
+                    }
+                }
+            }
+        }
+    }
+
+    fn take_pending(&mut self) -> Option<(SerialNumber<T>, BytesMut)> {
+        let expected = self.validator.expected();
+        let frame = self.pending.remove(&expected.0)?;
+        self.validator.check(expected);
+        Some((expected, frame))
+    }
+}
+
+impl<C, T: Wire> Encoder<Bytes> for SequencedCodec<C, T>
+    where C: Encoder<Bytes>, SerialNumber<T>: PartialOrd
+{
+    type Error = C::Error;
+
+    fn encode(&mut self, payload: Bytes, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut frame = BytesMut::with_capacity(T::SIZE + payload.len());
+        T::put(self.generator.next_serial(), &mut frame);
+        frame.extend_from_slice(&payload);
+        self.inner.encode(frame.freeze(), dst)
+    }
+}
+
+impl<C, T: Wire> Decoder for SequencedCodec<C, T>
+    where C: Decoder<Item = BytesMut>, SerialNumber<T>: PartialOrd
+{
+    type Item = (SerialNumber<T>, BytesMut);
+    type Error = DecodeError<C::Error, T>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            if let Some(item) = self.take_pending() {
+                return Ok(Some(item));
+            }
+            let mut frame = match self.inner.decode(src).map_err(DecodeError::Inner)? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            if frame.len() < T::SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          "frame too short for a serial number").into());
+            }
+            let received = T::get(&mut frame);
+            match self.sequence(received, frame) {
+                Ok(Some(item)) => return Ok(Some(item)),
+                Ok(None) => continue,
+                Err(expected) => return Err(DecodeError::Sequence { expected, received }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio_util::codec::LengthDelimitedCodec;
+
+    fn frames(serials: &[u8]) -> BytesMut {
+        let mut codec = LengthDelimitedCodec::new();
+        let mut buf = BytesMut::new();
+        for &serial in serials {
+            codec.encode(Bytes::from(vec![serial, serial]), &mut buf).unwrap();
+        }
+        buf
+    }
+
+    fn decode_all(codec: &mut SequencedCodec<LengthDelimitedCodec, u8>, buf: &mut BytesMut)
+                  -> Result<Vec<u8>, DecodeError<io::Error, u8>> {
+        let mut serials = Vec::new();
+        while let Some((serial, payload)) = codec.decode(buf)? {
+            assert_eq!(&payload[..], &[serial.0]);
+            serials.push(serial.0);
+        }
+        Ok(serials)
+    }
+
+    #[test]
+    fn drop() {
+        let mut codec = SequencedCodec::new(
+            LengthDelimitedCodec::new(), SerialNumber(254u8), Violation::Drop);
+        let mut buf = frames(&[254, 254, 0, 255, 1, 130]);
+        assert_eq!(decode_all(&mut codec, &mut buf).unwrap(), vec![254, 0, 1]);
+        assert_eq!(codec.validator().expected(), SerialNumber(2));
+    }
+
+    #[test]
+    fn reorder() {
+        let mut codec = SequencedCodec::new(
+            LengthDelimitedCodec::new(), SerialNumber(254u8), Violation::Reorder(2));
+        let mut buf = frames(&[255, 0, 0, 254, 253, 2]);
+        assert_eq!(decode_all(&mut codec, &mut buf).unwrap(), vec![254, 255, 0]);
+        assert_eq!(codec.pending(), 1);
+
+        let mut buf = frames(&[3, 4]);
+        match decode_all(&mut codec, &mut buf) {
+            Err(DecodeError::Sequence { expected, received }) => {
+                assert_eq!((expected, received), (SerialNumber(1), SerialNumber(4)));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let mut buf = frames(&[1]);
+        assert_eq!(decode_all(&mut codec, &mut buf).unwrap(), vec![1, 2, 3]);
+        assert_eq!(codec.pending(), 0);
+    }
+
+    #[test]
+    fn error() {
+        let mut codec = SequencedCodec::new(
+            LengthDelimitedCodec::new(), SerialNumber(0u8), Violation::Error);
+        let mut buf = frames(&[0, 2]);
+        let error = decode_all(&mut codec, &mut buf).unwrap_err();
+        assert_eq!(error.to_string(), "frame out of sequence, expected 1 but received 2");
+    }
+
+    #[test]
+    fn short_frame() {
+        let mut codec: SequencedCodec<_, u32> = SequencedCodec::new(
+            LengthDelimitedCodec::new(), SerialNumber(0), Violation::Error);
+        let mut buf = BytesMut::new();
+        LengthDelimitedCodec::new().encode(Bytes::from_static(b"\x00\x00"), &mut buf).unwrap();
+        match codec.decode(&mut buf) {
+            Err(DecodeError::Inner(error)) => assert_eq!(error.kind(), io::ErrorKind::InvalidData),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut codec: SequencedCodec<_, u64> = SequencedCodec::new(
+            LengthDelimitedCodec::new(), SerialNumber(u64::MAX), Violation::Error);
+        let mut buf = BytesMut::new();
+        codec.encode(Bytes::from_static(b"a"), &mut buf).unwrap();
+        codec.encode(Bytes::new(), &mut buf).unwrap();
+        assert_eq!(codec.generator().peek(), SerialNumber(1));
+        assert_eq!(buf.len(), 2 * (4 + 8) + 1);
+
+        let (serial, payload) = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!((serial, &payload[..]), (SerialNumber(u64::MAX), &b"a"[..]));
+        let (serial, payload) = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!((serial, payload.is_empty()), (SerialNumber(0), true));
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+}
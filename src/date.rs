@@ -0,0 +1,206 @@
+//! Date-based `YYYYMMDDnn` serial numbers as commonly used for DNS zones.
+//!
+//! Requires the `time` feature.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use time::{Date, Month};
+#[cfg(feature = "std")]
+use time::OffsetDateTime;
+
+use SerialNumber;
+
+/// An error when converting between dates and date-based serial numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateSerialError {
+    /// The serial number does not encode a valid calendar date.
+    InvalidDate,
+    /// The date or counter can not be represented as a `YYYYMMDDnn` serial
+    /// number.
+    OutOfRange,
+}
+
+impl fmt::Display for DateSerialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DateSerialError::InvalidDate => write!(f, "serial number does not encode a valid date"),
+            DateSerialError::OutOfRange => write!(f, "date or counter out of range"),
+        }
+    }
+}
+
+impl error::Error for DateSerialError {}
+
+/// A calendar date and a counter (`nn`) encoded as a `YYYYMMDDnn` serial
+/// number.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate sna;
+/// # extern crate time;
+/// use sna::SerialNumber;
+/// use sna::date::DateSerial;
+/// use time::{Date, Month};
+///
+/// let date = Date::from_calendar_date(2017, Month::December, 24).unwrap();
+/// let serial = DateSerial::new(date, 3).unwrap();
+///
+/// assert_eq!(serial.to_serial(), SerialNumber(2017122403u32));
+/// assert_eq!(DateSerial::from_serial(SerialNumber(2017122403u32)), Ok(serial));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DateSerial {
+    date: Date,
+    counter: u8,
+}
+
+impl DateSerial {
+    /// Create a date-based serial number from a date and a counter.
+    ///
+    /// Returns an error if the counter exceeds 99 or if the year is
+    /// negative or too large to fit into a 32-bit serial number.
+    pub fn new(date: Date, counter: u8) -> Result<Self, DateSerialError> {
+        let serial = DateSerial { date, counter };
+        if counter > 99 || date.year() < 0 || serial.value() > u64::from(u32::MAX) {
+            Err(DateSerialError::OutOfRange)
+        } else {
+            Ok(serial)
+        }
+    }
+
+    /// Return the date-based serial number for today (UTC) with a counter
+    /// of zero.
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn today() -> Self {
+        DateSerial::new(OffsetDateTime::now_utc().date(), 0)
+            .expect("current date out of range")
+    }
+
+    /// Parse a serial number in `YYYYMMDDnn` format.
+    pub fn from_serial(serial: SerialNumber<u32>) -> Result<Self, DateSerialError> {
+        let value = serial.0;
+        let month = Month::try_from((value / 10_000 % 100) as u8)
+            .map_err(|_| DateSerialError::InvalidDate)?;
+        let date = Date::from_calendar_date(
+            (value / 1_000_000) as i32, month, (value / 100 % 100) as u8)
+            .map_err(|_| DateSerialError::InvalidDate)?;
+        DateSerial::new(date, (value % 100) as u8)
+    }
+
+    /// Return the date.
+    #[inline]
+    pub fn date(&self) -> Date {
+        self.date
+    }
+
+    /// Return the counter (`nn`).
+    #[inline]
+    pub fn counter(&self) -> u8 {
+        self.counter
+    }
+
+    /// Encode as a `YYYYMMDDnn` serial number.
+    #[inline]
+    pub fn to_serial(&self) -> SerialNumber<u32> {
+        SerialNumber(self.value() as u32)
+    }
+
+    fn value(&self) -> u64 {
+        self.date.year() as u64 * 1_000_000
+            + u64::from(self.date.month() as u8) * 10_000
+            + u64::from(self.date.day()) * 100
+            + u64::from(self.counter)
+    }
+}
+
+/// Return the serial number that should follow `current` on `date`.
+///
+/// This is the first serial number of `date` if it follows `current` and
+/// `current + 1` otherwise (e.g. if the zone has already been changed more
+/// than once on that day or the serial number is not in date format).
+///
+/// # Examples
+///
+/// ```
+/// # extern crate sna;
+/// # extern crate time;
+/// use sna::SerialNumber;
+/// use sna::date::next_serial;
+/// use time::{Date, Month};
+///
+/// let date = Date::from_calendar_date(2017, Month::December, 24).unwrap();
+///
+/// assert_eq!(next_serial(SerialNumber(2017122301u32), date), Ok(SerialNumber(2017122400u32)));
+/// assert_eq!(next_serial(SerialNumber(2017122400u32), date), Ok(SerialNumber(2017122401u32)));
+/// ```
+pub fn next_serial(current: SerialNumber<u32>, date: Date)
+    -> Result<SerialNumber<u32>, DateSerialError>
+{
+    let first = DateSerial::new(date, 0)?.to_serial();
+    if first > current {
+        Ok(first)
+    } else {
+        Ok(current + 1)
+    }
+}
+
+/// Return the serial number that should follow `current` today (UTC).
+///
+/// See `next_serial`. Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn next_serial_now(current: SerialNumber<u32>) -> SerialNumber<u32> {
+    next_serial(current, OffsetDateTime::now_utc().date()).expect("current date out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn from_serial() {
+        assert_eq!(DateSerial::from_serial(SerialNumber(2000022999)),
+                   Ok(DateSerial { date: date(2000, Month::February, 29), counter: 99 }));
+        assert_eq!(DateSerial::from_serial(SerialNumber(2001022900)),
+                   Err(DateSerialError::InvalidDate));
+        assert_eq!(DateSerial::from_serial(SerialNumber(2001130100)),
+                   Err(DateSerialError::InvalidDate));
+        assert_eq!(DateSerial::from_serial(SerialNumber(1)), Err(DateSerialError::InvalidDate));
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(DateSerial::new(date(2017, Month::January, 1), 100),
+                   Err(DateSerialError::OutOfRange));
+        assert_eq!(DateSerial::new(date(4295, Month::January, 1), 0),
+                   Err(DateSerialError::OutOfRange));
+        assert!(DateSerial::new(date(4294, Month::January, 1), 0).is_ok());
+        assert_eq!(next_serial(SerialNumber(0), date(4295, Month::January, 1)),
+                   Err(DateSerialError::OutOfRange));
+    }
+
+    #[test]
+    fn next() {
+        let today = date(2018, Month::January, 1);
+        assert_eq!(next_serial(SerialNumber(2017123199), today), Ok(SerialNumber(2018010100)));
+        assert_eq!(next_serial(SerialNumber(2018010199), today), Ok(SerialNumber(2018010200)));
+        assert_eq!(next_serial(SerialNumber(1), today), Ok(SerialNumber(2018010100)));
+        assert_eq!(next_serial(SerialNumber(3000000000), today), Ok(SerialNumber(3000000001)));
+    }
+
+    #[test]
+    fn today() {
+        let today = DateSerial::today();
+        assert_eq!(today.counter(), 0);
+        assert_eq!(DateSerial::from_serial(today.to_serial()), Ok(today));
+        assert!(next_serial_now(today.to_serial()) > today.to_serial());
+    }
+}
@@ -0,0 +1,460 @@
+//! Composite serial numbers of an epoch and a sequence number, as used by
+//! the records of DTLS, see
+//! [chapter 4.1 of RFC 6347](https://tools.ietf.org/html/rfc6347#section-4.1).
+//!
+//! The epoch is a small wrapping counter that is incremented on every
+//! change of keys and the sequence number starts over at zero within each
+//! epoch. As sequence numbers of different epochs can not be related,
+//! `EpochReplayWindow` and `EpochReorderBuffer` keep the state of
+//! `replay::ReplayWindow` and `reorder::ReorderBuffer` per epoch. These
+//! require the `alloc` feature.
+
+#[cfg(feature = "alloc")]
+use std::mem;
+use std::cmp::Ordering;
+
+use SerialNumber;
+#[cfg(feature = "alloc")]
+use reorder::ReorderBuffer;
+#[cfg(feature = "alloc")]
+use replay::{Replay, ReplayWindow};
+use uint::Uint;
+
+/// An epoch and a sequence number within that epoch.
+///
+/// Epochs are compared first and sequence numbers second, both with serial
+/// semantics. Two values can not be ordered if their epochs can not be
+/// ordered or if their epochs are equal and their sequence numbers can not
+/// be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::epoch::EpochSerial;
+///
+/// let last = EpochSerial::<u16, u64>::new(u16::MAX, 1000);
+/// let first = last.next_epoch();
+/// assert_eq!(first, EpochSerial::new(0, 0));
+/// assert!(last < first);
+/// assert!(first < first.next_seq());
+/// assert_eq!(first.next_seq().seq, SerialNumber(1));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub struct EpochSerial<E = u16, S = u64> {
+    /// The epoch.
+    pub epoch: SerialNumber<E>,
+    /// The sequence number within the epoch.
+    pub seq: SerialNumber<S>,
+}
+
+impl<E: Uint, S: Uint> EpochSerial<E, S> {
+    /// Create a composite serial number from an epoch and a sequence
+    /// number.
+    #[inline]
+    pub fn new(epoch: E, seq: S) -> Self {
+        EpochSerial { epoch: SerialNumber(epoch), seq: SerialNumber(seq) }
+    }
+
+    /// Return the first serial number of the following epoch, i.e. with the
+    /// sequence number reset to zero.
+    #[inline]
+    pub fn next_epoch(self) -> Self {
+        EpochSerial { epoch: self.epoch.succ(), seq: SerialNumber(S::ZERO) }
+    }
+
+    /// Return the following serial number within the same epoch.
+    ///
+    /// The sequence number wraps silently, so a new epoch should be started
+    /// before it is exhausted.
+    #[inline]
+    pub fn next_seq(self) -> Self {
+        EpochSerial { epoch: self.epoch, seq: self.seq.succ() }
+    }
+}
+
+impl<E: Uint, S: Uint> PartialOrd for EpochSerial<E, S>
+    where SerialNumber<E>: PartialOrd, SerialNumber<S>: PartialOrd
+{
+    /// Compare the epochs and, if they are equal, the sequence numbers.
+    #[inline]
+    fn partial_cmp(&self, other: &EpochSerial<E, S>) -> Option<Ordering> {
+        match self.epoch.partial_cmp(&other.epoch)? {
+            Ordering::Equal => self.seq.partial_cmp(&other.seq),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// An anti-replay window keyed by `EpochSerial`.
+///
+/// Each epoch has its own `ReplayWindow`. The window of the previous epoch
+/// is retained, so records that were reordered around a change of keys are
+/// still accepted once, until `retire_previous` is called. Serial numbers
+/// of a newer epoch are accepted and replace the windows, those of other
+/// epochs are considered too old.
+///
+/// # Examples
+///
+/// ```
+/// use sna::epoch::{EpochReplayWindow, EpochSerial};
+/// use sna::replay::Replay;
+///
+/// let mut window = EpochReplayWindow::<u16, u64>::new(64);
+/// let first = EpochSerial::new(0, 5);
+/// assert_eq!(window.check_and_update(first), Replay::Accepted);
+/// assert_eq!(window.check_and_update(first.next_epoch()), Replay::Accepted);
+///
+/// // Reordered around the change of keys
+/// assert_eq!(window.check_and_update(EpochSerial::new(0, 4)), Replay::Accepted);
+/// assert_eq!(window.check_and_update(first), Replay::Duplicate);
+///
+/// window.retire_previous();
+/// assert_eq!(window.check(EpochSerial::new(0, 3)), Replay::TooOld);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct EpochReplayWindow<E = u16, S = u64> {
+    size: usize,
+    current: Option<(SerialNumber<E>, ReplayWindow<S>)>,
+    previous: Option<(SerialNumber<E>, ReplayWindow<S>)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Uint, S: Uint> EpochReplayWindow<E, S>
+    where SerialNumber<E>: PartialOrd, SerialNumber<S>: PartialOrd
+{
+    /// Create a window that tracks the `size` most recent sequence numbers
+    /// of each epoch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero or exceeds half of the sequence number
+    /// space.
+    pub fn new(size: usize) -> Self {
+        // Validate the size upfront rather than on the first epoch
+        let window = ReplayWindow::<S>::new(size);
+        EpochReplayWindow { size: window.size(), current: None, previous: None }
+    }
+
+    /// Return the size of the window of each epoch.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return the current epoch, i.e. the newest one accepted so far.
+    #[inline]
+    pub fn epoch(&self) -> Option<SerialNumber<E>> {
+        self.current.as_ref().map(|&(epoch, _)| epoch)
+    }
+
+    /// Return the highest serial number of the current epoch seen so far.
+    pub fn highest(&self) -> Option<EpochSerial<E, S>> {
+        let (epoch, window) = self.current.as_ref()?;
+        window.highest().map(|seq| EpochSerial { epoch: *epoch, seq })
+    }
+
+    /// Check whether `serial` would be accepted without updating the
+    /// window.
+    pub fn check(&self, serial: EpochSerial<E, S>) -> Replay {
+        let mut windows = self.current.iter().chain(self.previous.iter());
+        match windows.find(|&&(epoch, _)| epoch == serial.epoch) {
+            Some((_, window)) => window.check(serial.seq),
+            None if self.is_newer(serial.epoch) => Replay::Accepted,
+            None => Replay::TooOld,
+        }
+    }
+
+    /// Check whether `serial` is accepted and, if so, mark it as seen.
+    ///
+    /// Accepting a serial number of a newer epoch makes the current epoch
+    /// the previous one.
+    ///
+    /// Only call this once the record has been authenticated, as otherwise
+    /// forged records could advance the window.
+    pub fn check_and_update(&mut self, serial: EpochSerial<E, S>) -> Replay {
+        let mut windows = self.current.iter_mut().chain(self.previous.iter_mut());
+        if let Some((_, window)) = windows.find(|&&mut (epoch, _)| epoch == serial.epoch) {
+            return window.check_and_update(serial.seq);
+        }
+        if !self.is_newer(serial.epoch) {
+            return Replay::TooOld;
+        }
+
+        // Reuse the window of the retired epoch
+        let mut window = match self.previous.take() {
+            Some((_, mut window)) => {
+                window.reset();
+                window
+            }
+            None => ReplayWindow::new(self.size),
+        };
+        let replay = window.check_and_update(serial.seq);
+        self.previous = self.current.replace((serial.epoch, window));
+        replay
+    }
+
+    /// Forget the previous epoch, e.g. once the handshake that changed keys
+    /// has completed.
+    #[inline]
+    pub fn retire_previous(&mut self) {
+        self.previous = None;
+    }
+
+    /// Forget all epochs and serial numbers seen so far.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    fn is_newer(&self, epoch: SerialNumber<E>) -> bool {
+        match self.current {
+            Some((current, _)) => epoch > current,
+            None => true,
+        }
+    }
+}
+
+/// Holds items that arrived out of order and releases them in
+/// `EpochSerial` order, starting at the next expected serial number.
+///
+/// Items of the current epoch are ordered by a `ReorderBuffer` with the
+/// given distance. Items of the first newer epoch seen are held in a
+/// second buffer expecting sequence number zero first. Once no items of
+/// the current epoch are held, the newer epoch becomes current as soon as
+/// its next expected item has arrived. Items of any other epoch are
+/// rejected.
+///
+/// # Examples
+///
+/// ```
+/// use sna::epoch::{EpochReorderBuffer, EpochSerial};
+///
+/// let mut buffer = EpochReorderBuffer::<u8, u16, _>::new(EpochSerial::new(255, 7), 8);
+///
+/// buffer.insert(EpochSerial::new(0, 0), "c").unwrap();
+/// buffer.insert(EpochSerial::new(255, 8), "b").unwrap();
+/// assert_eq!(buffer.pop(), None);
+/// buffer.insert(EpochSerial::new(255, 7), "a").unwrap();
+/// assert_eq!(buffer.ready().map(|(_, value)| value).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+/// assert_eq!(buffer.next_expected(), EpochSerial::new(0, 1));
+///
+/// // The previous epoch is over
+/// assert_eq!(buffer.insert(EpochSerial::new(255, 9), "d"), Err("d"));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct EpochReorderBuffer<E, S, V> {
+    distance: usize,
+    epoch: SerialNumber<E>,
+    current: ReorderBuffer<S, V>,
+    pending: Option<(SerialNumber<E>, ReorderBuffer<S, V>)>,
+    lost: u64,
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Uint, S: Uint, V> EpochReorderBuffer<E, S, V>
+    where SerialNumber<E>: PartialOrd, SerialNumber<S>: PartialOrd
+{
+    /// Create a buffer expecting `next` first that holds items up to
+    /// `distance - 1` ahead of the next expected sequence number of each
+    /// epoch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `distance` is zero or exceeds half of the sequence number
+    /// space.
+    pub fn new(next: EpochSerial<E, S>, distance: usize) -> Self {
+        EpochReorderBuffer {
+            distance,
+            epoch: next.epoch,
+            current: ReorderBuffer::new(next.seq, distance),
+            pending: None,
+            lost: 0,
+        }
+    }
+
+    /// Return the next expected serial number.
+    #[inline]
+    pub fn next_expected(&self) -> EpochSerial<E, S> {
+        EpochSerial { epoch: self.epoch, seq: self.current.next_expected() }
+    }
+
+    /// Return the amount of items held back or ready in all epochs.
+    pub fn len(&self) -> usize {
+        self.current.len() + self.pending.as_ref().map_or(0, |(_, buffer)| buffer.len())
+    }
+
+    /// Return whether no items are held back or ready in any epoch.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the number of sequence numbers given up on so far.
+    ///
+    /// Sequence numbers missing at the end of an epoch are not known and
+    /// therefore not counted.
+    pub fn lost(&self) -> u64 {
+        self.lost + self.current.lost()
+            + self.pending.as_ref().map_or(0, |(_, buffer)| buffer.lost())
+    }
+
+    /// Buffer `value` with serial number `serial`.
+    ///
+    /// Returns the value back if it is rejected by the buffer of its epoch
+    /// (see `ReorderBuffer::insert`) or if its epoch is neither the current
+    /// one nor the first newer one seen.
+    pub fn insert(&mut self, serial: EpochSerial<E, S>, value: V) -> Result<(), V> {
+        match serial.epoch.partial_cmp(&self.epoch) {
+            Some(Ordering::Equal) => return self.current.insert(serial.seq, value),
+            Some(Ordering::Greater) => {}
+            _ => return Err(value),
+        }
+        match self.pending {
+            Some((epoch, ref mut buffer)) if epoch == serial.epoch => buffer.insert(serial.seq, value),
+            Some(_) => Err(value),
+            None => {
+                let mut buffer = ReorderBuffer::new(SerialNumber(S::ZERO), self.distance);
+                let result = buffer.insert(serial.seq, value);
+                self.pending = Some((serial.epoch, buffer));
+                result
+            }
+        }
+    }
+
+    /// Remove and return the next item in serial number order, if it is
+    /// ready.
+    pub fn pop(&mut self) -> Option<(EpochSerial<E, S>, V)> {
+        if let Some((seq, value)) = self.current.pop() {
+            return Some((EpochSerial { epoch: self.epoch, seq }, value));
+        }
+        if !self.current.is_empty() {
+            return None;
+        }
+        let (seq, value) = self.pending.as_mut()?.1.pop()?;
+        self.advance_epoch();
+        Some((EpochSerial { epoch: self.epoch, seq }, value))
+    }
+
+    /// Return an iterator that removes and returns the ready items.
+    #[inline]
+    pub fn ready(&mut self) -> Ready<'_, E, S, V> {
+        Ready { buffer: self }
+    }
+
+    /// Give up on the missing sequence numbers up to the oldest buffered
+    /// item, so it becomes ready.
+    ///
+    /// If no items of the current epoch are held, this gives up on the rest
+    /// of the current epoch in favour of the newer one.
+    ///
+    /// Returns the number of sequence numbers given up on.
+    pub fn flush_gap(&mut self) -> u64 {
+        if self.current.is_empty() && self.pending.is_some() {
+            self.advance_epoch();
+        }
+        self.current.flush_gap()
+    }
+
+    /// Make the pending epoch the current one.
+    fn advance_epoch(&mut self) {
+        if let Some((epoch, buffer)) = self.pending.take() {
+            self.lost += mem::replace(&mut self.current, buffer).lost();
+            self.epoch = epoch;
+        }
+    }
+}
+
+/// An iterator over the ready items of an `EpochReorderBuffer`.
+///
+/// Created by `EpochReorderBuffer::ready`.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Ready<'a, E: 'a, S: 'a, V: 'a> {
+    buffer: &'a mut EpochReorderBuffer<E, S, V>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, E: Uint, S: Uint, V> Iterator for Ready<'a, E, S, V>
+    where SerialNumber<E>: PartialOrd, SerialNumber<S>: PartialOrd
+{
+    type Item = (EpochSerial<E, S>, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(EpochSerial<E, S>, V)> {
+        self.buffer.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmp() {
+        let serial = EpochSerial::<u8, u8>::new(255, 200);
+        assert!(serial < EpochSerial::new(0, 0));
+        assert!(serial > EpochSerial::new(254, 255));
+        assert!(serial < serial.next_seq());
+        assert!(serial.next_seq() < serial.next_epoch());
+        assert_eq!(EpochSerial::new(255u8, 255u8).next_seq(), EpochSerial::new(255, 0));
+        assert_eq!(serial.partial_cmp(&EpochSerial::new(127, 0)), None);
+        assert_eq!(serial.partial_cmp(&EpochSerial::new(255, 72)), None);
+        assert_eq!(serial.partial_cmp(&serial), Some(Ordering::Equal));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn replay_window() {
+        let mut window = EpochReplayWindow::<u8, u32>::new(32);
+        assert_eq!(window.check(EpochSerial::new(100, 0)), Replay::Accepted);
+        assert_eq!(window.check_and_update(EpochSerial::new(254, 40)), Replay::Accepted);
+        assert_eq!(window.check_and_update(EpochSerial::new(254, 8)), Replay::TooOld);
+        assert_eq!(window.check_and_update(EpochSerial::new(1, 0)), Replay::Accepted);
+        assert_eq!(window.epoch(), Some(SerialNumber(1)));
+        assert_eq!(window.check_and_update(EpochSerial::new(254, 39)), Replay::Accepted);
+        assert_eq!(window.check_and_update(EpochSerial::new(0, 0)), Replay::TooOld);
+
+        // Retires epoch 254 and reuses its window
+        assert_eq!(window.check_and_update(EpochSerial::new(2, 3)), Replay::Accepted);
+        assert_eq!(window.check(EpochSerial::new(254, 38)), Replay::TooOld);
+        assert_eq!(window.check(EpochSerial::new(1, 0)), Replay::Duplicate);
+        assert_eq!(window.check(EpochSerial::new(2, 0)), Replay::Accepted);
+        assert_eq!(window.highest(), Some(EpochSerial::new(2, 3)));
+
+        window.reset();
+        assert_eq!(window.highest(), None);
+        assert_eq!(window.check(EpochSerial::new(0, 0)), Replay::Accepted);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn reorder_buffer() {
+        let mut buffer = EpochReorderBuffer::<u8, u16, u16>::new(EpochSerial::new(3, 10), 4);
+        buffer.insert(EpochSerial::new(3, 12), 12).unwrap();
+        buffer.insert(EpochSerial::new(4, 1), 1).unwrap();
+        assert_eq!(buffer.insert(EpochSerial::new(5, 0), 0), Err(0));
+        assert_eq!(buffer.insert(EpochSerial::new(2, 0), 0), Err(0));
+        assert_eq!(buffer.len(), 2);
+
+        // Gives up on 3:10, 3:11 and the rest of epoch 3, then on 4:0
+        assert_eq!(buffer.flush_gap(), 2);
+        assert_eq!(buffer.pop(), Some((EpochSerial::new(3, 12), 12)));
+        assert_eq!(buffer.pop(), None);
+        assert_eq!(buffer.flush_gap(), 1);
+        assert_eq!(buffer.pop(), Some((EpochSerial::new(4, 1), 1)));
+        assert_eq!(buffer.lost(), 3);
+        assert!(buffer.is_empty());
+
+        // Jumping ahead within the newer epoch
+        buffer.insert(EpochSerial::new(5, 9), 9).unwrap();
+        assert_eq!(buffer.pop(), None);
+        assert_eq!(buffer.flush_gap(), 3);
+        assert_eq!(buffer.pop(), Some((EpochSerial::new(5, 9), 9)));
+        assert_eq!(buffer.next_expected(), EpochSerial::new(5, 10));
+        assert_eq!(buffer.lost(), 3 + 6 + 3);
+    }
+}
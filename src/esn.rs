@@ -0,0 +1,208 @@
+//! Extended sequence numbers (ESN) of IPsec ESP, see
+//! [chapter 2.2.1 of RFC 4303](https://tools.ietf.org/html/rfc4303#section-2.2.1).
+//!
+//! Both sides maintain a 64-bit sequence number, but only its low-order 32
+//! bits are transmitted. The receiver infers the high-order 32 bits from
+//! its anti-replay window as described in
+//! [appendix A of RFC 4303](https://tools.ietf.org/html/rfc4303#appendix-A)
+//! and runs the anti-replay check over the full sequence number. The
+//! high-order bits are not transmitted, but they are appended to the
+//! packet for the ICV computation (see `icv_suffix`).
+//!
+//! Requires the `alloc` feature.
+
+use replay::{Replay, ReplayWindow};
+
+/// Infer the full sequence number of the low-order 32 bits `seq_low`,
+/// given the highest full sequence number received so far and the size of
+/// the anti-replay window.
+///
+/// This implements the algorithm of
+/// [appendix A2.2 of RFC 4303](https://tools.ietf.org/html/rfc4303#appendix-A2.2):
+/// `seq_low` is placed in the subspace of `highest` if it is within the
+/// window or ahead of it within that subspace, in the previous subspace if
+/// the window spans two subspaces and `seq_low` is within the lower one,
+/// and in the next subspace otherwise.
+///
+/// # Panics
+///
+/// Panics if `size` is zero or exceeds `2^31`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::esn;
+///
+/// // Ahead of the window, after the low-order bits wrapped
+/// assert_eq!(esn::infer((1 << 32) + 0xffff_fff0, 64, 3), (2 << 32) + 3);
+///
+/// // Within the window spanning two subspaces
+/// assert_eq!(esn::infer((2 << 32) + 10, 64, 0xffff_fff0), (1 << 32) + 0xffff_fff0);
+/// ```
+pub fn infer(highest: u64, size: usize, seq_low: u32) -> u64 {
+    assert!(size > 0 && size as u64 <= 1 << 31, "window size must be in 1..=2^31");
+    let (high, low) = ((highest >> 32) as u32, highest as u32);
+    let size = size as u32;
+    let bottom = low.wrapping_sub(size).wrapping_add(1);
+    let seq_high = if low >= size - 1 {
+        // Case A: the window is within one subspace
+        if seq_low >= bottom { high } else { high.wrapping_add(1) }
+    } else if seq_low >= bottom {
+        // Case B: the window spans two subspaces and `seq_low` is within
+        // the lower one, which does not exist for the first subspace
+        high.checked_sub(1).unwrap_or(high)
+    } else {
+        high
+    };
+    u64::from(seq_high) << 32 | u64::from(seq_low)
+}
+
+/// Return the bytes to append to the packet (after the ESP trailer) for the
+/// ICV computation, i.e. the high-order 32 bits of `seq` in network byte
+/// order.
+///
+/// # Examples
+///
+/// ```
+/// use sna::esn;
+///
+/// assert_eq!(esn::icv_suffix(0x0102_0304_0506_0708), [1, 2, 3, 4]);
+/// ```
+#[inline]
+pub fn icv_suffix(seq: u64) -> [u8; 4] {
+    ((seq >> 32) as u32).to_be_bytes()
+}
+
+/// An anti-replay window over 64-bit extended sequence numbers that
+/// receives their low-order 32 bits.
+///
+/// Use `infer` to obtain the full sequence number, verify the ICV with
+/// its high-order bits (see `icv_suffix`) and only then call
+/// `check_and_update`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::esn::EsnWindow;
+/// use sna::replay::Replay;
+///
+/// let mut window = EsnWindow::new(64);
+/// for seq_low in (u32::MAX - 2)..=u32::MAX {
+///     let seq = window.infer(seq_low);
+///     assert_eq!(window.check_and_update(seq), Replay::Accepted);
+/// }
+///
+/// // The low-order bits wrapped
+/// let seq = window.infer(1);
+/// assert_eq!(seq, (1 << 32) + 1);
+/// assert_eq!(window.check_and_update(seq), Replay::Accepted);
+///
+/// // A late packet from before the wrap
+/// let seq = window.infer(u32::MAX - 1);
+/// assert_eq!(seq, u64::from(u32::MAX - 1));
+/// assert_eq!(window.check(seq), Replay::Duplicate);
+/// ```
+#[derive(Clone, Debug)]
+pub struct EsnWindow {
+    window: ReplayWindow<u64>,
+}
+
+impl EsnWindow {
+    /// Create a window that tracks the `size` most recent sequence numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero or exceeds `2^31`.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0 && size as u64 <= 1 << 31, "window size must be in 1..=2^31");
+        EsnWindow { window: ReplayWindow::new(size) }
+    }
+
+    /// Return the size of the window.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.window.size()
+    }
+
+    /// Return the highest sequence number received so far.
+    #[inline]
+    pub fn highest(&self) -> Option<u64> {
+        self.window.highest().map(|highest| highest.0)
+    }
+
+    /// Infer the full sequence number of the low-order 32 bits `seq_low`
+    /// (see `esn::infer`).
+    ///
+    /// Before any sequence number has been received, the high-order bits
+    /// are zero.
+    #[inline]
+    pub fn infer(&self, seq_low: u32) -> u64 {
+        infer(self.highest().unwrap_or(0), self.size(), seq_low)
+    }
+
+    /// Check whether `seq` would be accepted without updating the window.
+    #[inline]
+    pub fn check(&self, seq: u64) -> Replay {
+        self.window.check(seq.into())
+    }
+
+    /// Check whether `seq` is accepted and, if so, mark it as received.
+    ///
+    /// Only call this once the ICV has been verified.
+    #[inline]
+    pub fn check_and_update(&mut self, seq: u64) -> Replay {
+        self.window.check_and_update(seq.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_a() {
+        // The window covers `0x1_0000_0001..=0x1_0000_0040`
+        let highest = (1 << 32) + 64;
+        assert_eq!(infer(highest, 64, 1), (1 << 32) + 1);
+        assert_eq!(infer(highest, 64, 64), highest);
+        assert_eq!(infer(highest, 64, 1000), (1 << 32) + 1000);
+        assert_eq!(infer(highest, 64, u32::MAX), (1 << 32) + u64::from(u32::MAX));
+        assert_eq!(infer(highest, 64, 0), 2 << 32);
+
+        // `Tl = W - 1`, the window exactly reaches the start of the subspace
+        assert_eq!(infer((1 << 32) + 63, 64, 0), 1 << 32);
+    }
+
+    #[test]
+    fn case_b() {
+        // The window covers `0xffff_ffc4..=0x1_0000_0003`
+        let highest = (1 << 32) + 3;
+        assert_eq!(infer(highest, 64, 0xffff_ffc4), 0xffff_ffc4);
+        assert_eq!(infer(highest, 64, 0xffff_ffc3), (1 << 32) + 0xffff_ffc3);
+        assert_eq!(infer(highest, 64, 0), 1 << 32);
+        assert_eq!(infer(highest, 64, 3), highest);
+        assert_eq!(infer(highest, 64, 1000), (1 << 32) + 1000);
+
+        // No subspace precedes the first one
+        assert_eq!(infer(3, 64, u32::MAX), u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn replay() {
+        let mut window = EsnWindow::new(32);
+        assert_eq!(window.infer(5), 5);
+        let seq = (3 << 32) + 10;
+        assert_eq!(window.check_and_update(seq), Replay::Accepted);
+        assert_eq!(window.infer(10), seq);
+        assert_eq!(window.check(window.infer(10)), Replay::Duplicate);
+
+        // Within the window, in the previous subspace
+        assert_eq!(window.infer(0xffff_fff0), (2 << 32) + 0xffff_fff0);
+        assert_eq!(window.check_and_update((2 << 32) + 0xffff_fff0), Replay::Accepted);
+
+        // Precedes the window, so it is placed in the current subspace
+        assert_eq!(window.infer(0xffff_ffe0), (3 << 32) + 0xffff_ffe0);
+        assert_eq!(window.check((2 << 32) + 0xffff_ffe0), Replay::TooOld);
+        assert_eq!(icv_suffix(seq), [0, 0, 0, 3]);
+    }
+}
@@ -0,0 +1,362 @@
+//! Extension of narrow serial numbers to wide monotonic counters.
+//!
+//! Protocols such as RTP transmit a 16-bit sequence number but statistics
+//! and SRTP need the extended sequence number, i.e. the number of times the
+//! sequence number wrapped (the *rollover counter*) times `2^16` plus the
+//! sequence number, see
+//! [appendix A.1 of RFC 3550](https://tools.ietf.org/html/rfc3550#appendix-A.1).
+//!
+//! `WrapCounter` counts the wraps of a serial number that only advances,
+//! e.g. for exporting long-lived counters as metrics.
+//!
+//! `SerialNumber::widen` reconstructs a single wide value relative to a
+//! nearby reference instead, as needed for QUIC packet numbers, SRTP
+//! indices and DTLS record sequence numbers.
+
+use std::cmp::Ordering;
+
+use SerialNumber;
+use uint::Uint;
+
+/// Reconstructs the extended (64-bit) value of serial numbers of type `T`.
+///
+/// Each serial number is placed relative to the highest one seen so far,
+/// so serial numbers that are reordered across the wrap point are attributed
+/// to the previous cycle. A serial number that is exactly half of the serial
+/// number space away from the highest one is considered newer.
+///
+/// `T` should be narrower than 64 bits, as the extended value wraps
+/// otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::extended::Extender;
+///
+/// let mut extender = Extender::new();
+///
+/// assert_eq!(extender.extend(SerialNumber(65534u16)), Some(65534));
+/// assert_eq!(extender.extend(SerialNumber(1u16)), Some(65537));
+///
+/// // Reordered from before the wrap
+/// assert_eq!(extender.extend(SerialNumber(65535u16)), Some(65535));
+/// assert_eq!(extender.highest(), Some(65537));
+/// assert_eq!(extender.cycles(), 1);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Extender<T> {
+    highest: Option<(u64, SerialNumber<T>)>,
+}
+
+impl<T: Uint> Extender<T> where SerialNumber<T>: PartialOrd {
+    /// Create an extender that starts at cycle zero with the first serial
+    /// number passed to it.
+    #[inline]
+    pub fn new() -> Self {
+        Extender { highest: None }
+    }
+
+    /// Create an extender whose first serial number belongs to cycle
+    /// `cycles`, e.g. to resume from a stored rollover counter.
+    #[inline]
+    pub fn with_cycles(cycles: u64, first: SerialNumber<T>) -> Self {
+        Extender { highest: Some((Self::join(cycles, first), first)) }
+    }
+
+    /// Return the highest extended value seen so far.
+    #[inline]
+    pub fn highest(&self) -> Option<u64> {
+        self.highest.map(|(extended, _)| extended)
+    }
+
+    /// Return the number of times the serial number wrapped so far (e.g.
+    /// the rollover counter of SRTP).
+    #[inline]
+    pub fn cycles(&self) -> u64 {
+        self.highest.map_or(0, |(extended, _)| Self::cycles_of(extended))
+    }
+
+    /// Return the extended value of `serial` without updating the highest
+    /// value seen so far.
+    ///
+    /// Returns `None` if `serial` would precede cycle zero.
+    pub fn peek(&self, serial: SerialNumber<T>) -> Option<u64> {
+        let (extended, highest) = match self.highest {
+            Some(highest) => highest,
+            None => return Some(serial.0.to_u128() as u64),
+        };
+        let cycles = Self::cycles_of(extended);
+        match serial.partial_cmp(&highest) {
+            Some(Ordering::Less) if serial.0 > highest.0 => {
+                cycles.checked_sub(1).map(|cycles| Self::join(cycles, serial))
+            }
+            Some(Ordering::Greater) | None if serial.0 < highest.0 => {
+                Some(Self::join(cycles.wrapping_add(1), serial))
+            }
+            _ => Some(Self::join(cycles, serial)),
+        }
+    }
+
+    /// Return the extended value of `serial` and update the highest value
+    /// seen so far if it is newer.
+    ///
+    /// Returns `None` and ignores `serial` if it would precede cycle zero.
+    pub fn extend(&mut self, serial: SerialNumber<T>) -> Option<u64> {
+        let extended = self.peek(serial)?;
+        let newer = match self.highest {
+            Some((_, highest)) => serial.partial_cmp(&highest) != Some(Ordering::Less),
+            None => true,
+        };
+        if newer {
+            self.highest = Some((extended, serial));
+        }
+        Some(extended)
+    }
+
+    /// Forget the highest serial number seen so far, e.g. when the sender
+    /// restarted its sequence, so the next serial number starts at cycle
+    /// zero again.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.highest = None;
+    }
+
+    fn cycles_of(extended: u64) -> u64 {
+        extended.checked_shr(T::BITS).unwrap_or(0)
+    }
+
+    fn join(cycles: u64, serial: SerialNumber<T>) -> u64 {
+        cycles.checked_shl(T::BITS).unwrap_or(0) | serial.0.to_u128() as u64
+    }
+}
+
+/// The outcome of updating a `WrapCounter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapUpdate {
+    /// The serial number advanced without wrapping (or did not change).
+    Advanced,
+    /// The serial number advanced and wrapped, which makes this the given
+    /// number of wraps since construction.
+    Wrapped(u64),
+    /// The serial number precedes the current one and has been ignored.
+    Stale,
+}
+
+/// A serial number that only advances and counts how often it wrapped.
+///
+/// A serial number that is exactly half of the serial number space away
+/// from the current one is considered to follow it.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::extended::{WrapCounter, WrapUpdate};
+///
+/// let mut counter = WrapCounter::new(SerialNumber(65000u16));
+///
+/// assert_eq!(counter.update(SerialNumber(65500)), WrapUpdate::Advanced);
+/// assert_eq!(counter.update(SerialNumber(200)), WrapUpdate::Wrapped(1));
+/// assert_eq!(counter.update(SerialNumber(65500)), WrapUpdate::Stale);
+///
+/// // Monotonic value to be exported as a metric
+/// assert_eq!(counter.projection(), 65536 + 200);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WrapCounter<T> {
+    current: SerialNumber<T>,
+    wraps: u64,
+}
+
+impl<T: Uint> WrapCounter<T> where SerialNumber<T>: PartialOrd {
+    /// Create a counter starting at `initial` without any wraps.
+    #[inline]
+    pub fn new(initial: SerialNumber<T>) -> Self {
+        WrapCounter { current: initial, wraps: 0 }
+    }
+
+    /// Return the current serial number.
+    #[inline]
+    pub fn get(&self) -> SerialNumber<T> {
+        self.current
+    }
+
+    /// Return the number of wraps since construction.
+    #[inline]
+    pub fn wraps(&self) -> u64 {
+        self.wraps
+    }
+
+    /// Return the number of wraps times `2^SERIAL_BITS` plus the current
+    /// serial number, which increases monotonically.
+    ///
+    /// This saturates if the number of wraps does not fit, which can only
+    /// happen for `u128` and `u64` serial numbers.
+    #[inline]
+    pub fn projection(&self) -> u128 {
+        u128::from(self.wraps).checked_shl(T::BITS)
+            .filter(|&shifted| shifted >> T::BITS == u128::from(self.wraps))
+            .map_or(u128::MAX, |shifted| shifted | self.current.0.to_u128())
+    }
+
+    /// Advance to `serial` if it follows the current serial number.
+    pub fn update(&mut self, serial: SerialNumber<T>) -> WrapUpdate {
+        if serial.partial_cmp(&self.current) == Some(Ordering::Less) {
+            return WrapUpdate::Stale;
+        }
+        let wrapped = serial.0 < self.current.0;
+        self.current = serial;
+        if wrapped {
+            self.wraps += 1;
+            WrapUpdate::Wrapped(self.wraps)
+        } else {
+            WrapUpdate::Advanced
+        }
+    }
+
+    /// Advance by `n`, which wraps if it exceeds the largest representable
+    /// number of this type.
+    ///
+    /// `n` must be less than half of the serial number space, as the
+    /// serial number is considered stale otherwise (see `update`).
+    #[inline]
+    pub fn advance(&mut self, n: T) -> WrapUpdate {
+        self.update(SerialNumber(self.current.0.wrapping_add(n)))
+    }
+}
+
+impl<T: Uint> SerialNumber<T> {
+    /// Reconstruct the value of a wider counter of type `W` whose least
+    /// significant bits are `self`, choosing the candidate closest to
+    /// `reference`.
+    ///
+    /// A candidate exactly half of the serial number space of `T` away from
+    /// `reference` is considered to follow it. The result wraps modulo the
+    /// largest representable number of `W`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `W` is narrower than `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::SerialNumber;
+    ///
+    /// let reference = 0x0001_fffeu32;
+    /// assert_eq!(SerialNumber(3u16).widen(reference), 0x0002_0003);
+    /// assert_eq!(SerialNumber(0xfff0u16).widen(reference), 0x0001_fff0);
+    /// assert_eq!(SerialNumber::<u16>::narrow(0x0002_0003u32), SerialNumber(3));
+    /// ```
+    pub fn widen<W: Uint>(self, reference: W) -> W {
+        assert!(W::BITS >= T::BITS, "W must not be narrower than T");
+        let truncated = T::from_u128(reference.to_u128());
+        let ahead = self.0.wrapping_sub(truncated);
+        if ahead <= T::HALF {
+            reference.wrapping_add(W::from_u128(ahead.to_u128()))
+        } else {
+            reference.wrapping_sub(W::from_u128(truncated.wrapping_sub(self.0).to_u128()))
+        }
+    }
+
+    /// Truncate the value of a wider counter of type `W` to its least
+    /// significant bits.
+    #[inline]
+    pub fn narrow<W: Uint>(wide: W) -> Self {
+        SerialNumber(T::from_u128(wide.to_u128()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping() {
+        let mut extender = Extender::new();
+        let mut expected = 250;
+        for _ in 0..1000 {
+            assert_eq!(extender.extend(SerialNumber(expected as u8)), Some(expected));
+            expected += 3;
+        }
+        assert_eq!(extender.cycles(), (expected - 3) >> 8);
+    }
+
+    #[test]
+    fn reordering() {
+        let mut extender = Extender::with_cycles(2, SerialNumber(65530u16));
+        assert_eq!(extender.extend(SerialNumber(3)), Some(3 << 16 | 3));
+        assert_eq!(extender.extend(SerialNumber(65533)), Some(2 << 16 | 65533));
+        assert_eq!(extender.extend(SerialNumber(2)), Some(3 << 16 | 2));
+        assert_eq!(extender.highest(), Some(3 << 16 | 3));
+        assert_eq!(extender.peek(SerialNumber(32771)), Some(3 << 16 | 32771));
+    }
+
+    #[test]
+    fn before_first_cycle() {
+        let mut extender = Extender::new();
+        assert_eq!(extender.extend(SerialNumber(1u32)), Some(1));
+        assert_eq!(extender.extend(SerialNumber(u32::MAX)), None);
+        assert_eq!(extender.highest(), Some(1));
+    }
+
+    #[test]
+    fn wrap_counter() {
+        let mut counter = WrapCounter::new(SerialNumber(0u8));
+        assert_eq!(counter.update(SerialNumber(0)), WrapUpdate::Advanced);
+        for expected in 1..=10 {
+            assert_eq!(counter.advance(127), WrapUpdate::Advanced);
+            assert_eq!(counter.advance(127), WrapUpdate::Advanced);
+            assert_eq!(counter.advance(2), WrapUpdate::Wrapped(expected));
+        }
+        assert_eq!(counter.wraps(), 10);
+        assert_eq!(counter.advance(100), WrapUpdate::Advanced);
+        assert_eq!(counter.projection(), 10 * 256 + 100);
+
+        // Exactly half of the serial number space is considered to follow
+        assert_eq!(counter.update(SerialNumber(228)), WrapUpdate::Advanced);
+        assert_eq!(counter.update(SerialNumber(100)), WrapUpdate::Wrapped(11));
+        assert_eq!(counter.update(SerialNumber(99)), WrapUpdate::Stale);
+        assert_eq!(counter.get(), SerialNumber(100));
+    }
+
+    #[test]
+    fn wrap_counter_saturates() {
+        let mut counter = WrapCounter::new(SerialNumber(u128::MAX));
+        assert_eq!(counter.advance(1), WrapUpdate::Wrapped(1));
+        assert_eq!(counter.projection(), u128::MAX);
+
+        let mut counter = WrapCounter::new(SerialNumber(u64::MAX));
+        counter.advance(1);
+        assert_eq!(counter.projection(), 1 << 64);
+    }
+
+    #[test]
+    fn widen() {
+        for &reference in &[0u64, 100, 65535, 65536, 1 << 40, u64::MAX - 10] {
+            for offset in -32767i64..=32768 {
+                let wide = reference.wrapping_add(offset as u64);
+                let serial = SerialNumber::<u16>::narrow(wide);
+                assert_eq!(serial.widen(reference), wide, "reference {} offset {}", reference, offset);
+            }
+        }
+        assert_eq!(SerialNumber(200u8).widen(5u8), 200);
+        assert_eq!(SerialNumber(u128::MAX).widen(0u128), u128::MAX);
+        assert_eq!(SerialNumber(1u64 << 63).widen(0u128), 1 << 63);
+    }
+
+    #[test]
+    #[should_panic]
+    fn widen_narrower() {
+        SerialNumber(1u32).widen(0u16);
+    }
+
+    #[test]
+    fn reset() {
+        let mut extender = Extender::with_cycles(7, SerialNumber(100u16));
+        extender.reset();
+        assert_eq!(extender.extend(SerialNumber(40000)), Some(40000));
+        assert_eq!(extender.cycles(), 0);
+    }
+}
@@ -0,0 +1,202 @@
+//! A C interface to serial number comparison, addition and replay windows,
+//! so C code can share the RFC 1982 semantics of this crate.
+//!
+//! The declarations are in `include/sna.h`, which can be regenerated with
+//! `cbindgen --config cbindgen.toml --output include/sna.h`. To link from
+//! C, build a `staticlib` or `cdylib` crate that depends on this crate with
+//! the `ffi` feature enabled.
+//!
+//! Requires the `ffi` feature.
+
+use std::cmp::Ordering;
+use std::ptr;
+
+use alloc::boxed::Box;
+
+use SerialNumber;
+use replay::{Replay, ReplayWindow};
+
+/// The ordering between two serial numbers.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnaOrdering {
+    /// The first serial number precedes the second one.
+    Less = -1,
+    /// Both serial numbers are equal.
+    Equal = 0,
+    /// The first serial number follows the second one.
+    Greater = 1,
+    /// Both serial numbers are exactly half of the serial number space
+    /// apart, for which RFC 1982 leaves the ordering undefined.
+    Undefined = 2,
+}
+
+impl From<Option<Ordering>> for SnaOrdering {
+    #[inline]
+    fn from(ordering: Option<Ordering>) -> Self {
+        match ordering {
+            Some(Ordering::Less) => SnaOrdering::Less,
+            Some(Ordering::Equal) => SnaOrdering::Equal,
+            Some(Ordering::Greater) => SnaOrdering::Greater,
+            None => SnaOrdering::Undefined,
+        }
+    }
+}
+
+/// The outcome of checking a serial number against a replay window.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnaReplay {
+    /// The serial number has not been seen, yet.
+    Accepted = 0,
+    /// The serial number has already been seen.
+    Duplicate = 1,
+    /// The serial number is older than the window.
+    TooOld = 2,
+    /// The window pointer is null.
+    Invalid = 3,
+}
+
+impl From<Replay> for SnaReplay {
+    #[inline]
+    fn from(replay: Replay) -> Self {
+        match replay {
+            Replay::Accepted => SnaReplay::Accepted,
+            Replay::Duplicate => SnaReplay::Duplicate,
+            Replay::TooOld => SnaReplay::TooOld,
+        }
+    }
+}
+
+/// An opaque replay window of 32-bit serial numbers.
+pub struct SnaReplayWindow {
+    window: ReplayWindow<u32>,
+}
+
+/// Return the ordering between the 32-bit serial numbers `a` and `b`.
+#[no_mangle]
+pub extern "C" fn sna_cmp_u32(a: u32, b: u32) -> SnaOrdering {
+    SerialNumber(a).partial_cmp(&SerialNumber(b)).into()
+}
+
+/// Add `addend` to the 32-bit serial number `serial` and store the sum in
+/// `result`.
+///
+/// Returns `false` without touching `result` if `addend` exceeds the range
+/// for which RFC 1982 defines addition (`2^31 - 1`) or `result` is null.
+///
+/// # Safety
+///
+/// `result` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sna_add_u32(serial: u32, addend: u32, result: *mut u32) -> bool {
+    match SerialNumber(serial).checked_add(addend) {
+        Ok(sum) if !result.is_null() => {
+            *result = sum.0;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Create a replay window of 32-bit serial numbers that tracks the `size`
+/// most recent serial numbers (see `ReplayWindow::new`).
+///
+/// Returns null if `size` is zero or exceeds half of the serial number
+/// space. The window must be freed with `sna_replay_window_free`.
+#[no_mangle]
+pub extern "C" fn sna_replay_window_new(size: usize) -> *mut SnaReplayWindow {
+    if size == 0 || size as u64 > 1 << 31 {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(SnaReplayWindow { window: ReplayWindow::new(size) }))
+}
+
+/// Check whether `serial` would be accepted by `window` without updating
+/// it.
+///
+/// # Safety
+///
+/// `window` must be null or returned by `sna_replay_window_new` and not
+/// freed, yet.
+#[no_mangle]
+pub unsafe extern "C" fn sna_replay_window_check(window: *const SnaReplayWindow, serial: u32)
+                                                 -> SnaReplay {
+    match window.as_ref() {
+        Some(window) => window.window.check(SerialNumber(serial)).into(),
+        None => SnaReplay::Invalid,
+    }
+}
+
+/// Check whether `serial` is accepted by `window` and, if so, mark it as
+/// seen.
+///
+/// Only call this once the packet has been authenticated.
+///
+/// # Safety
+///
+/// `window` must be null or returned by `sna_replay_window_new` and not
+/// freed, yet. It must not be accessed concurrently.
+#[no_mangle]
+pub unsafe extern "C" fn sna_replay_window_check_and_update(window: *mut SnaReplayWindow,
+                                                            serial: u32) -> SnaReplay {
+    match window.as_mut() {
+        Some(window) => window.window.check_and_update(SerialNumber(serial)).into(),
+        None => SnaReplay::Invalid,
+    }
+}
+
+/// Free a replay window. Does nothing if `window` is null.
+///
+/// # Safety
+///
+/// `window` must be null or returned by `sna_replay_window_new` and not
+/// freed, yet.
+#[no_mangle]
+pub unsafe extern "C" fn sna_replay_window_free(window: *mut SnaReplayWindow) {
+    if !window.is_null() {
+        drop(Box::from_raw(window));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmp() {
+        assert_eq!(sna_cmp_u32(0, u32::MAX), SnaOrdering::Greater);
+        assert_eq!(sna_cmp_u32(u32::MAX, 0), SnaOrdering::Less);
+        assert_eq!(sna_cmp_u32(7, 7), SnaOrdering::Equal);
+        assert_eq!(sna_cmp_u32(0, 1 << 31), SnaOrdering::Undefined);
+    }
+
+    #[test]
+    fn add() {
+        let mut result = 0;
+        unsafe {
+            assert!(sna_add_u32(u32::MAX, 2, &mut result));
+            assert_eq!(result, 1);
+            assert!(!sna_add_u32(0, 1 << 31, &mut result));
+            assert_eq!(result, 1);
+            assert!(!sna_add_u32(0, 1, ptr::null_mut()));
+        }
+    }
+
+    #[test]
+    fn replay_window() {
+        assert!(sna_replay_window_new(0).is_null());
+        assert!(sna_replay_window_new((1 << 31) + 1).is_null());
+
+        let window = sna_replay_window_new(64);
+        unsafe {
+            assert_eq!(sna_replay_window_check_and_update(window, u32::MAX), SnaReplay::Accepted);
+            assert_eq!(sna_replay_window_check_and_update(window, 100), SnaReplay::Accepted);
+            assert_eq!(sna_replay_window_check(window, 100), SnaReplay::Duplicate);
+            assert_eq!(sna_replay_window_check(window, u32::MAX), SnaReplay::TooOld);
+            assert_eq!(sna_replay_window_check(ptr::null(), 100), SnaReplay::Invalid);
+            sna_replay_window_free(window);
+            sna_replay_window_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! Variants of the stateful types with a capacity fixed at compile time,
+//! e.g. for firmware without an allocator.
+//!
+//! Unlike their counterparts in `replay` and `reorder`, `ReplayWindow` and
+//! `ReorderBuffer` store their state inline and do not require the `alloc`
+//! feature. Only these two have a fixed-capacity variant.
+//!
+//! Without `alloc`, the stateful types available are:
+//!
+//! * `fixed::ReplayWindow` and `fixed::ReorderBuffer`,
+//! * `window::ByteWindow` and `window::SendWindow`,
+//! * `monotonic::Monotonic` and `outstanding::OutstandingTracker`,
+//! * `extended::Extender`, `extended::WrapCounter` and `rtcp::StreamStats`,
+//! * `epoch::EpochSerial`, `isn::IsnClock` and `wlsb::WlsbDecoder`,
+//! * `atomic::AtomicSerialNumber`, `seqcount::SeqCount` and
+//!   `versioned::Versioned`.
+//!
+//! All others require `alloc`, in particular `replay::DuplicateDetector`,
+//! `replay::TimedReplayWindow`, `replay::PersistentReplayWindow`,
+//! `gap::GapTracker`, `map::SerialMap`, `rangeset::SerialRangeSet`,
+//! `epoch::EpochReplayWindow`, `epoch::EpochReorderBuffer`,
+//! `wlsb::WlsbEncoder` and the types of `esn`, `ipv4`, `packet_id`, `sctp`
+//! and `sentinel`.
+
+use std::array;
+use std::cmp::Ordering;
+
+use SerialNumber;
+use bitmap::Window;
+use uint::Uint;
+
+pub use bitmap::Replay;
+
+/// A sliding window that rejects replayed serial numbers, backed by a
+/// bitmap of `WORDS` 64-bit words.
+///
+/// This behaves like `replay::ReplayWindow` but tracks at most
+/// `64 * WORDS` serial numbers.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::fixed::{Replay, ReplayWindow};
+///
+/// let mut window = ReplayWindow::<u32, 2>::new();
+/// assert_eq!(window.size(), 128);
+///
+/// assert_eq!(window.check_and_update(SerialNumber(u32::MAX)), Replay::Accepted);
+/// assert_eq!(window.check_and_update(SerialNumber(100)), Replay::Accepted);
+/// assert_eq!(window.check_and_update(SerialNumber(100)), Replay::Duplicate);
+/// assert_eq!(window.check(SerialNumber(50)), Replay::Accepted);
+/// assert_eq!(window.check(SerialNumber(u32::MAX - 100)), Replay::TooOld);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReplayWindow<T = u32, const WORDS: usize = 1> {
+    window: Window<T>,
+    bitmap: [u64; WORDS],
+}
+
+impl<T: Uint, const WORDS: usize> ReplayWindow<T, WORDS> where SerialNumber<T>: PartialOrd {
+    /// Create a window that tracks the `64 * WORDS` most recent serial
+    /// numbers (including the highest one seen).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WORDS` is zero or the window exceeds half of the serial
+    /// number space.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_size(64 * WORDS)
+    }
+
+    /// Create a window that tracks the `size` most recent serial numbers,
+    /// e.g. 32 as the minimum of IPsec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, exceeds `64 * WORDS` or exceeds half of the
+    /// serial number space.
+    pub fn with_size(size: usize) -> Self {
+        assert!(size > 0, "window size must be greater than zero");
+        assert!(size <= 64 * WORDS, "window size exceeds the capacity of the bitmap");
+        assert!(size as u128 <= T::HALF.to_u128(),
+                "window size exceeds half of the serial number space");
+        ReplayWindow { window: Window { size, highest: None }, bitmap: [0; WORDS] }
+    }
+
+    /// Return the size of the window.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.window.size
+    }
+
+    /// Return the highest serial number seen so far.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.window.highest
+    }
+
+    /// Check whether `serial` would be accepted without updating the
+    /// window.
+    #[inline]
+    pub fn check(&self, serial: SerialNumber<T>) -> Replay {
+        self.window.check(&self.bitmap, serial)
+    }
+
+    /// Check the serial numbers of a burst, e.g. of a GRO batch, without
+    /// updating the window.
+    ///
+    /// Returns a bitmask whose bit `i` is set if `serials[i]` would be
+    /// accepted, i.e. it is accepted by the window and does not repeat an
+    /// earlier serial number of the burst. Once the accepted packets have
+    /// been authenticated, mark them as seen with `extend`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `serials` holds more than 64 serial numbers.
+    #[inline]
+    pub fn check_batch(&self, serials: &[SerialNumber<T>]) -> u64 {
+        self.window.check_batch(&self.bitmap, serials)
+    }
+
+    /// Check whether `serial` is accepted and, if so, mark it as seen.
+    ///
+    /// Only call this once the packet has been authenticated, as otherwise
+    /// forged packets could advance the window.
+    #[inline]
+    pub fn check_and_update(&mut self, serial: SerialNumber<T>) -> Replay {
+        self.window.check_and_update(&mut self.bitmap, serial)
+    }
+
+    /// Forget all serial numbers seen so far.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.window.reset(&mut self.bitmap);
+    }
+}
+
+impl<T: Uint, const WORDS: usize> Default for ReplayWindow<T, WORDS>
+    where SerialNumber<T>: PartialOrd
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Uint, const WORDS: usize> Extend<SerialNumber<T>> for ReplayWindow<T, WORDS>
+    where SerialNumber<T>: PartialOrd
+{
+    /// Mark the serial numbers as seen as if by `check_and_update`, e.g.
+    /// the authenticated packets of a burst checked by `check_batch`.
+    fn extend<I: IntoIterator<Item = SerialNumber<T>>>(&mut self, serials: I) {
+        for serial in serials {
+            self.check_and_update(serial);
+        }
+    }
+}
+
+/// Holds up to `CAP` items that arrived out of order and releases them in
+/// serial number order, starting at the next expected serial number.
+///
+/// This behaves like `reorder::ReorderBuffer` with a distance of `CAP`,
+/// except that items are released in place. Inserting an item at least
+/// `CAP` ahead of the next expected serial number gives up on the missing
+/// serial numbers only if no item would have to be released to make room.
+/// Otherwise, the value is returned back and the held items have to be
+/// released with `flush_gap` and `pop` first.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::fixed::ReorderBuffer;
+///
+/// let mut buffer = ReorderBuffer::<u8, _, 4>::new(SerialNumber(254));
+///
+/// buffer.insert(SerialNumber(255), "b").unwrap();
+/// assert_eq!(buffer.pop(), None);
+/// buffer.insert(SerialNumber(254), "a").unwrap();
+/// assert_eq!(buffer.ready().collect::<Vec<_>>(),
+///            vec![(SerialNumber(254), "a"), (SerialNumber(255), "b")]);
+///
+/// // 0 is missing and 1 is held, so 5 does not fit
+/// buffer.insert(SerialNumber(1), "d").unwrap();
+/// assert_eq!(buffer.insert(SerialNumber(5), "e"), Err("e"));
+/// assert_eq!(buffer.flush_gap(), 1);
+/// assert_eq!(buffer.pop(), Some((SerialNumber(1), "d")));
+/// buffer.insert(SerialNumber(5), "e").unwrap();
+/// assert_eq!(buffer.lost(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReorderBuffer<T, V, const CAP: usize> {
+    next: SerialNumber<T>,
+    head: usize,
+    slots: [Option<V>; CAP],
+    len: usize,
+    lost: u64,
+}
+
+impl<T: Uint, V, const CAP: usize> ReorderBuffer<T, V, CAP> where SerialNumber<T>: PartialOrd {
+    /// Create a buffer expecting `next` first that holds items up to
+    /// `CAP - 1` ahead of the next expected serial number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `CAP` is zero or exceeds half of the serial number space.
+    pub fn new(next: SerialNumber<T>) -> Self {
+        assert!(CAP > 0, "capacity must be greater than zero");
+        assert!(CAP as u128 <= T::HALF.to_u128(),
+                "capacity exceeds half of the serial number space");
+        ReorderBuffer { next, head: 0, slots: array::from_fn(|_| None), len: 0, lost: 0 }
+    }
+
+    /// Return the next expected serial number.
+    #[inline]
+    pub fn next_expected(&self) -> SerialNumber<T> {
+        self.next
+    }
+
+    /// Return the amount of items held.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether no items are held.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the number of serial numbers given up on so far.
+    #[inline]
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    /// Buffer `value` with serial number `serial`.
+    ///
+    /// Returns the value back if `serial` precedes the next expected
+    /// serial number, has already been buffered, can not be ordered
+    /// relative to the next expected serial number or is too far ahead to
+    /// fit while items are held.
+    pub fn insert(&mut self, serial: SerialNumber<T>, value: V) -> Result<(), V> {
+        match serial.partial_cmp(&self.next) {
+            Some(Ordering::Equal) | Some(Ordering::Greater) => {}
+            _ => return Err(value),
+        }
+        let mut offset = serial.0.wrapping_sub(self.next.0).to_u128();
+        if offset >= CAP as u128 {
+            let skip = offset - CAP as u128 + 1;
+            let passed = skip.min(CAP as u128) as usize;
+            if (0..passed).any(|offset| self.slots[self.index(offset)].is_some()) {
+                return Err(value);
+            }
+            self.advance(skip);
+            self.lost += skip as u64;
+            offset = CAP as u128 - 1;
+        }
+        let index = self.index(offset as usize);
+        match self.slots[index] {
+            Some(_) => Err(value),
+            None => {
+                self.slots[index] = Some(value);
+                self.len += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove and return the item with the next expected serial number, if
+    /// it has been buffered.
+    pub fn pop(&mut self) -> Option<(SerialNumber<T>, V)> {
+        let value = self.slots[self.head].take()?;
+        let serial = self.next;
+        self.len -= 1;
+        self.advance(1);
+        Some((serial, value))
+    }
+
+    /// Return an iterator that removes and returns the ready items.
+    #[inline]
+    pub fn ready(&mut self) -> Ready<'_, T, V, CAP> {
+        Ready { buffer: self }
+    }
+
+    /// Give up on the missing serial numbers up to the oldest buffered
+    /// item, so it becomes ready.
+    ///
+    /// Returns the number of serial numbers given up on.
+    pub fn flush_gap(&mut self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        let missing = (0..CAP).take_while(|&offset| self.slots[self.index(offset)].is_none())
+            .count();
+        self.advance(missing as u128);
+        self.lost += missing as u64;
+        missing as u64
+    }
+
+    fn index(&self, offset: usize) -> usize {
+        (self.head + offset) % CAP
+    }
+
+    /// Advance the next expected serial number by `count` over empty
+    /// slots.
+    fn advance(&mut self, count: u128) {
+        self.head = self.index((count % CAP as u128) as usize);
+        self.next = SerialNumber(self.next.0.wrapping_add(T::from_u128(count)));
+    }
+}
+
+/// An iterator over the ready items of a `ReorderBuffer`.
+///
+/// Created by `ReorderBuffer::ready`.
+#[derive(Debug)]
+pub struct Ready<'a, T: 'a, V: 'a, const CAP: usize> {
+    buffer: &'a mut ReorderBuffer<T, V, CAP>,
+}
+
+impl<'a, T: Uint, V, const CAP: usize> Iterator for Ready<'a, T, V, CAP>
+    where SerialNumber<T>: PartialOrd
+{
+    type Item = (SerialNumber<T>, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(SerialNumber<T>, V)> {
+        self.buffer.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn replay_window() {
+        let mut window = ReplayWindow::<u16, 2>::with_size(100);
+        assert_eq!(window.check_and_update(SerialNumber(65500)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(63)), Replay::Accepted);
+        assert_eq!(window.check(SerialNumber(65500)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(65499)), Replay::TooOld);
+        assert_eq!(window.check(SerialNumber(0)), Replay::Accepted);
+
+        window.reset();
+        assert_eq!(window.highest(), None);
+        assert_eq!(window.check(SerialNumber(63)), Replay::Accepted);
+
+        window.extend([SerialNumber(10), SerialNumber(20)]);
+        assert_eq!(window.check_batch(&[10, 11, 11, 20].map(SerialNumber)), 0b0010);
+    }
+
+    #[test]
+    fn replay_window_unused_word() {
+        let mut window = ReplayWindow::<u32, 2>::with_size(32);
+        assert_eq!(window.check_and_update(SerialNumber(100)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(110)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(100)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(78)), Replay::TooOld);
+        assert_eq!(window.check_and_update(SerialNumber(141)), Replay::Accepted);
+        assert_eq!(window.check(SerialNumber(110)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(109)), Replay::TooOld);
+        assert_eq!(window.bitmap[1], 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn replay_window_too_large() {
+        ReplayWindow::<u8, 4>::new();
+    }
+
+    #[test]
+    fn reorder_buffer() {
+        let mut buffer = ReorderBuffer::<u16, u16, 4>::new(SerialNumber(65534));
+        for &serial in &[65535u16, 1, 0] {
+            buffer.insert(SerialNumber(serial), serial).unwrap();
+        }
+        assert_eq!(buffer.insert(SerialNumber(1), 1), Err(1));
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.flush_gap(), 1);
+        assert_eq!(buffer.ready().map(|(serial, _)| serial.0).collect::<Vec<_>>(), vec![65535, 0, 1]);
+        assert_eq!(buffer.flush_gap(), 0);
+        assert!(buffer.is_empty());
+
+        // Jumps past everything, which is empty
+        buffer.insert(SerialNumber(100), 100).unwrap();
+        assert_eq!(buffer.next_expected(), SerialNumber(97));
+        assert_eq!(buffer.lost(), 1 + 95);
+        assert_eq!(buffer.insert(SerialNumber(96), 96), Err(96));
+        buffer.insert(SerialNumber(97), 97).unwrap();
+        assert_eq!(buffer.ready().map(|(serial, _)| serial.0).collect::<Vec<_>>(), vec![97]);
+        assert_eq!(buffer.flush_gap(), 2);
+        assert_eq!(buffer.pop(), Some((SerialNumber(100), 100)));
+    }
+}
@@ -0,0 +1,337 @@
+//! Detection of missing serial numbers for NACK-based repair, e.g. generic
+//! NACKs of RTCP, see
+//! [chapter 6.2.1 of RFC 4585](https://tools.ietf.org/html/rfc4585#section-6.2.1).
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use SerialNumber;
+use range::SerialRange;
+use rangeset::Compaction;
+use uint::Uint;
+
+/// An event reported by `GapTracker::poll_event`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GapEvent<T> {
+    /// The serial numbers of the range are missing and have not been
+    /// reordered within the reordering tolerance, e.g. to send a NACK.
+    Detected(SerialRange<T>),
+    /// The serial numbers of the range are still missing after the
+    /// persistence threshold, e.g. to give up on them or escalate.
+    Persisted(SerialRange<T>),
+    /// The serial number has been received after its gap had been
+    /// detected, e.g. due to a retransmission.
+    Filled(SerialNumber<T>),
+}
+
+#[derive(Clone, Debug)]
+struct Gap<T> {
+    range: SerialRange<T>,
+    packets: usize,
+    detected: bool,
+    persisted: bool,
+}
+
+/// Tracks the gaps between received serial numbers.
+///
+/// A gap is detected once more than `reorder` packets following it
+/// (including the one revealing it) have been received without filling it,
+/// so reordering by up to `reorder` packets does not cause any event. It is
+/// reported again once more than `persist` packets following it have been
+/// received. Gaps that fall behind the highest received serial number by
+/// half of the serial number space or more are forgotten.
+///
+/// # Examples
+///
+/// ```
+/// use sna::{SerialNumber, SerialRange};
+/// use sna::gap::{GapEvent, GapTracker};
+///
+/// let mut tracker = GapTracker::new(1, 3);
+///
+/// for &serial in &[10u16, 13, 12, 14] {
+///     tracker.receive(SerialNumber(serial));
+/// }
+/// assert_eq!(tracker.highest(), Some(SerialNumber(14)));
+///
+/// // 12 was reordered, but 11 is still missing after two packets
+/// let missing = SerialRange::new(SerialNumber(11), SerialNumber(12));
+/// assert_eq!(tracker.poll_event(), Some(GapEvent::Detected(missing)));
+/// assert_eq!(tracker.poll_event(), None);
+///
+/// tracker.receive(SerialNumber(11));
+/// assert_eq!(tracker.poll_event(), Some(GapEvent::Filled(SerialNumber(11))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GapTracker<T> {
+    reorder: usize,
+    persist: usize,
+    highest: Option<SerialNumber<T>>,
+    gaps: Vec<Gap<T>>,
+    events: VecDeque<GapEvent<T>>,
+}
+
+impl<T: Uint> GapTracker<T> where SerialNumber<T>: PartialOrd {
+    /// Create a tracker that detects gaps once more than `reorder` packets
+    /// following them have been received and reports them as persistent
+    /// once more than `persist` packets following them have been received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `persist` is not greater than `reorder`.
+    pub fn new(reorder: usize, persist: usize) -> Self {
+        assert!(persist > reorder, "persistence threshold must exceed the reordering tolerance");
+        GapTracker { reorder, persist, highest: None, gaps: Vec::new(), events: VecDeque::new() }
+    }
+
+    /// Return the highest serial number received so far.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.highest
+    }
+
+    /// Return an iterator over the missing serial numbers, lowest first.
+    pub fn gaps(&self) -> impl Iterator<Item = SerialRange<T>> + '_ {
+        self.gaps.iter().map(|gap| gap.range)
+    }
+
+    /// Record the receipt of `serial`.
+    ///
+    /// Returns `false` and ignores `serial` if it is a duplicate, has been
+    /// forgotten or can not be ordered relative to the highest serial
+    /// number received.
+    pub fn receive(&mut self, serial: SerialNumber<T>) -> bool {
+        let highest = match self.highest {
+            Some(highest) => highest,
+            None => {
+                self.highest = Some(serial);
+                return true;
+            }
+        };
+        match serial.partial_cmp(&highest) {
+            Some(Ordering::Greater) => {
+                self.count(serial);
+                let next = SerialNumber(highest.0.wrapping_add(T::ONE));
+                if next != serial {
+                    self.gaps.push(Gap {
+                        range: SerialRange::new(next, serial),
+                        packets: 1,
+                        detected: false,
+                        persisted: false,
+                    });
+                }
+                self.highest = Some(serial);
+                self.gaps.retain(|gap| gap.range.start().partial_cmp(&serial) == Some(Ordering::Less));
+            }
+            Some(Ordering::Less) => {
+                let index = match self.gaps.iter().position(|gap| gap.range.contains(serial)) {
+                    Some(index) => index,
+                    None => return false,
+                };
+                self.fill(index, serial);
+                self.count(serial);
+            }
+            _ => return false,
+        }
+        self.check();
+        true
+    }
+
+    /// Return the next pending event, oldest first.
+    #[inline]
+    pub fn poll_event(&mut self) -> Option<GapEvent<T>> {
+        self.events.pop_front()
+    }
+
+    /// Merge adjacent gaps and forget the oldest gaps until at most
+    /// `max_gaps` remain, e.g. to bound the size of a NACK under heavy
+    /// loss.
+    ///
+    /// Serial numbers of forgotten gaps are ignored by `receive`, as if they
+    /// had fallen behind. A merged gap is reported again once it is
+    /// detected or persists.
+    pub fn compact(&mut self, max_gaps: usize) -> Compaction {
+        let len = self.gaps.len();
+        self.gaps.dedup_by(|next, previous| {
+            let adjacent = previous.range.end() == next.range.start();
+            if adjacent {
+                *previous = Gap {
+                    range: SerialRange::new(previous.range.start(), next.range.end()),
+                    packets: next.packets,
+                    detected: previous.detected && next.detected,
+                    persisted: previous.persisted && next.persisted,
+                };
+            }
+            adjacent
+        });
+        let coalesced = len - self.gaps.len();
+        let dropped = self.gaps.len().saturating_sub(max_gaps);
+        self.gaps.drain(..dropped);
+        self.check();
+        Compaction { coalesced, dropped }
+    }
+
+    /// Forget all gaps, pending events and the highest serial number.
+    pub fn reset(&mut self) {
+        self.highest = None;
+        self.gaps.clear();
+        self.events.clear();
+    }
+
+    /// Remove `serial` from the gap at `index`, splitting it if necessary.
+    fn fill(&mut self, index: usize, serial: SerialNumber<T>) {
+        let gap = self.gaps.remove(index);
+        if gap.detected {
+            self.events.push_back(GapEvent::Filled(serial));
+        }
+        let after = SerialNumber(serial.0.wrapping_add(T::ONE));
+        let parts = [
+            SerialRange::new(after, gap.range.end()),
+            SerialRange::new(gap.range.start(), serial),
+        ];
+        for &range in parts.iter().filter(|range| !range.is_empty()) {
+            self.gaps.insert(index, Gap { range, ..gap });
+        }
+    }
+
+    /// Count a packet for each gap that precedes `serial`.
+    fn count(&mut self, serial: SerialNumber<T>) {
+        for gap in &mut self.gaps {
+            if gap.range.end().partial_cmp(&serial) != Some(Ordering::Greater) {
+                gap.packets += 1;
+            }
+        }
+    }
+
+    fn check(&mut self) {
+        for gap in &mut self.gaps {
+            if !gap.detected && gap.packets > self.reorder {
+                gap.detected = true;
+                self.events.push_back(GapEvent::Detected(gap.range));
+            }
+            if !gap.persisted && gap.packets > self.persist {
+                gap.persisted = true;
+                self.events.push_back(GapEvent::Persisted(gap.range));
+            }
+        }
+    }
+}
+
+impl<T: Uint> Extend<SerialNumber<T>> for GapTracker<T> where SerialNumber<T>: PartialOrd {
+    /// Record the receipt of the serial numbers as if by `receive`, e.g. of
+    /// a burst of packets.
+    fn extend<I: IntoIterator<Item = SerialNumber<T>>>(&mut self, serials: I) {
+        for serial in serials {
+            self.receive(serial);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    fn range(start: u8, end: u8) -> SerialRange<u8> {
+        SerialRange::new(SerialNumber(start), SerialNumber(end))
+    }
+
+    fn events(tracker: &mut GapTracker<u8>) -> Vec<GapEvent<u8>> {
+        let mut events = Vec::new();
+        while let Some(event) = tracker.poll_event() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn immediate() {
+        let mut tracker = GapTracker::new(0, 2);
+        assert!(tracker.receive(SerialNumber(250)));
+        assert!(tracker.receive(SerialNumber(253)));
+        assert!(tracker.receive(SerialNumber(1)));
+        assert_eq!(events(&mut tracker), vec![
+            GapEvent::Detected(range(251, 253)),
+            GapEvent::Detected(range(254, 1)),
+        ]);
+        assert!(tracker.receive(SerialNumber(2)));
+        assert_eq!(events(&mut tracker), vec![GapEvent::Persisted(range(251, 253))]);
+    }
+
+    #[test]
+    fn reordering_is_tolerated() {
+        let mut tracker = GapTracker::new(3, 10);
+        for &serial in &[1u8, 4, 5, 3, 2, 6, 7, 8] {
+            assert!(tracker.receive(SerialNumber(serial)));
+        }
+        assert!(events(&mut tracker).is_empty());
+        assert!(tracker.gaps().next().is_none());
+
+        tracker.extend([10, 9, 12].map(SerialNumber));
+        assert_eq!(tracker.gaps().collect::<Vec<_>>(), vec![range(11, 12)]);
+        assert!(!tracker.receive(SerialNumber(3)));
+        assert!(!tracker.receive(SerialNumber(8)));
+    }
+
+    #[test]
+    fn split_and_fill() {
+        let mut tracker = GapTracker::new(2, 4);
+        tracker.receive(SerialNumber(0));
+        tracker.receive(SerialNumber(10));
+        tracker.receive(SerialNumber(5));
+        assert_eq!(tracker.gaps().collect::<Vec<_>>(), vec![range(1, 5), range(6, 10)]);
+        assert!(events(&mut tracker).is_empty());
+
+        tracker.receive(SerialNumber(11));
+        assert_eq!(events(&mut tracker), vec![GapEvent::Detected(range(1, 5))]);
+        tracker.receive(SerialNumber(12));
+        assert_eq!(events(&mut tracker), vec![GapEvent::Detected(range(6, 10))]);
+
+        tracker.receive(SerialNumber(7));
+        tracker.receive(SerialNumber(3));
+        assert_eq!(tracker.gaps().collect::<Vec<_>>(),
+                   vec![range(1, 3), range(4, 5), range(6, 7), range(8, 10)]);
+        assert_eq!(events(&mut tracker), vec![
+            GapEvent::Filled(SerialNumber(7)),
+            GapEvent::Persisted(range(1, 5)),
+            GapEvent::Filled(SerialNumber(3)),
+        ]);
+    }
+
+    #[test]
+    fn compact() {
+        let mut tracker = GapTracker::new(0, 2);
+        tracker.extend([0, 5, 10, 15].map(SerialNumber));
+        assert_eq!(events(&mut tracker).len(), 4);
+        assert_eq!(tracker.compact(3), Compaction::default());
+        assert_eq!(tracker.compact(1), Compaction { coalesced: 0, dropped: 2 });
+        assert_eq!(tracker.gaps().collect::<Vec<_>>(), vec![range(11, 15)]);
+        assert!(!tracker.receive(SerialNumber(3)));
+
+        tracker.receive(SerialNumber(12));
+        assert_eq!(events(&mut tracker), vec![GapEvent::Filled(SerialNumber(12))]);
+        tracker.gaps[0].range = range(11, 13);
+        assert_eq!(tracker.compact(1), Compaction { coalesced: 1, dropped: 0 });
+        assert_eq!(tracker.gaps().collect::<Vec<_>>(), vec![range(11, 15)]);
+        assert!(events(&mut tracker).is_empty());
+    }
+
+    #[test]
+    fn forget_old_gaps() {
+        let mut tracker = GapTracker::new(1, 2);
+        tracker.receive(SerialNumber(0));
+        tracker.receive(SerialNumber(10));
+        tracker.receive(SerialNumber(130));
+        assert_eq!(tracker.gaps().collect::<Vec<_>>(), vec![range(11, 130)]);
+        assert!(!tracker.receive(SerialNumber(5)));
+
+        tracker.reset();
+        assert_eq!(tracker.highest(), None);
+        assert!(tracker.poll_event().is_none());
+    }
+}
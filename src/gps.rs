@@ -0,0 +1,114 @@
+//! Resolution of truncated GPS week numbers.
+//!
+//! GPS satellites broadcast the number of weeks since the GPS epoch
+//! (1980-01-06 00:00 UTC) truncated to 10 bits in the legacy navigation
+//! message, which wraps every 1024 weeks (approximately 19.6 years), or to
+//! 13 bits in the modernized (CNAV) navigation message, which wraps every
+//! 8192 weeks (approximately 157 years). A receiver reconstructs the full
+//! week number relative to an approximate reference week, e.g. derived from
+//! its firmware build date or a real-time clock.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use bits::Serial;
+
+/// The offset of the GPS epoch from the Unix epoch (1980-01-06 00:00 UTC).
+pub const GPS_EPOCH_UNIX: Duration = Duration::from_secs(315_964_800);
+
+/// The duration of a GPS week.
+pub const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A 10-bit week number of the legacy navigation message.
+pub type LegacyWeek = Serial<u16, 10>;
+
+/// A 13-bit week number of the modernized navigation message.
+pub type Week = Serial<u16, 13>;
+
+/// Return the full week number of `since_epoch` after the GPS epoch, e.g.
+/// to derive a reference week from a clock.
+///
+/// Note that GPS time does not include leap seconds, but they are
+/// negligible for a reference week.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use sna::gps;
+///
+/// // 2019-04-07, the second rollover of the 10-bit week number
+/// let since_unix_epoch = Duration::from_secs(1_554_595_200);
+/// assert_eq!(gps::week_of(since_unix_epoch - gps::GPS_EPOCH_UNIX), 2048);
+/// ```
+#[inline]
+pub fn week_of(since_epoch: Duration) -> u32 {
+    (since_epoch.as_secs() / WEEK.as_secs()) as u32
+}
+
+/// Reconstruct the full week number of the truncated `week` that is closest
+/// to the full week number `reference`.
+///
+/// A `week` that is exactly half of the week number space away from
+/// `reference` is considered to follow it. The result never precedes the
+/// GPS epoch.
+///
+/// # Examples
+///
+/// ```
+/// use sna::gps::{self, LegacyWeek, Week};
+///
+/// // Received shortly after the 2019 rollover, with a reference from 2018
+/// let week = LegacyWeek::new(3).unwrap();
+/// assert_eq!(gps::resolve(week, 2000), 2051);
+///
+/// // A slightly stale reference still resolves to the current cycle
+/// assert_eq!(gps::resolve(LegacyWeek::new(1020).unwrap(), 2051), 2044);
+///
+/// assert_eq!(gps::resolve(Week::new(2051).unwrap(), 2000), 2051);
+/// ```
+pub fn resolve<const BITS: u32>(week: Serial<u16, BITS>, reference: u32) -> u32 {
+    let reference_week = Serial::<u16, BITS>::new_masked(reference as u16);
+    let modulus = 1u32 << BITS;
+    let ahead = u32::from(week.get().wrapping_sub(reference_week.get())) & (modulus - 1);
+    match week.partial_cmp(&reference_week) {
+        Some(Ordering::Less) => reference.checked_sub(modulus - ahead)
+            .unwrap_or(reference + ahead),
+        _ => reference + ahead,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy() {
+        for &reference in &[0, 511, 512, 1023, 1024, 2048, 5000] {
+            for offset in -511i64..=512 {
+                let full = i64::from(reference) + offset;
+                if full < 0 {
+                    continue;
+                }
+                let week = LegacyWeek::new_masked(full as u16);
+                assert_eq!(resolve(week, reference), full as u32,
+                           "reference {} offset {}", reference, offset);
+            }
+        }
+    }
+
+    #[test]
+    fn modernized() {
+        let reference = 8192 + 100;
+        assert_eq!(resolve(Week::new(8000).unwrap(), reference), 8000);
+        assert_eq!(resolve(Week::new(200).unwrap(), reference), 8392);
+        assert_eq!(resolve(Week::new(100 + 4096).unwrap(), reference), 8192 + 100 + 4096);
+    }
+
+    #[test]
+    fn never_before_epoch() {
+        assert_eq!(resolve(LegacyWeek::new(1000).unwrap(), 10), 1000);
+        assert_eq!(resolve(LegacyWeek::new(512).unwrap(), 0), 512);
+    }
+}
@@ -0,0 +1,215 @@
+//! Tracking of the 16-bit Identification field of IPv4 packets.
+//!
+//! Fragments of the same datagram are identified by the source and
+//! destination address, the protocol and the Identification field, see
+//! [chapter 3.2 of RFC 791](https://tools.ietf.org/html/rfc791#section-3.2).
+//! An Identification value that is reused while fragments with the same
+//! value may still be reassembled can cause fragments of different
+//! datagrams to be mixed up, see [RFC 4963](https://tools.ietf.org/html/rfc4963).
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use alloc::collections::BTreeMap;
+
+use SerialNumber;
+
+/// The key under which IPv4 fragments are reassembled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FlowKey {
+    /// The source address.
+    pub source: Ipv4Addr,
+    /// The destination address.
+    pub destination: Ipv4Addr,
+    /// The protocol number.
+    pub protocol: u8,
+}
+
+impl FlowKey {
+    /// Create a key from the source address, destination address and
+    /// protocol number of a packet.
+    #[inline]
+    pub fn new(source: Ipv4Addr, destination: Ipv4Addr, protocol: u8) -> Self {
+        FlowKey { source, destination, protocol }
+    }
+}
+
+/// The outcome of `IdTracker::observe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Observation {
+    /// The Identification value has not been seen within the reassembly
+    /// window.
+    Fresh,
+    /// The Identification value has already been seen within the
+    /// reassembly window.
+    Reused,
+}
+
+#[derive(Clone, Debug)]
+struct Flow {
+    newest: SerialNumber<u16>,
+    seen: BTreeMap<u16, Duration>,
+}
+
+/// Tracks the Identification values seen per `FlowKey` within a
+/// reassembly window.
+///
+/// Time is passed in by the caller as the duration since an arbitrary but
+/// fixed point in time, so the tracker can be driven by a monotonic clock
+/// or by simulated time.
+///
+/// # Examples
+///
+/// ```
+/// use std::net::Ipv4Addr;
+/// use std::time::Duration;
+///
+/// use sna::SerialNumber;
+/// use sna::ipv4::{FlowKey, IdTracker, Observation};
+///
+/// let key = FlowKey::new(Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2), 17);
+/// let mut tracker = IdTracker::new(Duration::from_secs(30));
+///
+/// let now = Duration::from_secs(100);
+/// assert_eq!(tracker.observe(key, SerialNumber(65535), now), Observation::Fresh);
+/// assert_eq!(tracker.observe(key, SerialNumber(0), now), Observation::Fresh);
+/// assert_eq!(tracker.newest(&key), Some(SerialNumber(0)));
+///
+/// // Reused while fragments with that ID may still be reassembled
+/// assert_eq!(tracker.observe(key, SerialNumber(65535), now + Duration::from_secs(10)),
+///            Observation::Reused);
+///
+/// // No longer a reuse once the reassembly window has passed
+/// tracker.expire(now + Duration::from_secs(60));
+/// assert_eq!(tracker.observe(key, SerialNumber(65535), now + Duration::from_secs(60)),
+///            Observation::Fresh);
+/// ```
+#[derive(Clone, Debug)]
+pub struct IdTracker {
+    window: Duration,
+    flows: BTreeMap<FlowKey, Flow>,
+}
+
+impl IdTracker {
+    /// Create a tracker that remembers Identification values for the
+    /// reassembly timeout `window` (e.g. 15 seconds as suggested by RFC 791
+    /// or 30 seconds as used by Linux).
+    #[inline]
+    pub fn new(window: Duration) -> Self {
+        IdTracker { window, flows: BTreeMap::new() }
+    }
+
+    /// Return the reassembly window.
+    #[inline]
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Return the amount of flows being tracked.
+    #[inline]
+    pub fn flows(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Record that a packet of `key` with Identification value `id` has
+    /// been seen at `now`.
+    ///
+    /// Returns whether the value has already been seen within the
+    /// reassembly window. Values that have been seen before `now` minus the
+    /// reassembly window are not considered a reuse, even if they have not
+    /// been expired, yet.
+    pub fn observe(&mut self, key: FlowKey, id: SerialNumber<u16>, now: Duration) -> Observation {
+        let window = self.window;
+        let flow = self.flows.entry(key).or_insert_with(|| Flow { newest: id, seen: BTreeMap::new() });
+        if id.partial_cmp(&flow.newest) == Some(Ordering::Greater) {
+            flow.newest = id;
+        }
+        match flow.seen.insert(id.0, now) {
+            Some(seen) if now.saturating_sub(seen) < window => Observation::Reused,
+            _ => Observation::Fresh,
+        }
+    }
+
+    /// Return the newest Identification value (in serial number order)
+    /// seen for `key`.
+    #[inline]
+    pub fn newest(&self, key: &FlowKey) -> Option<SerialNumber<u16>> {
+        self.flows.get(key).map(|flow| flow.newest)
+    }
+
+    /// Return whether `id` is newer than every Identification value seen
+    /// for `key` so far, which is also the case if nothing has been seen
+    /// for `key`.
+    pub fn is_newer(&self, key: &FlowKey, id: SerialNumber<u16>) -> bool {
+        match self.newest(key) {
+            Some(newest) => id.partial_cmp(&newest) == Some(Ordering::Greater),
+            None => true,
+        }
+    }
+
+    /// Forget all Identification values that have last been seen before
+    /// `now` minus the reassembly window, and all flows without any
+    /// remaining values.
+    pub fn expire(&mut self, now: Duration) {
+        let window = self.window;
+        self.flows.retain(|_, flow| {
+            flow.seen.retain(|_, seen| now.saturating_sub(*seen) < window);
+            !flow.seen.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(protocol: u8) -> FlowKey {
+        FlowKey::new(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2), protocol)
+    }
+
+    #[test]
+    fn reuse_per_flow() {
+        let mut tracker = IdTracker::new(Duration::from_secs(15));
+        let now = Duration::from_secs(1);
+        assert_eq!(tracker.observe(key(6), SerialNumber(7), now), Observation::Fresh);
+        assert_eq!(tracker.observe(key(17), SerialNumber(7), now), Observation::Fresh);
+        assert_eq!(tracker.observe(key(6), SerialNumber(7), now), Observation::Reused);
+        assert_eq!(tracker.flows(), 2);
+
+        let later = now + Duration::from_secs(15);
+        assert_eq!(tracker.observe(key(6), SerialNumber(7), later), Observation::Fresh);
+        assert_eq!(tracker.observe(key(6), SerialNumber(7), later), Observation::Reused);
+    }
+
+    #[test]
+    fn newer_across_wrap() {
+        let mut tracker = IdTracker::new(Duration::from_secs(15));
+        let now = Duration::from_secs(0);
+        assert!(tracker.is_newer(&key(1), SerialNumber(0)));
+        tracker.observe(key(1), SerialNumber(65000), now);
+        tracker.observe(key(1), SerialNumber(100), now);
+        tracker.observe(key(1), SerialNumber(65500), now);
+        assert_eq!(tracker.newest(&key(1)), Some(SerialNumber(100)));
+        assert!(tracker.is_newer(&key(1), SerialNumber(101)));
+        assert!(!tracker.is_newer(&key(1), SerialNumber(65535)));
+    }
+
+    #[test]
+    fn expire() {
+        let mut tracker = IdTracker::new(Duration::from_secs(30));
+        tracker.observe(key(6), SerialNumber(1), Duration::from_secs(0));
+        tracker.observe(key(6), SerialNumber(2), Duration::from_secs(20));
+        tracker.observe(key(17), SerialNumber(1), Duration::from_secs(5));
+
+        tracker.expire(Duration::from_secs(40));
+        assert_eq!(tracker.flows(), 1);
+        assert_eq!(tracker.newest(&key(17)), None);
+        assert_eq!(tracker.observe(key(6), SerialNumber(1), Duration::from_secs(40)),
+                   Observation::Fresh);
+        assert_eq!(tracker.observe(key(6), SerialNumber(2), Duration::from_secs(40)),
+                   Observation::Reused);
+    }
+}
@@ -0,0 +1,276 @@
+//! A simulated clock for generating TCP initial sequence numbers (ISNs) in
+//! tests.
+//!
+//! [RFC 793](https://tools.ietf.org/html/rfc793#page-27) bases the ISN on a
+//! 32-bit clock whose low-order bit is incremented roughly every 4
+//! microseconds, so it cycles approximately every 4.55 hours. Since the clock
+//! is driven by simulated time only, tests can jump straight to the wrap
+//! point or to the moment an ISN is reused instead of waiting for it.
+//!
+//! `IsnGenerator` produces hard-to-predict ISNs for real connections as
+//! defined by [RFC 6528](https://tools.ietf.org/html/rfc6528).
+
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use SerialNumber;
+
+/// The tick of the ISN clock as defined by RFC 793.
+pub const RFC_793_TICK: Duration = Duration::from_micros(4);
+
+/// A deterministic ISN clock over simulated time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use sna::SerialNumber;
+/// use sna::isn::IsnClock;
+///
+/// let mut clock = IsnClock::rfc_793().with_initial(SerialNumber(u32::MAX));
+/// let isn = clock.isn();
+///
+/// clock.advance(Duration::from_micros(10));
+/// assert_eq!(clock.isn(), SerialNumber(1u32));
+/// assert!(clock.isn() > isn);
+///
+/// // Jump to the moment the first ISN is handed out again
+/// assert_eq!(clock.advance_to(isn), clock.period() - Duration::from_micros(10));
+/// assert_eq!(clock.isn(), isn);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IsnClock {
+    tick: Duration,
+    initial: SerialNumber<u32>,
+    elapsed: Duration,
+}
+
+impl IsnClock {
+    /// Create a clock that is incremented every 4 microseconds as defined by
+    /// RFC 793.
+    #[inline]
+    pub fn rfc_793() -> Self {
+        IsnClock::with_tick(RFC_793_TICK)
+    }
+
+    /// Create a clock that is incremented every `tick`, e.g. to model a
+    /// faster clock than the one of RFC 793.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is zero.
+    #[inline]
+    pub fn with_tick(tick: Duration) -> Self {
+        assert!(tick > Duration::ZERO, "tick must not be zero");
+        IsnClock { tick, initial: SerialNumber(0), elapsed: Duration::ZERO }
+    }
+
+    /// Let the clock start at `initial` instead of zero.
+    #[inline]
+    pub fn with_initial(mut self, initial: SerialNumber<u32>) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    /// Return the tick of the clock.
+    #[inline]
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    /// Return the simulated time that has passed.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Return the time it takes the clock to cycle through all 2^32
+    /// values.
+    #[inline]
+    pub fn period(&self) -> Duration {
+        self.tick * (1 << 16) * (1 << 16)
+    }
+
+    /// Return the ISN at the current simulated time.
+    #[inline]
+    pub fn isn(&self) -> SerialNumber<u32> {
+        SerialNumber(self.initial.0.wrapping_add(self.ticks() as u32))
+    }
+
+    /// Let `duration` of simulated time pass.
+    #[inline]
+    pub fn advance(&mut self, duration: Duration) {
+        self.elapsed += duration;
+    }
+
+    /// Let the clock tick `ticks` times.
+    #[inline]
+    pub fn advance_ticks(&mut self, ticks: u32) {
+        self.elapsed += self.tick * ticks;
+    }
+
+    /// Let simulated time pass until the clock next reads `isn`.
+    ///
+    /// Returns the simulated time that has passed, which is zero if the
+    /// clock already reads `isn`.
+    pub fn advance_to(&mut self, isn: SerialNumber<u32>) -> Duration {
+        let ticks = isn.0.wrapping_sub(self.isn().0);
+        if ticks == 0 {
+            return Duration::ZERO;
+        }
+        let before = self.elapsed;
+        self.elapsed = self.tick_duration(self.ticks() + u128::from(ticks));
+        self.elapsed - before
+    }
+
+    /// Return the simulated time until the clock next wraps to zero.
+    pub fn until_wrap(&self) -> Duration {
+        let ticks = (1 << 32) - u128::from(self.isn().0);
+        self.tick_duration(self.ticks() + ticks) - self.elapsed
+    }
+
+    fn ticks(&self) -> u128 {
+        self.elapsed.as_nanos() / self.tick.as_nanos()
+    }
+
+    fn tick_duration(&self, ticks: u128) -> Duration {
+        let nanos = ticks * self.tick.as_nanos();
+        Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32)
+    }
+}
+
+/// A generator of initial sequence numbers that are hard to predict by an
+/// off-path attacker, see [RFC 6528](https://tools.ietf.org/html/rfc6528).
+///
+/// The ISN is the RFC 793 clock (incremented every 4 microseconds) plus a
+/// keyed hash of the connection's 4-tuple, so ISNs of the same 4-tuple
+/// still increase over time while those of different 4-tuples are
+/// unrelated. The secret key is the state of the `BuildHasher` `S`, which
+/// must therefore be a keyed hash function (e.g. SipHash with a random key)
+/// that is kept secret.
+///
+/// Time is passed in by the caller as the duration since an arbitrary but
+/// fixed point in time, e.g. the start of the process.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::RandomState;
+/// use std::net::SocketAddr;
+/// use std::time::Duration;
+///
+/// use sna::isn::IsnGenerator;
+///
+/// let generator = IsnGenerator::with_hasher(RandomState::new());
+/// let local: SocketAddr = "192.0.2.1:80".parse().unwrap();
+/// let remote: SocketAddr = "198.51.100.7:50000".parse().unwrap();
+///
+/// let isn = generator.generate(local, remote, Duration::from_secs(1));
+/// let later = generator.generate(local, remote, Duration::from_secs(2));
+/// assert_eq!(later.0.wrapping_sub(isn.0), 250_000);
+/// ```
+#[derive(Clone)]
+pub struct IsnGenerator<S> {
+    hasher: S,
+}
+
+#[cfg(feature = "std")]
+impl IsnGenerator<RandomState> {
+    /// Create a generator keyed with a random key.
+    ///
+    /// Requires the `std` feature.
+    #[inline]
+    pub fn new() -> Self {
+        IsnGenerator::with_hasher(RandomState::new())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for IsnGenerator<RandomState> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: BuildHasher> IsnGenerator<S> {
+    /// Create a generator that uses `hasher` as the keyed hash function.
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        IsnGenerator { hasher }
+    }
+
+    /// Return the ISN for a connection from `local` to `remote` at `now`.
+    pub fn generate(&self, local: SocketAddr, remote: SocketAddr, now: Duration)
+        -> SerialNumber<u32>
+    {
+        let clock = (now.as_nanos() / RFC_793_TICK.as_nanos()) as u32;
+        let offset = self.hasher.hash_one((local, remote)) as u32;
+        SerialNumber(clock.wrapping_add(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_793_period() {
+        let clock = IsnClock::rfc_793();
+        assert_eq!(clock.period(), Duration::from_micros(4 << 32));
+        assert_eq!(clock.until_wrap(), clock.period());
+    }
+
+    #[test]
+    fn fast_clock() {
+        let mut clock = IsnClock::with_tick(Duration::from_nanos(1))
+            .with_initial(SerialNumber(u32::MAX - 1));
+        clock.advance(Duration::from_nanos(3));
+        assert_eq!(clock.isn(), SerialNumber(1));
+        clock.advance_ticks(u32::MAX);
+        assert_eq!(clock.isn(), SerialNumber(0));
+        assert_eq!(clock.elapsed(), Duration::from_nanos(3 + u64::from(u32::MAX)));
+    }
+
+    #[test]
+    fn advance_to_aligns_to_tick() {
+        let mut clock = IsnClock::rfc_793();
+        clock.advance(Duration::from_micros(6));
+        assert_eq!(clock.isn(), SerialNumber(1));
+        assert_eq!(clock.advance_to(SerialNumber(1)), Duration::ZERO);
+        assert_eq!(clock.advance_to(SerialNumber(3)), Duration::from_micros(6));
+        assert_eq!(clock.elapsed(), Duration::from_micros(12));
+        assert_eq!(clock.until_wrap(), clock.period() - Duration::from_micros(12));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn generator() {
+        let generator = IsnGenerator::new();
+        let local: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
+        let remote: SocketAddr = "[2001:db8::2]:1024".parse().unwrap();
+
+        let isn = generator.generate(local, remote, Duration::ZERO);
+        assert_eq!(generator.generate(local, remote, Duration::from_micros(3)), isn);
+        assert_eq!(generator.generate(local, remote, Duration::from_micros(4)), isn + 1);
+        assert_eq!(generator.generate(local, remote, RFC_793_TICK * (1 << 16) * (1 << 16)), isn);
+
+        // Another generator uses another key
+        let other = IsnGenerator::new();
+        let isns: Vec<_> = (0..4).map(|port| {
+            let remote = SocketAddr::new(remote.ip(), port);
+            (generator.generate(local, remote, Duration::ZERO), other.generate(local, remote, Duration::ZERO))
+        }).collect();
+        assert!(isns.iter().any(|&(isn, other)| isn != other));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_tick() {
+        IsnClock::with_tick(Duration::ZERO);
+    }
+}
@@ -1,10 +1,208 @@
 #![cfg_attr(feature="clippy", feature(plugin))]
 #![cfg_attr(feature="clippy", plugin(clippy))]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(not(feature = "std"))]
+extern crate core as std;
+// The tests may allocate regardless of the features
+#[cfg(any(feature = "alloc", test))]
+extern crate alloc;
+
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "bytemuck")]
+extern crate bytemuck;
+#[cfg(feature = "bytes")]
+extern crate bytes;
+#[cfg(feature = "diesel")]
+extern crate diesel;
+#[cfg(feature = "num-traits")]
+extern crate num_traits;
+#[cfg(feature = "proptest")]
+#[macro_use]
+extern crate proptest;
+#[cfg(feature = "rand")]
+extern crate rand;
+#[cfg(feature = "rkyv")]
+extern crate rkyv;
+#[cfg(feature = "sqlx")]
+extern crate sqlx;
+#[cfg(feature = "time")]
+extern crate time;
+#[cfg(feature = "codec")]
+extern crate tokio_util;
+#[cfg(feature = "zerocopy")]
+extern crate zerocopy;
+
+use std::error;
 use std::fmt;
-use std::ops::{Add, AddAssign};
+use std::hash::Hash;
+use std::ops::{Add, AddAssign, BitAnd, Sub};
 use std::cmp::Ordering;
-use std::num::Wrapping;
+use std::array::TryFromSliceError;
+use std::convert::TryFrom;
+use std::num::{ParseIntError, TryFromIntError, Wrapping};
+use std::str::FromStr;
+
+#[macro_use]
+#[doc(hidden)]
+pub mod assert;
+pub mod atomic;
+pub mod bits;
+#[cfg(feature = "bytes")]
+pub mod buf;
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(feature = "time")]
+pub mod date;
+pub mod epoch;
+#[cfg(feature = "alloc")]
+pub mod esn;
+pub mod extended;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+#[cfg(feature = "alloc")]
+pub mod gap;
+pub mod gps;
+#[cfg(feature = "alloc")]
+pub mod ipv4;
+pub mod isn;
+#[cfg(feature = "alloc")]
+pub mod map;
+pub mod modular;
+pub mod monotonic;
+pub mod nonzero;
+pub mod ntp;
+#[cfg(feature = "num-traits")]
+pub mod num;
+pub mod order;
+pub mod outstanding;
+#[cfg(feature = "alloc")]
+pub mod packet_id;
+pub mod quic;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod range;
+#[cfg(feature = "alloc")]
+pub mod rangeset;
+#[cfg(feature = "alloc")]
+pub mod reorder;
+#[cfg(feature = "alloc")]
+pub mod replay;
+pub mod rtcp;
+#[cfg(feature = "alloc")]
+pub mod sctp;
+#[cfg(feature = "alloc")]
+pub mod sentinel;
+pub mod seqcount;
+pub mod soa;
+#[cfg(any(feature = "diesel", feature = "sqlx"))]
+pub mod sql;
+pub mod srtp;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+pub mod tcp;
+pub mod versioned;
+#[cfg(feature = "async")]
+pub mod watermark;
+pub mod window;
+pub mod wlsb;
+
+mod bitmap;
+
+pub use bits::{Serial, Serial48};
+pub use modular::ModSerial;
+pub use nonzero::NonZeroSerial;
+pub use range::SerialRange;
+
+/// Operations on the unsigned integer type wrapped by `SerialNumber`.
+///
+/// This is implemented for the built-in unsigned integer types. Implement
+/// it for other integer types (e.g. 24-bit integers) to use them as serial
+/// numbers. Only the operators of `SerialNumber` and `checked_add` are
+/// available for such types.
+///
+/// # Examples
+///
+/// ```
+/// use sna::{SerialNumber, SerialOps};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// struct U24(u32);
+///
+/// impl SerialOps for U24 {
+///     type Signed = i32;
+///
+///     const ZERO: U24 = U24(0);
+///     const HALF: U24 = U24(1 << 23);
+///
+///     fn wrapping_add(self, other: U24) -> U24 {
+///         U24(self.0.wrapping_add(other.0) & 0xff_ffff)
+///     }
+///
+///     fn wrapping_sub(self, other: U24) -> U24 {
+///         U24(self.0.wrapping_sub(other.0) & 0xff_ffff)
+///     }
+///
+///     fn as_signed(self) -> i32 {
+///         // Sign-extend from 24 bits
+///         ((self.0 << 8) as i32) >> 8
+///     }
+/// }
+///
+/// let max = SerialNumber(U24(0xff_ffff));
+/// assert_eq!(max + U24(2), SerialNumber(U24(1)));
+/// assert!(max < SerialNumber(U24(0)));
+/// assert_eq!(SerialNumber(U24(0)) - max, 1);
+/// ```
+pub trait SerialOps: Copy + Ord + fmt::Debug {
+    /// The signed integer type of the same width that subtraction of two
+    /// serial numbers results in.
+    type Signed;
+
+    /// Zero.
+    const ZERO: Self;
+    /// Half of the serial number space (`2^(SERIAL_BITS - 1)`).
+    const HALF: Self;
+
+    /// Addition modulo the largest representable number.
+    fn wrapping_add(self, other: Self) -> Self;
+
+    /// Subtraction modulo the largest representable number.
+    fn wrapping_sub(self, other: Self) -> Self;
+
+    /// Reinterpret the bits as the signed integer type of the same width,
+    /// i.e. in two's complement.
+    fn as_signed(self) -> Self::Signed;
+}
+
+mod uint {
+    use super::*;
+
+    /// Operations on the unsigned integer types wrapped by `SerialNumber`
+    /// that the generic types of this crate build upon.
+    pub trait Uint: SerialOps + Hash + BitAnd<Output = Self> {
+        /// The number of bits of this type (`SERIAL_BITS`).
+        const BITS: u32;
+        /// One.
+        const ONE: Self;
+        /// The largest representable number of this type.
+        const MAX: Self;
+
+        /// Truncate a signed offset modulo the largest representable number.
+        fn from_i64(value: i64) -> Self;
+
+        /// Return a number with the `bits` least significant bits set.
+        fn mask(bits: u32) -> Self;
+
+        /// Widen losslessly.
+        fn to_u128(self) -> u128;
+
+        /// Truncate modulo the largest representable number.
+        fn from_u128(value: u128) -> Self;
+    }
+}
 
 /// Provides an implementation of a Serial Number as defined by
 /// [RFC 1982](https://tools.ietf.org/html/rfc1982).
@@ -15,8 +213,8 @@ use std::num::Wrapping;
 ///   representable number of this type (e.g. it will *wrap* when
 ///   overflowing).
 /// * Partial equality operators are defined but may lead to surprising
-/// results, so make sure you've read
-/// [chapter 3.2 of RFC 1982](https://tools.ietf.org/html/rfc1982#section-3.2).
+///   results, so make sure you've read
+///   [chapter 3.2 of RFC 1982](https://tools.ietf.org/html/rfc1982#section-3.2).
 ///
 /// # Examples
 ///
@@ -24,12 +222,23 @@ use std::num::Wrapping;
 /// use sna::SerialNumber;
 ///
 /// let zero = SerialNumber(0u8);
-/// let one = SerialNumber(1u8);
+/// let max = SerialNumber(255u8);
 ///
-/// assert_eq!(0u8, one + 255u8);
-/// assert!(zero > 255u8);
+/// assert_eq!(0u8, max + 1u8);
+/// assert!(zero > max);
 /// ```
-#[derive(PartialEq, Clone, Copy, Hash)]
+///
+/// The layout is that of `T`, so serial numbers can be part of zero-copy
+/// packet layouts (see the `bytemuck` and `zerocopy` features).
+///
+/// `SerialNumber<u32>` and `SerialNumber<u64>` can be stored in SQL
+/// databases (see the `sql` module).
+#[derive(PartialEq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "zerocopy", derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable,
+                                        zerocopy::KnownLayout, zerocopy::Unaligned))]
+#[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+           diesel(sql_type = diesel::sql_types::BigInt))]
+#[repr(transparent)]
 pub struct SerialNumber<T>(pub T); // TODO: Can we limit this to the types defined below?
 
 impl<T: fmt::Debug> fmt::Debug for SerialNumber<T> {
@@ -68,222 +277,1092 @@ impl<T: fmt::UpperHex> fmt::UpperHex for SerialNumber<T> {
     }
 }
 
+// Safety: `SerialNumber` is `repr(transparent)`, so it has the same layout
+// and bit validity as `T`.
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: bytemuck::Zeroable> bytemuck::Zeroable for SerialNumber<T> {}
+#[cfg(feature = "bytemuck")]
+unsafe impl<T: bytemuck::Pod> bytemuck::Pod for SerialNumber<T> {}
+#[cfg(feature = "bytemuck")]
+unsafe impl<T> bytemuck::TransparentWrapper<T> for SerialNumber<T> {}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for SerialNumber<T> {
+    #[inline]
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        T::arbitrary(u).map(SerialNumber)
+    }
+
+    #[inline]
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        T::size_hint(depth)
+    }
+}
+
+/// A serial number is archived as a serial number of the archived form of
+/// its integer, which has the same layout, so archives can be shared with
+/// code that stores plain integers.
+#[cfg(feature = "rkyv")]
+impl<T: rkyv::Archive> rkyv::Archive for SerialNumber<T> {
+    type Archived = SerialNumber<T::Archived>;
+    type Resolver = T::Resolver;
+
+    #[inline]
+    fn resolve(&self, resolver: T::Resolver, out: rkyv::Place<SerialNumber<T::Archived>>) {
+        // Safety: `SerialNumber` is `repr(transparent)`, so its only field is
+        // at offset zero.
+        let out = unsafe { out.cast_unchecked::<T::Archived>() };
+        self.0.resolve(resolver, out)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, S> rkyv::Serialize<S> for SerialNumber<T>
+    where T: rkyv::Serialize<S>, S: rkyv::rancor::Fallible + ?Sized
+{
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<T::Resolver, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<T, D> rkyv::Deserialize<SerialNumber<T>, D> for SerialNumber<T::Archived>
+    where T: rkyv::Archive, T::Archived: rkyv::Deserialize<T, D>, D: rkyv::rancor::Fallible + ?Sized
+{
+    #[inline]
+    fn deserialize(&self, deserializer: &mut D) -> Result<SerialNumber<T>, D::Error> {
+        self.0.deserialize(deserializer).map(SerialNumber)
+    }
+}
+
+// Safety: `SerialNumber` is `repr(transparent)`, so it has the same layout
+// and bit validity as `T`.
+#[cfg(feature = "rkyv")]
+unsafe impl<T: rkyv::Portable> rkyv::Portable for SerialNumber<T> {}
+#[cfg(feature = "rkyv")]
+unsafe impl<T, C> rkyv::bytecheck::CheckBytes<C> for SerialNumber<T>
+    where T: rkyv::bytecheck::CheckBytes<C>, C: rkyv::rancor::Fallible + ?Sized
+{
+    #[inline]
+    unsafe fn check_bytes(value: *const Self, context: &mut C) -> Result<(), C::Error> {
+        T::check_bytes(value as *const T, context)
+    }
+}
+
+/// The error returned by `SerialNumber::checked_add` if the addend is
+/// outside of the range for which RFC 1982 defines addition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdditionOutOfRange<T> {
+    addend: T,
+}
+
+impl<T: Copy> AdditionOutOfRange<T> {
+    /// Return the rejected addend.
+    #[inline]
+    pub fn addend(&self) -> T {
+        self.addend
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for AdditionOutOfRange<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "addend {} exceeds the range defined by RFC 1982", self.addend)
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> error::Error for AdditionOutOfRange<T> {}
+
+/// The result of comparing two serial numbers as defined by RFC 1982.
+///
+/// Unlike `Option<Ordering>`, the case of two serial numbers that are
+/// exactly half of the serial number space apart has to be handled
+/// explicitly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SerialOrdering {
+    /// The serial number precedes the other one.
+    Less,
+    /// Both serial numbers are equal.
+    Equal,
+    /// The serial number follows the other one.
+    Greater,
+    /// RFC 1982 leaves the comparison undefined.
+    Ambiguous,
+}
+
+impl SerialOrdering {
+    /// Return whether the comparison is undefined.
+    #[inline]
+    pub fn is_ambiguous(self) -> bool {
+        self == SerialOrdering::Ambiguous
+    }
+
+    /// Convert to an `Ordering`, returning `None` if ambiguous.
+    #[inline]
+    pub fn to_ordering(self) -> Option<Ordering> {
+        match self {
+            SerialOrdering::Less => Some(Ordering::Less),
+            SerialOrdering::Equal => Some(Ordering::Equal),
+            SerialOrdering::Greater => Some(Ordering::Greater),
+            SerialOrdering::Ambiguous => None,
+        }
+    }
+}
+
+impl From<Option<Ordering>> for SerialOrdering {
+    #[inline]
+    fn from(ordering: Option<Ordering>) -> Self {
+        match ordering {
+            Some(Ordering::Less) => SerialOrdering::Less,
+            Some(Ordering::Equal) => SerialOrdering::Equal,
+            Some(Ordering::Greater) => SerialOrdering::Greater,
+            None => SerialOrdering::Ambiguous,
+        }
+    }
+}
+
+/// The error returned by `SerialNumber::precedes` and
+/// `SerialNumber::follows` if RFC 1982 leaves the comparison undefined, and
+/// by `order::serial_max` and `order::serial_min` if serial numbers can not
+/// be ordered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ambiguous;
+
+impl fmt::Display for Ambiguous {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("serial numbers are half of the serial number space apart")
+    }
+}
+
+impl error::Error for Ambiguous {}
+
+impl<T> SerialNumber<T> where SerialNumber<T>: PartialOrd {
+    /// Compare `self` to `other`, making the undefined case explicit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{SerialNumber, SerialOrdering};
+    ///
+    /// assert_eq!(SerialNumber(0u8).cmp_serial(&SerialNumber(255)), SerialOrdering::Greater);
+    /// assert_eq!(SerialNumber(0u8).cmp_serial(&SerialNumber(128)), SerialOrdering::Ambiguous);
+    /// ```
+    #[inline]
+    pub fn cmp_serial(&self, other: &Self) -> SerialOrdering {
+        self.partial_cmp(other).into()
+    }
+
+    /// Return whether `self` precedes `other` or an error if the comparison
+    /// is undefined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{Ambiguous, SerialNumber};
+    ///
+    /// assert_eq!(SerialNumber(255u8).precedes(&SerialNumber(0)), Ok(true));
+    /// assert_eq!(SerialNumber(0u8).precedes(&SerialNumber(0)), Ok(false));
+    /// assert_eq!(SerialNumber(0u8).precedes(&SerialNumber(128)), Err(Ambiguous));
+    /// ```
+    #[inline]
+    pub fn precedes(&self, other: &Self) -> Result<bool, Ambiguous> {
+        self.partial_cmp(other).map(|ordering| ordering == Ordering::Less).ok_or(Ambiguous)
+    }
+
+    /// Return whether `self` follows `other` or an error if the comparison
+    /// is undefined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{Ambiguous, SerialNumber};
+    ///
+    /// assert_eq!(SerialNumber(0u8).follows(&SerialNumber(255)), Ok(true));
+    /// assert_eq!(SerialNumber(128u8).follows(&SerialNumber(0)), Err(Ambiguous));
+    /// ```
+    #[inline]
+    pub fn follows(&self, other: &Self) -> Result<bool, Ambiguous> {
+        self.partial_cmp(other).map(|ordering| ordering == Ordering::Greater).ok_or(Ambiguous)
+    }
+}
+
+#[inline]
+fn strict_addend<T: SerialOps>(addend: T) {
+    if cfg!(feature = "strict") {
+        debug_assert!(addend < T::HALF, "addend {:?} exceeds the range defined by RFC 1982", addend);
+    }
+}
+
+impl<T: SerialOps> SerialNumber<T> {
+    /// Apply addition of a positive integer as defined by
+    /// [chapter 3.1 of RFC 1982](https://tools.ietf.org/html/rfc1982#section-3.1).
+    ///
+    /// Returns an error if `addend` is outside of the range
+    /// `[0 .. (2^(SERIAL_BITS - 1) - 1)]` for which addition is defined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(u8::MAX).checked_add(3), Ok(SerialNumber(2)));
+    /// assert!(SerialNumber(0u8).checked_add(u8::MAX).is_err());
+    /// ```
+    #[inline]
+    pub fn checked_add(self, addend: T) -> Result<Self, AdditionOutOfRange<T>> {
+        if addend < T::HALF {
+            Ok(SerialNumber(self.0.wrapping_add(addend)))
+        } else {
+            Err(AdditionOutOfRange { addend })
+        }
+    }
+
+    /// Return the signed shortest distance from `other` to `self`, i.e. how
+    /// far `self` is ahead of `other`.
+    ///
+    /// This is the same as `self - other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(2u16).offset_from(SerialNumber(u16::MAX)), 3);
+    /// assert_eq!(SerialNumber(u16::MAX).offset_from(SerialNumber(2)), -3);
+    /// ```
+    #[inline]
+    pub fn offset_from(self, other: Self) -> T::Signed {
+        self - other
+    }
+
+    /// Return the shortest distance between `self` and `other` in either
+    /// direction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(2u16).abs_distance(SerialNumber(u16::MAX)), 3);
+    /// assert_eq!(SerialNumber(u16::MAX).abs_distance(SerialNumber(2)), 3);
+    /// assert_eq!(SerialNumber(0u16).abs_distance(SerialNumber(1 << 15)), 1 << 15);
+    /// ```
+    #[inline]
+    pub fn abs_distance(self, other: Self) -> T {
+        let ahead = self.0.wrapping_sub(other.0);
+        let behind = other.0.wrapping_sub(self.0);
+        if ahead < behind { ahead } else { behind }
+    }
+
+    /// Return whether `self` is within the window of `size` serial numbers
+    /// starting at `base`, i.e. `base <= self < base + size` modulo the
+    /// largest representable number of this type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// let cursor = SerialNumber(u16::MAX - 1);
+    /// assert!(cursor.is_within(cursor, 1024));
+    /// assert!(SerialNumber(1021u16).is_within(cursor, 1024));
+    /// assert!(!SerialNumber(1022u16).is_within(cursor, 1024));
+    /// assert!(!SerialNumber(u16::MAX - 2).is_within(cursor, 1024));
+    /// assert!(!cursor.is_within(cursor, 0));
+    /// ```
+    #[inline]
+    pub fn is_within(self, base: Self, size: T) -> bool {
+        self.0.wrapping_sub(base.0) < size
+    }
+}
+
+impl<T: uint::Uint> SerialNumber<T> {
+    /// Return the successor of the serial number, i.e. `self + 1` wrapping
+    /// at the largest representable number of this type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(u8::MAX).succ(), SerialNumber(0));
+    /// assert!(SerialNumber(u8::MAX).succ() > SerialNumber(u8::MAX));
+    /// ```
+    #[inline]
+    pub fn succ(self) -> Self {
+        SerialNumber(self.0.wrapping_add(T::ONE))
+    }
+
+    /// Return the predecessor of the serial number, i.e. the serial number
+    /// whose successor is `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(0u8).pred(), SerialNumber(u8::MAX));
+    /// assert_eq!(SerialNumber(7u8).pred().succ(), SerialNumber(7));
+    /// ```
+    #[inline]
+    pub fn pred(self) -> Self {
+        SerialNumber(self.0.wrapping_sub(T::ONE))
+    }
+
+    /// Advance the serial number by `n`, like `self + n`.
+    ///
+    /// RFC 1982 only defines addition for `n` in the range
+    /// `[0 .. (2^(SERIAL_BITS - 1) - 1)]`, in which case the result follows
+    /// `self` (unless `n` is zero). Larger values of `n` wrap, so the result
+    /// may precede `self`. With the `strict` feature, this panics in debug
+    /// builds if `n` exceeds the range (see `checked_add`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// let serial = SerialNumber(250u8);
+    /// assert_eq!(serial.advance(10), SerialNumber(4));
+    /// assert!(serial.advance(127) > serial);
+    /// ```
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        self + n
+    }
+}
+
+impl<T: SerialOps> Add for SerialNumber<T> {
+    type Output = SerialNumber<T>;
+
+    /// Apply addition of a positive integer modulo the largest possible
+    /// representable number of this type (e.g. it will *wrap* when
+    /// overflowing).
+    ///
+    /// With the `strict` feature, this panics in debug builds if the addend
+    /// exceeds the range defined by RFC 1982 (see `checked_add`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(254u8) + SerialNumber(4), 2u8);
+    /// ```
+    #[inline]
+    fn add(self, other: SerialNumber<T>) -> SerialNumber<T> {
+        self + other.0
+    }
+}
+
+impl<T: SerialOps> Add<T> for SerialNumber<T> {
+    type Output = SerialNumber<T>;
+
+    /// Apply addition of a positive integer modulo the largest possible
+    /// representable number of this type (e.g. it will *wrap* when
+    /// overflowing).
+    ///
+    /// With the `strict` feature, this panics in debug builds if the addend
+    /// exceeds the range defined by RFC 1982 (see `checked_add`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(254u8) + 4, 2u8);
+    /// ```
+    #[inline]
+    fn add(self, other: T) -> SerialNumber<T> {
+        strict_addend(other);
+        SerialNumber(self.0.wrapping_add(other))
+    }
+}
+
+impl<T: SerialOps> AddAssign for SerialNumber<T> {
+    /// Performs the `+=` operation modulo the largest possible representable
+    /// number of this type (e.g. it will *wrap* when overflowing).
+    ///
+    /// With the `strict` feature, this panics in debug builds if the addend
+    /// exceeds the range defined by RFC 1982.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// let mut a = SerialNumber(255u8);
+    /// a += SerialNumber(3u8);
+    /// assert_eq!(SerialNumber(2), a);
+    /// ```
+    #[inline]
+    fn add_assign(&mut self, other: SerialNumber<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: SerialOps> AddAssign<T> for SerialNumber<T> {
+    /// Performs the `+=` operation modulo the largest possible representable
+    /// number of this type (e.g. it will *wrap* when overflowing).
+    ///
+    /// With the `strict` feature, this panics in debug builds if the addend
+    /// exceeds the range defined by RFC 1982.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// let mut a = SerialNumber(255u8);
+    /// a += 3u8;
+    /// assert_eq!(SerialNumber(2), a);
+    /// ```
+    #[inline]
+    fn add_assign(&mut self, other: T) {
+        *self = *self + other;
+    }
+}
+
+impl<T: SerialOps> Sub for SerialNumber<T> {
+    type Output = T::Signed;
+
+    /// Return the signed shortest distance from `other` to `self` modulo
+    /// the largest possible representable number of this type, i.e. how far
+    /// `self` is ahead of `other`.
+    ///
+    /// The result is positive if `self > other` and negative if
+    /// `self < other`. If both are exactly half of the serial number space
+    /// apart (the case where RFC 1982 leaves the comparison undefined), the
+    /// result is always the smallest representable signed number,
+    /// regardless of the order of the operands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(1u8) - SerialNumber(u8::MAX), 2);
+    /// assert_eq!(SerialNumber(u8::MAX) - SerialNumber(1u8), -2);
+    /// ```
+    #[inline]
+    fn sub(self, other: SerialNumber<T>) -> T::Signed {
+        self.0.wrapping_sub(other.0).as_signed()
+    }
+}
+
+impl<T: SerialOps> Sub<T> for SerialNumber<T> {
+    type Output = T::Signed;
+
+    /// Return the signed shortest distance from `other` of this integer type
+    /// to `self`.
+    ///
+    /// See `SerialNumber - SerialNumber`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(0u8) - u8::MAX, 1);
+    /// ```
+    #[inline]
+    fn sub(self, other: T) -> T::Signed {
+        self - SerialNumber(other)
+    }
+}
+
+impl<T: SerialOps> PartialEq<T> for SerialNumber<T> {
+    /// Test if `self` and `other` of this integer type are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// assert_eq!(SerialNumber(42u8), 42u8);
+    /// ```
+    #[inline]
+    fn eq(&self, other: &T) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Implement a binary operator for all combinations of references of the
+/// operands, forwarding to the implementation for values.
+macro_rules! forward_ref_binop {
+    (impl $imp:ident, $method:ident for $t:ty, $u:ty) => {
+        impl<'a, T: SerialOps> $imp<$u> for &'a $t {
+            type Output = <$t as $imp<$u>>::Output;
+
+            #[inline]
+            fn $method(self, other: $u) -> Self::Output {
+                $imp::$method(*self, other)
+            }
+        }
+
+        impl<'a, T: SerialOps> $imp<&'a $u> for $t {
+            type Output = <$t as $imp<$u>>::Output;
+
+            #[inline]
+            fn $method(self, other: &'a $u) -> Self::Output {
+                $imp::$method(self, *other)
+            }
+        }
+
+        impl<'a, 'b, T: SerialOps> $imp<&'a $u> for &'b $t {
+            type Output = <$t as $imp<$u>>::Output;
+
+            #[inline]
+            fn $method(self, other: &'a $u) -> Self::Output {
+                $imp::$method(*self, *other)
+            }
+        }
+    };
+}
+
+forward_ref_binop!(impl Add, add for SerialNumber<T>, SerialNumber<T>);
+forward_ref_binop!(impl Add, add for SerialNumber<T>, T);
+forward_ref_binop!(impl Sub, sub for SerialNumber<T>, SerialNumber<T>);
+forward_ref_binop!(impl Sub, sub for SerialNumber<T>, T);
+
+impl<'a, T: SerialOps> AddAssign<&'a SerialNumber<T>> for SerialNumber<T> {
+    #[inline]
+    fn add_assign(&mut self, other: &'a SerialNumber<T>) {
+        *self += *other;
+    }
+}
+
+impl<'a, T: SerialOps> AddAssign<&'a T> for SerialNumber<T> {
+    #[inline]
+    fn add_assign(&mut self, other: &'a T) {
+        *self += *other;
+    }
+}
+
+impl<'a, T: SerialOps> PartialEq<&'a SerialNumber<T>> for SerialNumber<T> {
+    #[inline]
+    fn eq(&self, other: &&'a SerialNumber<T>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<'a, T: SerialOps> PartialOrd<&'a SerialNumber<T>> for SerialNumber<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &&'a SerialNumber<T>) -> Option<Ordering> {
+        self.partial_cmp(*other)
+    }
+}
+
+impl<T> From<Wrapping<T>> for SerialNumber<T> {
+    /// Convert from a `Wrapping` into a `SerialNumber`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use std::num::Wrapping;
+    ///
+    /// assert_eq!(SerialNumber::from(Wrapping(42u8)), SerialNumber(42u8));
+    /// ```
+    #[inline]
+    fn from(number: Wrapping<T>) -> Self {
+        SerialNumber(number.0)
+    }
+}
+
+impl<T> From<SerialNumber<T>> for Wrapping<T> {
+    /// Convert from a `SerialNumber` into a `Wrapping`, e.g. to apply
+    /// arithmetic that is not defined for serial numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use std::num::Wrapping;
+    ///
+    /// assert_eq!(Wrapping::from(SerialNumber(255u8)) * Wrapping(2), Wrapping(254));
+    /// ```
+    #[inline]
+    fn from(number: SerialNumber<T>) -> Self {
+        Wrapping(number.0)
+    }
+}
+
+impl<T: SerialOps> Add<Wrapping<T>> for SerialNumber<T> {
+    type Output = SerialNumber<T>;
+
+    /// Apply addition of a positive integer modulo the largest possible
+    /// representable number of this type, like `SerialNumber + T`.
+    ///
+    /// As `Wrapping` asks for wrapping arithmetic, the addend is not
+    /// checked against the range defined by RFC 1982, even with the
+    /// `strict` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use std::num::Wrapping;
+    ///
+    /// assert_eq!(SerialNumber(254u8) + Wrapping(4), 2u8);
+    /// assert_eq!(SerialNumber(254u8) + Wrapping(255), 253u8);
+    /// ```
+    #[inline]
+    fn add(self, other: Wrapping<T>) -> SerialNumber<T> {
+        SerialNumber(self.0.wrapping_add(other.0))
+    }
+}
+
+impl<T: SerialOps> AddAssign<Wrapping<T>> for SerialNumber<T> {
+    /// Performs the `+=` operation modulo the largest possible representable
+    /// number of this type without checking the addend (see
+    /// `SerialNumber + Wrapping<T>`).
+    #[inline]
+    fn add_assign(&mut self, other: Wrapping<T>) {
+        *self = *self + other;
+    }
+}
+
+impl<T: SerialOps> PartialOrd for SerialNumber<T> {
+    /// Return an ordering between `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use std::cmp::Ordering;
+    ///
+    /// let zero = SerialNumber(0u8);
+    /// let u8_max = SerialNumber(255u8);
+    ///
+    /// assert_eq!(zero.partial_cmp(&zero), Some(Ordering::Equal));
+    /// assert_eq!(zero.partial_cmp(&u8_max), Some(Ordering::Greater));
+    /// ```
+    #[inline]
+    fn partial_cmp(&self, other: &SerialNumber<T>) -> Option<Ordering> {
+        let distance = other.0.wrapping_sub(self.0);
+        if distance == T::ZERO {
+            Some(Ordering::Equal)
+        } else if distance < T::HALF {
+            Some(Ordering::Less)
+        } else if distance > T::HALF {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+
+    // The operators each need a single wrapping subtraction and comparison
+    // against half of the serial number space: `self` precedes `other` if
+    // `other` is less than half of the serial number space ahead, i.e. if
+    // `self - other` exceeds half of it.
+
+    #[inline]
+    fn lt(&self, other: &SerialNumber<T>) -> bool {
+        self.0.wrapping_sub(other.0) > T::HALF
+    }
+
+    #[inline]
+    fn le(&self, other: &SerialNumber<T>) -> bool {
+        other.0.wrapping_sub(self.0) < T::HALF
+    }
+
+    #[inline]
+    fn gt(&self, other: &SerialNumber<T>) -> bool {
+        other.0.wrapping_sub(self.0) > T::HALF
+    }
+
+    #[inline]
+    fn ge(&self, other: &SerialNumber<T>) -> bool {
+        self.0.wrapping_sub(other.0) < T::HALF
+    }
+}
+
+impl<T: SerialOps> PartialOrd<T> for SerialNumber<T> {
+    /// Return an ordering between `self` and `other` of this integer type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use std::cmp::Ordering;
+    ///
+    /// let zero = SerialNumber(0u8);
+    ///
+    /// assert_eq!(zero.partial_cmp(&0u8), Some(Ordering::Equal));
+    /// assert_eq!(zero.partial_cmp(&255u8), Some(Ordering::Greater));
+    /// ```
+    #[inline]
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        self.partial_cmp(&SerialNumber(*other))
+    }
+}
+
 macro_rules! uint_half {
     ($x:expr) => (1 << ($x-1));
 }
 
 macro_rules! uint_impl {
-    ($m:ident, $T:ty, $BITS:expr) => {
-        impl From<$T> for SerialNumber<$T> {
-            /// Convert from this integer type into a `SerialNumber`.
+    ($m:ident, $T:ty, $S:ty, $BITS:expr) => {
+        impl SerialOps for $T {
+            type Signed = $S;
+
+            const ZERO: $T = 0;
+            const HALF: $T = uint_half!($BITS);
+
+            #[inline]
+            fn wrapping_add(self, other: $T) -> $T {
+                self.wrapping_add(other)
+            }
+
+            #[inline]
+            fn wrapping_sub(self, other: $T) -> $T {
+                self.wrapping_sub(other)
+            }
+
+            #[inline]
+            fn as_signed(self) -> $S {
+                self as $S
+            }
+        }
+
+        impl uint::Uint for $T {
+            const BITS: u32 = $BITS;
+            const ONE: $T = 1;
+            const MAX: $T = <$T>::max_value();
+
+            #[inline]
+            fn from_i64(value: i64) -> $T {
+                value as $T
+            }
+
+            #[inline]
+            fn mask(bits: u32) -> $T {
+                if bits >= $BITS { <$T>::max_value() } else { (1 << bits) - 1 }
+            }
+
+            #[inline]
+            fn to_u128(self) -> u128 {
+                self as u128
+            }
+
+            #[inline]
+            fn from_u128(value: u128) -> $T {
+                value as $T
+            }
+        }
+
+        impl SerialNumber<$T> {
+            /// Zero, which is also the `Default`.
+            pub const ZERO: Self = SerialNumber(0);
+            /// The largest representable serial number of this type.
+            pub const MAX: Self = SerialNumber(<$T>::max_value());
+            /// Half of the serial number space (`2^(SERIAL_BITS - 1)`), i.e.
+            /// the serial number that cannot be compared to `ZERO`.
+            pub const HALF: Self = SerialNumber(uint_half!($BITS));
+
+            /// Create a serial number from `value`.
+            ///
+            /// Unlike `SerialNumber(value)` and `value.into()`, this infers
+            /// the integer type from the serial number type.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(SerialNumber::from(255u8), SerialNumber(255u8));
+            #[doc = concat!("const INITIAL: SerialNumber<", stringify!($T), "> = ",
+                            "SerialNumber::<", stringify!($T), ">::new(1);")]
+            /// assert_eq!(INITIAL, 1);
             /// ```
             #[inline]
-            fn from(number: $T) -> Self {
-                SerialNumber(number)
+            pub const fn new(value: $T) -> Self {
+                SerialNumber(value)
             }
-        }
 
-        impl From<SerialNumber<$T>> for $T {
-            /// Convert from a `SerialNumber` into this integer type.
+            /// Return the value of the serial number.
+            #[inline]
+            pub const fn get(self) -> $T {
+                self.0
+            }
+
+            /// Apply addition of a positive integer modulo the largest
+            /// representable number of this type, like `+` but usable in
+            /// `const` contexts.
+            ///
+            /// Unlike `+`, this does not check the addend with the `strict`
+            /// feature (see `checked_add`).
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(<u8>::from(SerialNumber(42u8)), 42u8);
+            #[doc = concat!("const LAST: SerialNumber<", stringify!($T), "> = ",
+                            "SerialNumber::<", stringify!($T), ">::MAX.wrapping_add(2);")]
+            /// assert_eq!(LAST, 1);
             /// ```
             #[inline]
-            fn from(number: SerialNumber<$T>) -> Self {
-                number.0
+            pub const fn wrapping_add(self, addend: $T) -> Self {
+                SerialNumber(self.0.wrapping_add(addend))
             }
-        }
 
-        impl Add for SerialNumber<$T> {
-            type Output = SerialNumber<$T>;
-
-            /// Apply addition of a positive integer modulo the largest
-            /// possible representable number of this type (e.g. it
-            /// will *wrap* when overflowing).
+            /// Return an ordering between `self` and `other` like
+            /// `partial_cmp` but usable in `const` contexts.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(SerialNumber(254u8) + SerialNumber(4), 2u8);
+            /// use std::cmp::Ordering;
+            ///
+            #[doc = concat!("const WRAPS: bool = matches!(SerialNumber::<", stringify!($T), ">::MAX",
+                            ".compare(SerialNumber::<", stringify!($T), ">::ZERO), ",
+                            "Some(Ordering::Less));")]
+            /// assert!(WRAPS);
             /// ```
             #[inline]
-            fn add(self, other: SerialNumber<$T>) -> SerialNumber<$T> {
-                SerialNumber((Wrapping(self.0) + Wrapping(other.0)).0)
+            pub const fn compare(self, other: Self) -> Option<Ordering> {
+                // Same formulation as `partial_cmp`
+                let distance = other.0.wrapping_sub(self.0);
+                if distance == 0 {
+                    Some(Ordering::Equal)
+                } else if distance < uint_half!($BITS) {
+                    Some(Ordering::Less)
+                } else if distance > uint_half!($BITS) {
+                    Some(Ordering::Greater)
+                } else {
+                    None
+                }
             }
         }
 
-        impl Add<$T> for SerialNumber<$T> {
-            type Output = SerialNumber<$T>;
+        impl SerialNumber<$T> {
+            /// Reinterpret the bits of a signed integer of the same width as
+            /// a serial number, e.g. for wire formats and C APIs that hand
+            /// sequence numbers over as signed integers.
+            ///
+            /// Negative values map to the upper half of the serial number
+            /// space.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// # use sna::SerialNumber;
+            #[doc = concat!("assert_eq!(SerialNumber::<", stringify!($T), ">::from_signed(-1), ",
+                            "SerialNumber(", stringify!($T), "::MAX));")]
+            /// ```
+            #[inline]
+            pub fn from_signed(value: $S) -> Self {
+                SerialNumber(value as $T)
+            }
 
-            /// Apply addition of a positive integer modulo the largest
-            /// possible representable number of this type (e.g. it
-            /// will *wrap* when overflowing).
+            /// Reinterpret the bits of the serial number as a signed
+            /// integer of the same width.
+            ///
+            /// Serial numbers in the upper half of the serial number space
+            /// map to negative values.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(SerialNumber(254u8) + 4, 2u8);
+            #[doc = concat!("assert_eq!(SerialNumber(", stringify!($T), "::MAX).as_signed(), -1);")]
             /// ```
             #[inline]
-            fn add(self, other: $T) -> SerialNumber<$T> {
-                SerialNumber((Wrapping(self.0) + Wrapping(other)).0)
+            pub fn as_signed(self) -> $S {
+                self.0 as $S
             }
         }
 
-        impl Add<SerialNumber<$T>> for $T {
-            type Output = SerialNumber<$T>;
-
-            /// Apply addition of a positive integer modulo the largest
-            /// possible representable number of this type (e.g. it
-            /// will *wrap* when overflowing).
+        impl SerialNumber<$T> {
+            /// Create a serial number from its big-endian representation,
+            /// i.e. in network byte order.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(254u8 + SerialNumber(4), 2u8);
+            #[doc = concat!("let bytes = (", stringify!($T), "::MAX - 1).to_be_bytes();")]
+            #[doc = concat!("assert_eq!(SerialNumber::<", stringify!($T), ">::from_be_bytes(bytes), ",
+                            "SerialNumber(", stringify!($T), "::MAX - 1));")]
             /// ```
             #[inline]
-            fn add(self, other: SerialNumber<$T>) -> SerialNumber<$T> {
-                other + self
+            pub fn from_be_bytes(bytes: [u8; std::mem::size_of::<$T>()]) -> Self {
+                SerialNumber(<$T>::from_be_bytes(bytes))
+            }
+
+            /// Create a serial number from its little-endian representation.
+            #[inline]
+            pub fn from_le_bytes(bytes: [u8; std::mem::size_of::<$T>()]) -> Self {
+                SerialNumber(<$T>::from_le_bytes(bytes))
             }
-        }
 
-        impl AddAssign for SerialNumber<$T> {
-            /// Performs the `+=` operation modulo the largest possible
-            /// representable number of this type (e.g. it will *wrap*
-            /// when overflowing).
+            /// Create a serial number from its big-endian representation in
+            /// `bytes`.
+            ///
+            /// Returns an error if the length of `bytes` does not match the
+            /// size of the serial number.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// let mut a = SerialNumber(255u8);
-            /// a += SerialNumber(3u8);
-            /// assert_eq!(SerialNumber(2), a);
+            #[doc = concat!("let packet = [0; 2 + std::mem::size_of::<", stringify!($T), ">()];")]
+            #[doc = concat!("assert_eq!(SerialNumber::<", stringify!($T), ">::from_be_slice(&packet[2..]).ok(), ",
+                            "Some(SerialNumber(0)));")]
+            #[doc = concat!("assert!(SerialNumber::<", stringify!($T), ">::from_be_slice(&packet[1..]).is_err());")]
             /// ```
             #[inline]
-            fn add_assign(&mut self, other: SerialNumber<$T>) {
-                *self = SerialNumber((Wrapping(self.0) + Wrapping(other.0)).0);
+            pub fn from_be_slice(bytes: &[u8]) -> Result<Self, TryFromSliceError> {
+                <[u8; std::mem::size_of::<$T>()]>::try_from(bytes).map(Self::from_be_bytes)
+            }
+
+            /// Create a serial number from its little-endian representation
+            /// in `bytes`.
+            ///
+            /// Returns an error if the length of `bytes` does not match the
+            /// size of the serial number.
+            #[inline]
+            pub fn from_le_slice(bytes: &[u8]) -> Result<Self, TryFromSliceError> {
+                <[u8; std::mem::size_of::<$T>()]>::try_from(bytes).map(Self::from_le_bytes)
+            }
+
+            /// Return the big-endian representation of the serial number,
+            /// i.e. in network byte order.
+            #[inline]
+            pub fn to_be_bytes(self) -> [u8; std::mem::size_of::<$T>()] {
+                self.0.to_be_bytes()
+            }
+
+            /// Return the little-endian representation of the serial
+            /// number.
+            #[inline]
+            pub fn to_le_bytes(self) -> [u8; std::mem::size_of::<$T>()] {
+                self.0.to_le_bytes()
             }
         }
 
-        impl AddAssign<$T> for SerialNumber<$T> {
-            /// Performs the `+=` operation modulo the largest possible
-            /// representable number of this type (e.g. it will *wrap*
-            /// when overflowing).
+        impl TryFrom<$S> for SerialNumber<$T> {
+            type Error = TryFromIntError;
+
+            /// Convert from a signed integer of the same width if it is not
+            /// negative.
+            ///
+            /// Unlike `from_signed`, this preserves the numeric value and
+            /// fails for negative values instead of reinterpreting their
+            /// bits.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// let mut a = SerialNumber(255u8);
-            /// a += 3u8;
-            /// assert_eq!(SerialNumber(2), a);
+            /// use std::convert::TryFrom;
+            ///
+            #[doc = concat!("assert_eq!(SerialNumber::<", stringify!($T), ">::try_from(42", stringify!($S),
+                            "), Ok(SerialNumber(42)));")]
+            #[doc = concat!("assert!(SerialNumber::<", stringify!($T), ">::try_from(-1",
+                            stringify!($S), ").is_err());")]
             /// ```
             #[inline]
-            fn add_assign(&mut self, other: $T) {
-                *self = SerialNumber((Wrapping(self.0) + Wrapping(other)).0);
+            fn try_from(value: $S) -> Result<Self, TryFromIntError> {
+                <$T>::try_from(value).map(SerialNumber)
             }
         }
 
-        impl PartialEq<$T> for SerialNumber<$T> {
-            /// Test if `self` and `other` of this integer type are
-            /// equal.
+        impl FromStr for SerialNumber<$T> {
+            type Err = ParseIntError;
+
+            /// Parse a serial number from a decimal or a `0x`-prefixed
+            /// hexadecimal string.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(SerialNumber(42u8), 42u8);
+            #[doc = concat!("assert_eq!(\"42\".parse(), Ok(SerialNumber(42", stringify!($T), ")));")]
+            #[doc = concat!("assert_eq!(\"0x2a\".parse(), Ok(SerialNumber(42", stringify!($T), ")));")]
+            #[doc = concat!("assert!(\"-1\".parse::<SerialNumber<", stringify!($T), ">>().is_err());")]
             /// ```
-            #[inline]
-            fn eq(&self, other: &$T) -> bool {
-                self.eq(&SerialNumber(*other))
+            fn from_str(value: &str) -> Result<Self, ParseIntError> {
+                match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+                    Some(hex) => <$T>::from_str_radix(hex, 16),
+                    None => value.parse(),
+                }.map(SerialNumber)
             }
         }
 
-        impl PartialEq<SerialNumber<$T>> for $T {
-            /// Test if `self` and `other` of type `SerialNumber` are
-            /// equal.
+        impl From<$T> for SerialNumber<$T> {
+            /// Convert from this integer type into a `SerialNumber`.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// assert_eq!(42u8, SerialNumber(42u8));
+            /// assert_eq!(SerialNumber::from(255u8), SerialNumber(255u8));
             /// ```
             #[inline]
-            fn eq(&self, other: &SerialNumber<$T>) -> bool {
-                other.eq(self)
+            fn from(number: $T) -> Self {
+                SerialNumber(number)
             }
         }
 
-        impl PartialOrd for SerialNumber<$T> {
-            /// Return an ordering between `self` and `other`.
+        impl From<SerialNumber<$T>> for $T {
+            /// Convert from a `SerialNumber` into this integer type.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// use std::cmp::Ordering;
+            /// assert_eq!(<u8>::from(SerialNumber(42u8)), 42u8);
+            /// ```
+            #[inline]
+            fn from(number: SerialNumber<$T>) -> Self {
+                number.0
+            }
+        }
+
+        impl Add<SerialNumber<$T>> for $T {
+            type Output = SerialNumber<$T>;
+
+            /// Apply addition of a positive integer modulo the largest
+            /// possible representable number of this type (e.g. it
+            /// will *wrap* when overflowing).
             ///
-            /// let zero = SerialNumber(0u8);
-            /// let u8_max = SerialNumber(255u8);
+            /// With the `strict` feature, this panics in debug builds if
+            /// the addend exceeds the range defined by RFC 1982 (see
+            /// `checked_add`).
+            ///
+            /// # Examples
             ///
-            /// assert_eq!(zero.partial_cmp(&zero), Some(Ordering::Equal));
-            /// assert_eq!(zero.partial_cmp(&u8_max), Some(Ordering::Greater));
+            /// ```
+            /// # use sna::SerialNumber;
+            /// assert_eq!(4u8 + SerialNumber(254), 2u8);
             /// ```
             #[inline]
-            fn partial_cmp(&self, other: &SerialNumber<$T>) -> Option<Ordering> {
-                if self.0 == other.0 {
-                    Some(Ordering::Equal)
-                } else if
-                    (self.0 < other.0 && (other.0 - self.0) < uint_half!($BITS)) ||
-                    (self.0 > other.0 && (self.0 - other.0) > uint_half!($BITS))
-                {
-                    Some(Ordering::Less)
-                } else if
-                    (self.0 < other.0 && (other.0 - self.0) > uint_half!($BITS)) ||
-                    (self.0 > other.0 && (self.0 - other.0) < uint_half!($BITS))
-                {
-                    Some(Ordering::Greater)
-                } else {
-                    None
-                }
+            fn add(self, other: SerialNumber<$T>) -> SerialNumber<$T> {
+                other + self
             }
         }
 
-        impl PartialOrd<$T> for SerialNumber<$T> {
-            /// Return an ordering between `self` and `other` of this
-            /// integer type.
+        impl Sub<SerialNumber<$T>> for $T {
+            type Output = $S;
+
+            /// Return the signed shortest distance from `other` of type
+            /// `SerialNumber` to `self`.
+            ///
+            /// See `SerialNumber - SerialNumber`.
             ///
             /// # Examples
             ///
             /// ```
             /// # use sna::SerialNumber;
-            /// use std::cmp::Ordering;
+            #[doc = concat!("assert_eq!(", stringify!($T), "::MAX - SerialNumber(0",
+                            stringify!($T), "), -1);")]
+            /// ```
+            #[inline]
+            fn sub(self, other: SerialNumber<$T>) -> $S {
+                SerialNumber(self) - other
+            }
+        }
+
+        impl PartialEq<SerialNumber<$T>> for $T {
+            /// Test if `self` and `other` of type `SerialNumber` are
+            /// equal.
             ///
-            /// let zero = SerialNumber(0u8);
+            /// # Examples
             ///
-            /// assert_eq!(zero.partial_cmp(&0u8), Some(Ordering::Equal));
-            /// assert_eq!(zero.partial_cmp(&255u8), Some(Ordering::Greater));
+            /// ```
+            /// # use sna::SerialNumber;
+            /// assert_eq!(42u8, SerialNumber(42u8));
             /// ```
             #[inline]
-            fn partial_cmp(&self, other: &$T) -> Option<Ordering> {
-                self.partial_cmp(&SerialNumber(*other))
+            fn eq(&self, other: &SerialNumber<$T>) -> bool {
+                other.eq(self)
             }
         }
 
@@ -330,19 +1409,95 @@ macro_rules! uint_impl {
                 assert_eq!(<$T>::max_value(), value);
             }
 
+            #[test]
+            fn signed() {
+                let max = SerialNumber(<$T>::max_value());
+                assert_eq!(SerialNumber::<$T>::from_signed(-1), max);
+                assert_eq!(max.as_signed(), -1);
+                assert_eq!(SerialNumber::<$T>::from_signed(<$S>::min_value()),
+                           SerialNumber(uint_half!($BITS)));
+                assert_eq!(SerialNumber::<$T>::from_signed(<$S>::max_value()).as_signed(),
+                           <$S>::max_value());
+                assert_eq!(SerialNumber::<$T>::try_from(<$S>::max_value()),
+                           Ok(SerialNumber(<$S>::max_value() as $T)));
+                assert!(SerialNumber::<$T>::try_from(<$S>::min_value()).is_err());
+            }
+
+            #[test]
+            fn checked_add() {
+                let max = SerialNumber(<$T>::max_value());
+                let half_minus_one = uint_half!($BITS) - 1;
+                assert_eq!(max.checked_add(0), Ok(max));
+                assert_eq!(max.checked_add(half_minus_one), Ok(SerialNumber(half_minus_one - 1)));
+                let error = max.checked_add(uint_half!($BITS)).unwrap_err();
+                assert_eq!(error.addend(), uint_half!($BITS));
+            }
+
             #[test]
             fn add() {
+                assert_eq!(0, SerialNumber(<$T>::max_value()) + SerialNumber(1));
+                assert_eq!(0, 1 + SerialNumber(<$T>::max_value()));
+                assert_eq!(0, SerialNumber(<$T>::max_value()) + 1);
+            }
+
+            #[test]
+            #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+            fn add_serial_out_of_range() {
                 assert_eq!(0, SerialNumber(1) + SerialNumber(<$T>::max_value()));
+            }
+
+            #[test]
+            #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+            fn add_to_uint_out_of_range() {
                 assert_eq!(0, <$T>::max_value() + SerialNumber(1));
+            }
+
+            #[test]
+            #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+            fn add_uint_out_of_range() {
                 assert_eq!(0, SerialNumber(1) + <$T>::max_value());
             }
 
+            #[test]
+            fn sub() {
+                let zero: SerialNumber<$T> = 0.into();
+                let half: SerialNumber<$T> = uint_half!($BITS).into();
+                let max = SerialNumber(<$T>::max_value());
+                assert_eq!(zero - zero, 0);
+                assert_eq!(zero - max, 1);
+                assert_eq!(max - zero, -1);
+                assert_eq!(zero - 1, -1);
+                assert_eq!(1 - zero, 1);
+                assert_eq!(half - 1, <$S>::max_value());
+                assert_eq!(SerialNumber(1) - half, <$S>::min_value() + 1);
+
+                // Ambiguous
+                assert_eq!(half - zero, <$S>::min_value());
+                assert_eq!(zero - half, <$S>::min_value());
+            }
+
             #[test]
             fn add_assign() {
+                let mut a = SerialNumber(<$T>::max_value());
+                a += SerialNumber(1);
+                assert_eq!(SerialNumber(0), a);
+
+                let mut a = SerialNumber(<$T>::max_value());
+                a += uint_half!($BITS) - 1;
+                assert_eq!(SerialNumber(uint_half!($BITS) - 2), a);
+            }
+
+            #[test]
+            #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+            fn add_assign_serial_out_of_range() {
                 let mut a = SerialNumber(<$T>::max_value());
                 a += SerialNumber(<$T>::max_value());
                 assert_eq!(SerialNumber(<$T>::max_value() - 1), a);
+            }
 
+            #[test]
+            #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+            fn add_assign_uint_out_of_range() {
                 let mut a = SerialNumber(<$T>::max_value());
                 a += <$T>::max_value();
                 assert_eq!(SerialNumber(<$T>::max_value() - 1), a);
@@ -392,20 +1547,89 @@ macro_rules! uint_impl {
                 assert_eq!(1.partial_cmp(&(half + 1)), None);
                 assert_eq!(half_minus_one.partial_cmp(&max), None);
             }
+
+            #[test]
+            fn bytes() {
+                let serial = SerialNumber(<$T>::max_value() - 1);
+                assert_eq!(SerialNumber::<$T>::from_be_bytes(serial.to_be_bytes()), serial);
+                assert_eq!(SerialNumber::<$T>::from_le_bytes(serial.to_le_bytes()), serial);
+                assert_eq!(serial.to_be_bytes().last(), Some(&0xfe));
+                assert_eq!(serial.to_le_bytes()[0], 0xfe);
+                assert_eq!(SerialNumber::<$T>::from_be_slice(&serial.to_be_bytes()).ok(), Some(serial));
+                assert_eq!(SerialNumber::<$T>::from_le_slice(&serial.to_le_bytes()).ok(), Some(serial));
+                assert!(SerialNumber::<$T>::from_be_slice(&[]).is_err());
+            }
+
+            #[test]
+            fn constants() {
+                const INITIAL: SerialNumber<$T> = SerialNumber::<$T>::MAX.wrapping_add(3);
+                const BEFORE_HALF: Option<Ordering> = SerialNumber::<$T>::ZERO
+                    .compare(SerialNumber::<$T>::HALF.wrapping_add(1));
+                assert_eq!(INITIAL, SerialNumber::<$T>::new(2));
+                assert_eq!(INITIAL.get(), 2);
+                assert_eq!(BEFORE_HALF, Some(Ordering::Greater));
+                assert_eq!(SerialNumber::<$T>::ZERO.compare(SerialNumber::<$T>::HALF), None);
+                assert_eq!(SerialNumber::<$T>::default(), SerialNumber::<$T>::ZERO);
+                assert_eq!(SerialNumber::<$T>::HALF, uint_half!($BITS));
+            }
+
+            #[test]
+            fn cmp_serial() {
+                let zero: SerialNumber<$T> = 0.into();
+                let half: SerialNumber<$T> = uint_half!($BITS).into();
+                let max = SerialNumber(<$T>::max_value());
+
+                assert_eq!(max.cmp_serial(&max), SerialOrdering::Equal);
+                assert_eq!(max.cmp_serial(&zero), SerialOrdering::Less);
+                assert_eq!(zero.cmp_serial(&max), SerialOrdering::Greater);
+                assert_eq!(zero.cmp_serial(&half), SerialOrdering::Ambiguous);
+
+                assert_eq!(max.precedes(&zero), Ok(true));
+                assert_eq!(zero.precedes(&max), Ok(false));
+                assert_eq!(half.precedes(&zero), Err(Ambiguous));
+                assert_eq!(zero.follows(&max), Ok(true));
+                assert_eq!(zero.follows(&zero), Ok(false));
+                assert_eq!(zero.follows(&half), Err(Ambiguous));
+            }
         }
     };
 }
 
-// Add implementations for u8, u16, u32 and u64
-uint_impl!(u8, u8, 8);
-uint_impl!(u16, u16, 16);
-uint_impl!(u32, u32, 32);
-uint_impl!(u64, u64, 64);
+macro_rules! try_from_impl {
+    ($T:ty, $($U:ty),+) => {$(
+        impl TryFrom<$U> for SerialNumber<$T> {
+            type Error = TryFromIntError;
+
+            /// Convert from a wider integer type if the value fits.
+            #[inline]
+            fn try_from(value: $U) -> Result<Self, TryFromIntError> {
+                <$T>::try_from(value).map(SerialNumber)
+            }
+        }
+    )+};
+}
+
+// Add implementations for u8, u16, u32, u64, u128 and usize (and their signed counterparts)
+uint_impl!(u8, u8, i8, 8);
+uint_impl!(u16, u16, i16, 16);
+uint_impl!(u32, u32, i32, 32);
+uint_impl!(u64, u64, i64, 64);
+uint_impl!(u128, u128, i128, 128);
+uint_impl!(usize, usize, isize, usize::BITS);
+
+// Add fallible conversions from wider integer types
+try_from_impl!(u8, u64, u128);
+try_from_impl!(u16, u64, u128);
+try_from_impl!(u32, u64, u128);
+try_from_impl!(u64, u128);
+try_from_impl!(usize, u64, u128);
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use alloc::format;
+
     #[test]
     fn cmp() {
         assert!(SerialNumber(1u8) > 0);
@@ -420,9 +1644,152 @@ mod tests {
         assert!(SerialNumber(44u8) > 200);
     }
 
+    #[test]
+    fn cmp_exhaustive() {
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let distance = b.wrapping_sub(a);
+                let expected = match distance {
+                    0 => Some(Ordering::Equal),
+                    1..=127 => Some(Ordering::Less),
+                    128 => None,
+                    _ => Some(Ordering::Greater),
+                };
+                let (a, b) = (SerialNumber(a), SerialNumber(b));
+                assert_eq!(a.partial_cmp(&b), expected);
+                assert_eq!(a < b, expected == Some(Ordering::Less));
+                assert_eq!(a <= b, expected == Some(Ordering::Less) || expected == Some(Ordering::Equal));
+                assert_eq!(a > b, expected == Some(Ordering::Greater));
+                assert_eq!(a >= b, expected == Some(Ordering::Greater) || expected == Some(Ordering::Equal));
+            }
+        }
+    }
+
     #[test]
     fn fmt() {
         assert_eq!(format!("{}", SerialNumber(33u8)), "33");
         assert_eq!(format!("{:?}", SerialNumber(33u8)), "33");
     }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("65535".parse(), Ok(SerialNumber(u16::MAX)));
+        assert_eq!("0xFFFF".parse(), Ok(SerialNumber(u16::MAX)));
+        assert_eq!("0Xff".parse(), Ok(SerialNumber(255u16)));
+        assert!("65536".parse::<SerialNumber<u16>>().is_err());
+        assert!("0x".parse::<SerialNumber<u16>>().is_err());
+        assert!("ff".parse::<SerialNumber<u16>>().is_err());
+    }
+
+    #[test]
+    fn try_from_wider() {
+        assert_eq!(SerialNumber::<u8>::try_from(255u64), Ok(SerialNumber(255)));
+        assert!(SerialNumber::<u8>::try_from(256u64).is_err());
+        assert_eq!(SerialNumber::<u32>::try_from(u32::MAX as u128), Ok(SerialNumber(u32::MAX)));
+        assert!(SerialNumber::<u64>::try_from(u64::MAX as u128 + 1).is_err());
+        assert_eq!(SerialNumber::<usize>::try_from(7u64), Ok(SerialNumber(7)));
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut u = Unstructured::new(&[0xff, 0xfe, 1]);
+        assert_eq!(SerialNumber::<u16>::arbitrary(&mut u).ok(), Some(SerialNumber(0xfeff)));
+        assert_eq!(SerialNumber::<u16>::size_hint(0), (2, Some(2)));
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(SerialNumber(0u8).abs_distance(SerialNumber(0)), 0);
+        assert_eq!(SerialNumber(0u8).abs_distance(SerialNumber(127)), 127);
+        assert_eq!(SerialNumber(0u8).abs_distance(SerialNumber(129)), 127);
+        assert_eq!(SerialNumber(5u64).offset_from(SerialNumber(u64::MAX)), 6);
+        assert_eq!(SerialNumber(0u32).offset_from(SerialNumber(1 << 31)), i32::MIN);
+        assert!(SerialNumber(u32::MAX - 1).is_within(SerialNumber(0), u32::MAX));
+        assert!(!SerialNumber(u32::MAX).is_within(SerialNumber(0), u32::MAX));
+    }
+
+    #[test]
+    fn references() {
+        let (a, b) = (&SerialNumber(u16::MAX), &SerialNumber(2u16));
+        let two = &2;
+        assert_eq!(a + b, SerialNumber(1));
+        assert_eq!(a + *b, *a + b);
+        assert_eq!(a + two, SerialNumber(1));
+        assert_eq!(*a + two, a + 2);
+        assert_eq!(b - a, 3);
+        assert_eq!(*b - a, b - *a);
+        assert_eq!(b - 2, *b - two);
+        assert!(*a < b);
+        assert!(*b == b);
+
+        let mut c = *a;
+        c += b;
+        c += two;
+        assert_eq!(c, SerialNumber(3));
+
+        let serials = [SerialNumber(3u16), SerialNumber(4)];
+        assert_eq!(serials.iter().fold(SerialNumber(65530), |sum, serial| sum + serial), SerialNumber(1));
+    }
+
+    #[test]
+    fn succ_pred() {
+        assert_eq!(SerialNumber(u16::MAX).succ(), SerialNumber(0));
+        assert_eq!(SerialNumber(0u16).pred(), SerialNumber(u16::MAX));
+        assert_eq!(SerialNumber(u128::MAX).succ().pred(), SerialNumber(u128::MAX));
+        assert!(SerialNumber(1u64 << 63).pred() < SerialNumber(1 << 63));
+        assert_eq!(SerialNumber(u32::MAX).advance(1), SerialNumber(u32::MAX).succ());
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut serial = SerialNumber(250u8) + Wrapping(10);
+        assert_eq!(serial, SerialNumber(4));
+        serial += Wrapping(252);
+        assert_eq!(Wrapping::from(serial), Wrapping(0));
+        assert_eq!(SerialNumber::from(Wrapping(7u64)), SerialNumber(7));
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn rkyv() {
+        use rkyv::rancor::Error;
+
+        let serial = SerialNumber(0xfffe_u32);
+        let bytes = rkyv::to_bytes::<Error>(&serial).unwrap();
+        assert_eq!(bytes.as_slice(), rkyv::to_bytes::<Error>(&0xfffe_u32).unwrap().as_slice());
+
+        let archived = rkyv::access::<rkyv::Archived<SerialNumber<u32>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.0.to_native(), 0xfffe);
+        assert_eq!(rkyv::deserialize::<SerialNumber<u32>, Error>(archived).unwrap(), serial);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn bytemuck() {
+        let serials = [SerialNumber(1u16), SerialNumber(u16::MAX)];
+        assert_eq!(bytemuck::cast_slice::<_, u16>(&serials), &[1, u16::MAX]);
+        assert_eq!(bytemuck::cast::<u32, SerialNumber<u32>>(7), SerialNumber(7));
+    }
+
+    #[cfg(feature = "zerocopy")]
+    #[test]
+    fn zerocopy() {
+        use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+        use zerocopy::byteorder::{BigEndian, U16};
+
+        #[derive(FromBytes, IntoBytes, Immutable, KnownLayout, Unaligned)]
+        #[repr(C)]
+        struct Header {
+            flags: u8,
+            kind: u8,
+            sequence: SerialNumber<U16<BigEndian>>,
+        }
+
+        let header = Header::ref_from_bytes(&[0, 1, 0xff, 0xfe][..]).unwrap();
+        assert_eq!(header.sequence.0.get(), 0xfffe);
+        assert_eq!(header.as_bytes(), &[0, 1, 0xff, 0xfe]);
+    }
 }
@@ -0,0 +1,250 @@
+//! Maps keyed by serial numbers that only retain a sliding window of keys,
+//! e.g. retransmission buffers and per-packet metadata caches.
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+
+use alloc::vec::Vec;
+
+use SerialNumber;
+use uint::Uint;
+
+/// A ring buffer map that retains the entries whose keys are within the
+/// `capacity` most recent serial numbers (including the highest key
+/// inserted so far).
+///
+/// Inserting a key that follows the highest key advances the window and
+/// evicts the entries that fall behind it. Insertion and lookup are `O(1)`,
+/// apart from evicting when the window advances.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::map::SerialMap;
+///
+/// let mut sent = SerialMap::with_capacity(4);
+/// for serial in 65533..=65535u16 {
+///     sent.insert(SerialNumber(serial), serial).unwrap();
+/// }
+/// assert_eq!(sent.get(SerialNumber(65533)), Some(&65533));
+///
+/// // Advances the window, so 65533 and 65534 fall behind
+/// sent.insert(SerialNumber(2), 2).unwrap();
+/// assert_eq!(sent.get(SerialNumber(65533)), None);
+/// assert_eq!(sent.iter().map(|(serial, _)| serial.0).collect::<Vec<_>>(), vec![65535, 2]);
+///
+/// // Too old to be retained
+/// assert_eq!(sent.insert(SerialNumber(65534), 65534), Err(65534));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SerialMap<T, V> {
+    highest: Option<SerialNumber<T>>,
+    slots: Vec<Option<(SerialNumber<T>, V)>>,
+    len: usize,
+}
+
+impl<T: Uint, V> SerialMap<T, V> where SerialNumber<T>: PartialOrd {
+    /// Create a map that retains the `capacity` most recent serial numbers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is not a power of two or exceeds half of the
+    /// serial number space.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity.is_power_of_two(), "capacity must be a power of two");
+        assert!(capacity as u128 <= T::HALF.to_u128(),
+                "capacity exceeds half of the serial number space");
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, || None);
+        SerialMap { highest: None, slots, len: 0 }
+    }
+
+    /// Return the number of serial numbers retained.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Return the number of entries.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether there are no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the highest key inserted so far, even if its entry has been
+    /// removed.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.highest
+    }
+
+    /// Return whether `key` is within the window, i.e. an entry with this
+    /// key would be retained.
+    pub fn in_window(&self, key: SerialNumber<T>) -> bool {
+        match self.highest {
+            Some(highest) => highest.0.wrapping_sub(key.0).to_u128() < self.capacity() as u128,
+            None => false,
+        }
+    }
+
+    /// Insert `value` with `key`, returning the value previously stored
+    /// with `key`.
+    ///
+    /// If `key` follows the highest key, the window advances to `key` and
+    /// the entries that fall behind it are dropped. Returns the value back
+    /// if `key` is behind the window or can not be ordered relative to the
+    /// highest key.
+    pub fn insert(&mut self, key: SerialNumber<T>, value: V) -> Result<Option<V>, V> {
+        match self.highest {
+            None => self.highest = Some(key),
+            Some(highest) => match key.partial_cmp(&highest) {
+                Some(Ordering::Greater) => self.advance(highest, key),
+                Some(_) if self.in_window(key) => {}
+                _ => return Err(value),
+            },
+        }
+        let index = self.index(key);
+        let previous = self.slots[index].replace((key, value));
+        match previous {
+            Some((_, previous)) => Ok(Some(previous)),
+            None => {
+                self.len += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Return a reference to the value stored with `key`.
+    pub fn get(&self, key: SerialNumber<T>) -> Option<&V> {
+        match self.slots[self.index(key)] {
+            Some((stored, ref value)) if stored == key && self.in_window(key) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Return a mutable reference to the value stored with `key`.
+    pub fn get_mut(&mut self, key: SerialNumber<T>) -> Option<&mut V> {
+        let in_window = self.in_window(key);
+        let index = self.index(key);
+        match self.slots[index] {
+            Some((stored, ref mut value)) if stored == key && in_window => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Return whether an entry with `key` is stored.
+    #[inline]
+    pub fn contains_key(&self, key: SerialNumber<T>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove and return the value stored with `key`.
+    pub fn remove(&mut self, key: SerialNumber<T>) -> Option<V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let index = self.index(key);
+        self.len -= 1;
+        self.slots[index].take().map(|(_, value)| value)
+    }
+
+    /// Return an iterator over the entries in serial number order.
+    pub fn iter(&self) -> impl Iterator<Item = (SerialNumber<T>, &V)> + '_ {
+        let start = self.highest.map_or(0, |highest| self.index(highest.succ()));
+        let (newer, older) = self.slots.split_at(start);
+        older.iter().chain(newer).filter_map(|slot| {
+            slot.as_ref().map(|&(key, ref value)| (key, value))
+        })
+    }
+
+    /// Remove all entries and forget the highest key.
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            *slot = None;
+        }
+        self.highest = None;
+        self.len = 0;
+    }
+
+    fn index(&self, key: SerialNumber<T>) -> usize {
+        key.0.to_u128() as usize & (self.capacity() - 1)
+    }
+
+    /// Advance the window from `highest` to `key`, dropping the entries
+    /// whose slots are reused by the serial numbers in between.
+    fn advance(&mut self, highest: SerialNumber<T>, key: SerialNumber<T>) {
+        let distance = key.0.wrapping_sub(highest.0).to_u128();
+        let evicted = distance.min(self.capacity() as u128) as usize;
+        let mut serial = highest;
+        for _ in 0..evicted {
+            serial = serial.succ();
+            let index = self.index(serial);
+            if self.slots[index].take().is_some() {
+                self.len -= 1;
+            }
+        }
+        self.highest = Some(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    #[test]
+    fn window() {
+        let mut map = SerialMap::with_capacity(8);
+        for serial in 250..=255u8 {
+            assert_eq!(map.insert(SerialNumber(serial), serial), Ok(None));
+        }
+        assert_eq!(map.insert(SerialNumber(252), 0), Ok(Some(252)));
+        assert_eq!(map.len(), 6);
+
+        // Evicts 250 and 251
+        assert_eq!(map.insert(SerialNumber(3), 3), Ok(None));
+        assert_eq!(map.len(), 5);
+        assert!(!map.contains_key(SerialNumber(251)));
+        assert!(map.in_window(SerialNumber(250).succ().succ()));
+        assert_eq!(map.iter().map(|(serial, _)| serial.0).collect::<Vec<_>>(),
+                   vec![252, 253, 254, 255, 3]);
+
+        // Fill the gap behind the highest key
+        assert_eq!(map.insert(SerialNumber(0), 0), Ok(None));
+        assert_eq!(map.remove(SerialNumber(254)), Some(254));
+        assert_eq!(map.remove(SerialNumber(254)), None);
+        *map.get_mut(SerialNumber(255)).unwrap() = 0;
+        assert_eq!(map.iter().map(|(serial, &value)| (serial.0, value)).collect::<Vec<_>>(),
+                   vec![(252, 0), (253, 253), (255, 0), (0, 0), (3, 3)]);
+    }
+
+    #[test]
+    fn jump() {
+        let mut map = SerialMap::with_capacity(4);
+        for serial in 0..4u32 {
+            map.insert(SerialNumber(serial), ()).unwrap();
+        }
+        map.insert(SerialNumber(1000), ()).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.highest(), Some(SerialNumber(1000)));
+
+        // Ambiguous or behind the window
+        assert_eq!(map.insert(SerialNumber(1000 + (1 << 31)), ()), Err(()));
+        assert_eq!(map.insert(SerialNumber(996), ()), Err(()));
+        assert!(map.insert(SerialNumber(997), ()).is_ok());
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.highest(), None);
+        assert_eq!(map.get(SerialNumber(997)), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! Serial numbers modulo an arbitrary `N`, e.g. counters limited to a number
+//! of decimal digits or application counters modulo `10^9`.
+//!
+//! This generalizes RFC 1982 from `2^SERIAL_BITS` to any modulus: addition
+//! wraps modulo `N` and `N / 2` is the half of the serial number space.
+//! If `N` is even, serial numbers exactly `N / 2` apart cannot be compared
+//! just like in RFC 1982. If `N` is odd, no two serial numbers are exactly
+//! half of the serial number space apart, so any two serial numbers can be
+//! compared: the one that is at most `(N - 1) / 2` behind the other one
+//! precedes it.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
+use uint::Uint;
+
+/// A serial number modulo `N` stored in the unsigned integer type `T`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::ModSerial;
+///
+/// // A counter with 3 decimal digits
+/// let max = ModSerial::<u16, 1000>::new(999).unwrap();
+/// let zero = max + 1;
+///
+/// assert_eq!(zero.get(), 0);
+/// assert!(zero > max);
+/// assert_eq!(ModSerial::<u16, 1000>::new(1000), None);
+/// assert_eq!(ModSerial::<u16, 1000>::new_reduced(1001).get(), 1);
+///
+/// // 500 apart is ambiguous for an even modulus only
+/// assert_eq!(zero.partial_cmp(&(zero + 500)), None);
+/// assert!(ModSerial::<u16, 999>::new_reduced(0) < ModSerial::new_reduced(499));
+/// assert!(ModSerial::<u16, 999>::new_reduced(0) > ModSerial::new_reduced(500));
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ModSerial<T, const N: u128>(T);
+
+impl<T: Uint, const N: u128> ModSerial<T, N> {
+    const VALID: () = assert!(N >= 2 && (T::BITS >= 128 || (N - 1) >> T::BITS == 0),
+                              "N must be in 2..=2^T::BITS");
+
+    /// The modulus `N`.
+    #[inline]
+    pub fn modulus() -> u128 {
+        let () = Self::VALID;
+        N
+    }
+
+    /// Create a serial number from `value`.
+    ///
+    /// Returns `None` if `value` is not smaller than `N`.
+    #[inline]
+    pub fn new(value: T) -> Option<Self> {
+        if value.to_u128() < Self::modulus() {
+            Some(ModSerial(value))
+        } else {
+            None
+        }
+    }
+
+    /// Create a serial number from `value` modulo `N`.
+    #[inline]
+    pub fn new_reduced(value: T) -> Self {
+        ModSerial(T::from_u128(value.to_u128() % Self::modulus()))
+    }
+
+    /// Return the value of the serial number.
+    #[inline]
+    pub fn get(self) -> T {
+        self.0
+    }
+
+    /// Return the successor of the serial number, wrapping at `N`.
+    #[inline]
+    pub fn succ(self) -> Self {
+        self + T::ONE
+    }
+
+    /// Return the predecessor of the serial number, wrapping at `N`.
+    #[inline]
+    pub fn pred(self) -> Self {
+        if self.0 == T::ZERO {
+            ModSerial(T::from_u128(Self::modulus() - 1))
+        } else {
+            ModSerial(self.0.wrapping_sub(T::ONE))
+        }
+    }
+
+    /// Advance the serial number by `n` modulo `N`, like `self + n`.
+    ///
+    /// The result only follows `self` if `n` modulo `N` is less than
+    /// `N / 2` (see `SerialNumber::advance`).
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        self + n
+    }
+
+    /// Return the distance from `self` to `other` modulo `N`.
+    fn distance(self, other: Self) -> u128 {
+        let (from, to) = (self.0.to_u128(), other.0.to_u128());
+        if to >= from { to - from } else { N - (from - to) }
+    }
+}
+
+impl<T: fmt::Debug, const N: u128> fmt::Debug for ModSerial<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display, const N: u128> fmt::Display for ModSerial<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Uint, const N: u128> Add<T> for ModSerial<T, N> {
+    type Output = ModSerial<T, N>;
+
+    /// Apply addition of a positive integer modulo `N` (e.g. it will *wrap*
+    /// when overflowing).
+    #[inline]
+    fn add(self, other: T) -> ModSerial<T, N> {
+        // Avoid overflowing `u128` for a modulus close to `2^128`
+        let addend = other.to_u128() % Self::modulus();
+        let value = self.0.to_u128();
+        if value >= N - addend {
+            ModSerial(T::from_u128(value - (N - addend)))
+        } else {
+            ModSerial(T::from_u128(value + addend))
+        }
+    }
+}
+
+impl<T: Uint, const N: u128> AddAssign<T> for ModSerial<T, N> {
+    /// Performs the `+=` operation modulo `N` (e.g. it will *wrap* when
+    /// overflowing).
+    #[inline]
+    fn add_assign(&mut self, other: T) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Uint, const N: u128> PartialOrd for ModSerial<T, N> {
+    /// Return an ordering between `self` and `other`, which is undefined if
+    /// `N` is even and both are exactly `N / 2` apart.
+    fn partial_cmp(&self, other: &ModSerial<T, N>) -> Option<Ordering> {
+        let distance = self.distance(*other);
+        // Compare the distance to `N / 2` without rounding by comparing it
+        // to the remaining distance
+        if distance == 0 {
+            Some(Ordering::Equal)
+        } else if distance < N - distance {
+            Some(Ordering::Less)
+        } else if distance > N - distance {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        assert_eq!(ModSerial::<u32, 1_000_000_000>::modulus(), 1_000_000_000);
+        assert_eq!(ModSerial::<u32, 1_000_000_000>::new(999_999_999).map(ModSerial::get),
+                   Some(999_999_999));
+        assert_eq!(ModSerial::<u32, 1_000_000_000>::new(1_000_000_000), None);
+        assert_eq!(ModSerial::<u8, 256>::new(255).map(ModSerial::get), Some(255));
+        assert_eq!(ModSerial::<u8, 10>::new_reduced(255).get(), 5);
+    }
+
+    #[test]
+    fn add() {
+        let mut serial = ModSerial::<u8, 10>::new_reduced(8);
+        serial += 3;
+        assert_eq!(serial.get(), 1);
+        assert_eq!((serial + 255).get(), 6);
+        assert_eq!((ModSerial::<u8, 256>::new_reduced(255) + 1).get(), 0);
+
+        // No overflow close to `2^128`
+        let max = ModSerial::<u128, { u128::MAX }>::new_reduced(u128::MAX - 1);
+        assert_eq!((max + (u128::MAX - 1)).get(), u128::MAX - 2);
+        assert_eq!((max + 1).get(), 0);
+    }
+
+    #[test]
+    fn succ_pred() {
+        let zero = ModSerial::<u16, 1000>::new_reduced(0);
+        assert_eq!(zero.pred().get(), 999);
+        assert_eq!(zero.pred().succ(), zero);
+        assert_eq!(ModSerial::<u8, 256>::new_reduced(0).pred().get(), 255);
+        assert_eq!(zero.advance(1001).get(), 1);
+    }
+
+    #[test]
+    fn partial_cmp_even() {
+        let zero = ModSerial::<u16, 1000>::new_reduced(0);
+        assert_eq!(zero.partial_cmp(&zero), Some(Ordering::Equal));
+        assert_eq!(zero.partial_cmp(&(zero + 499)), Some(Ordering::Less));
+        assert_eq!(zero.partial_cmp(&(zero + 500)), None);
+        assert_eq!(zero.partial_cmp(&(zero + 501)), Some(Ordering::Greater));
+        assert!(ModSerial::<u16, 1000>::new_reduced(999) < zero);
+    }
+
+    #[test]
+    fn partial_cmp_odd() {
+        let zero = ModSerial::<u8, 7>::new_reduced(0);
+        assert_eq!(zero.partial_cmp(&(zero + 3)), Some(Ordering::Less));
+        assert_eq!(zero.partial_cmp(&(zero + 4)), Some(Ordering::Greater));
+        for a in 0..7 {
+            for b in 0..7 {
+                let (a, b) = (ModSerial::<u8, 7>::new_reduced(a), ModSerial::new_reduced(b));
+                assert!(a.partial_cmp(&b).is_some());
+                assert_eq!(a < b, b > a);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Validation of counters that may only move forward, e.g. the frame
+//! counters of LoRaWAN or the sequence numbers tracked by stateful
+//! firewalls.
+
+use std::error;
+use std::fmt;
+
+use SerialNumber;
+use uint::Uint;
+
+/// The error returned by `Monotonic::check` and `Monotonic::update`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MonotonicError {
+    /// The serial number precedes the current one.
+    Stale,
+    /// The serial number equals the current one.
+    Duplicate,
+    /// The serial number follows the current one by more than the maximum
+    /// jump or can not be ordered relative to it.
+    JumpTooLarge,
+}
+
+impl fmt::Display for MonotonicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MonotonicError::Stale => write!(f, "serial number precedes the current one"),
+            MonotonicError::Duplicate => write!(f, "serial number equals the current one"),
+            MonotonicError::JumpTooLarge => write!(f, "serial number is too far ahead"),
+        }
+    }
+}
+
+impl error::Error for MonotonicError {}
+
+/// A serial number that only accepts updates that move strictly forward by
+/// at most a maximum jump.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::monotonic::{Monotonic, MonotonicError};
+///
+/// // LoRaWAN 1.0 rejects frame counters more than MAX_FCNT_GAP ahead
+/// let mut counter = Monotonic::new(SerialNumber(65530u16), 16384);
+///
+/// assert_eq!(counter.update(SerialNumber(2)), Ok(8));
+/// assert_eq!(counter.update(SerialNumber(2)), Err(MonotonicError::Duplicate));
+/// assert_eq!(counter.update(SerialNumber(65535)), Err(MonotonicError::Stale));
+/// assert_eq!(counter.update(SerialNumber(20000)), Err(MonotonicError::JumpTooLarge));
+/// assert_eq!(counter.get(), SerialNumber(2));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Monotonic<T> {
+    current: SerialNumber<T>,
+    max_jump: T,
+}
+
+impl<T: Uint> Monotonic<T> where SerialNumber<T>: PartialOrd {
+    /// Create a guard starting at `current` that accepts serial numbers
+    /// following it by at most `max_jump`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_jump` is zero or not below half of the serial number
+    /// space.
+    pub fn new(current: SerialNumber<T>, max_jump: T) -> Self {
+        assert!(max_jump != T::ZERO, "maximum jump must be greater than zero");
+        assert!(max_jump < T::HALF, "maximum jump exceeds the range defined by RFC 1982");
+        Monotonic { current, max_jump }
+    }
+
+    /// Return the current serial number.
+    #[inline]
+    pub fn get(&self) -> SerialNumber<T> {
+        self.current
+    }
+
+    /// Return the maximum jump.
+    #[inline]
+    pub fn max_jump(&self) -> T {
+        self.max_jump
+    }
+
+    /// Check whether `serial` would be accepted, returning the distance it
+    /// moves forward.
+    pub fn check(&self, serial: SerialNumber<T>) -> Result<T, MonotonicError> {
+        let jump = serial.0.wrapping_sub(self.current.0);
+        if jump == T::ZERO {
+            Err(MonotonicError::Duplicate)
+        } else if serial < self.current {
+            Err(MonotonicError::Stale)
+        } else if jump > self.max_jump {
+            Err(MonotonicError::JumpTooLarge)
+        } else {
+            Ok(jump)
+        }
+    }
+
+    /// Move to `serial` if it is accepted, returning the distance it moved
+    /// forward.
+    pub fn update(&mut self, serial: SerialNumber<T>) -> Result<T, MonotonicError> {
+        let jump = self.check(serial)?;
+        self.current = serial;
+        Ok(jump)
+    }
+
+    /// Move to `serial` unconditionally, e.g. after a counter reset.
+    #[inline]
+    pub fn reset(&mut self, serial: SerialNumber<T>) {
+        self.current = serial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundaries() {
+        let mut counter = Monotonic::new(SerialNumber(250u8), 10);
+        assert_eq!(counter.check(SerialNumber(4)), Ok(10));
+        assert_eq!(counter.check(SerialNumber(5)), Err(MonotonicError::JumpTooLarge));
+        assert_eq!(counter.check(SerialNumber(122)), Err(MonotonicError::JumpTooLarge));
+        assert_eq!(counter.check(SerialNumber(123)), Err(MonotonicError::Stale));
+        assert_eq!(counter.check(SerialNumber(249)), Err(MonotonicError::Stale));
+
+        assert_eq!(counter.update(SerialNumber(251)), Ok(1));
+        assert_eq!(counter.update(SerialNumber(251)), Err(MonotonicError::Duplicate));
+        counter.reset(SerialNumber(0));
+        assert_eq!(counter.get(), SerialNumber(0));
+        assert_eq!(counter.max_jump(), 10);
+    }
+
+    #[test]
+    fn ambiguous() {
+        let counter = Monotonic::new(SerialNumber(0u32), (1 << 31) - 1);
+        assert_eq!(counter.check(SerialNumber((1 << 31) - 1)), Ok((1 << 31) - 1));
+        assert_eq!(counter.check(SerialNumber(1 << 31)), Err(MonotonicError::JumpTooLarge));
+    }
+
+    #[test]
+    #[should_panic]
+    fn max_jump_too_large() {
+        Monotonic::new(SerialNumber(0u8), 128);
+    }
+}
@@ -0,0 +1,309 @@
+//! Serial numbers that exclude zero, so `Option<NonZeroSerial<T>>` is as
+//! large as `T`, e.g. for optional sequence numbers in large per-packet
+//! tables.
+//!
+//! The serial number space consists of the `2^SERIAL_BITS - 1` values
+//! `1..=T::MAX` and incrementing the largest value wraps to `1`, like
+//! reserving `0` in a `sentinel::SentinelSpace`. As the size of the serial
+//! number space is odd, no two serial numbers are exactly half of it apart
+//! and any two serial numbers can be compared (see `modular`): the one
+//! that is less than `2^(SERIAL_BITS - 1)` behind the other one precedes
+//! it.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use std::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize};
+use std::ops::{Add, AddAssign};
+
+use {AdditionOutOfRange, strict_addend};
+use uint::Uint;
+
+mod repr {
+    use super::*;
+
+    /// An unsigned integer type with a `NonZero` counterpart.
+    pub trait Repr: Uint + fmt::Display {
+        type NonZero: Copy + Eq + Hash;
+
+        fn to_non_zero(self) -> Option<Self::NonZero>;
+        fn from_non_zero(value: Self::NonZero) -> Self;
+    }
+}
+
+use self::repr::Repr;
+
+/// A serial number in the space `1..=T::MAX` stored as the `NonZero`
+/// counterpart of `T`.
+///
+/// # Examples
+///
+/// ```
+/// use std::mem;
+///
+/// use sna::NonZeroSerial;
+///
+/// assert_eq!(mem::size_of::<Option<NonZeroSerial<u32>>>(), 4);
+///
+/// let max = NonZeroSerial::new(u8::MAX).unwrap();
+/// assert_eq!(max.succ().get(), 1);
+/// assert_eq!((max + 3).get(), 3);
+/// assert_eq!(NonZeroSerial::<u8>::new(0), None);
+///
+/// // 128 is 127 ahead of 1, while 129 is 127 behind it
+/// let one = NonZeroSerial::new(1u8).unwrap();
+/// assert!(one < NonZeroSerial::new(128).unwrap());
+/// assert!(one > NonZeroSerial::new(129).unwrap());
+/// ```
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub struct NonZeroSerial<T: Repr>(T::NonZero);
+
+impl<T: Repr> NonZeroSerial<T> {
+    /// Create a serial number from `value`.
+    ///
+    /// Returns `None` if `value` is zero.
+    #[inline]
+    pub fn new(value: T) -> Option<Self> {
+        value.to_non_zero().map(NonZeroSerial)
+    }
+
+    /// Return the value of the serial number.
+    #[inline]
+    pub fn get(self) -> T {
+        T::from_non_zero(self.0)
+    }
+
+    /// Return the successor of the serial number, wrapping from `T::MAX`
+    /// to `1`.
+    #[inline]
+    pub fn succ(self) -> Self {
+        self.wrapping_add(T::ONE)
+    }
+
+    /// Return the predecessor of the serial number, wrapping from `1` to
+    /// `T::MAX`.
+    #[inline]
+    pub fn pred(self) -> Self {
+        let value = self.get();
+        let difference = value.wrapping_sub(T::ONE);
+        // Reaching zero borrows from the next lower cycle
+        Self::from_value(if difference == T::ZERO { T::MAX } else { difference })
+    }
+
+    /// Advance the serial number by `n`, like `self + n`.
+    ///
+    /// The result only follows `self` if `n` is less than
+    /// `2^(SERIAL_BITS - 1)` (see `SerialNumber::advance`).
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        self + n
+    }
+
+    /// Apply addition of a positive integer as defined by RFC 1982 for a
+    /// serial number space of `2^SERIAL_BITS - 1` values.
+    ///
+    /// Returns an error if `addend` is not less than `2^(SERIAL_BITS - 1)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::NonZeroSerial;
+    ///
+    /// let max = NonZeroSerial::new(u8::MAX).unwrap();
+    /// assert_eq!(max.checked_add(127).map(NonZeroSerial::get), Ok(127));
+    /// assert!(max.checked_add(128).is_err());
+    /// ```
+    #[inline]
+    pub fn checked_add(self, addend: T) -> Result<Self, AdditionOutOfRange<T>> {
+        if addend < T::HALF {
+            Ok(self.wrapping_add(addend))
+        } else {
+            Err(AdditionOutOfRange { addend })
+        }
+    }
+
+    /// Add `addend` modulo `2^SERIAL_BITS - 1`.
+    fn wrapping_add(self, addend: T) -> Self {
+        let value = self.get();
+        let sum = value.wrapping_add(addend);
+        // Overflowing skips zero, as `2^SERIAL_BITS` is congruent to `1`
+        Self::from_value(if sum < value { sum.wrapping_add(T::ONE) } else { sum })
+    }
+
+    /// Return the distance from `self` to `other` modulo
+    /// `2^SERIAL_BITS - 1`.
+    fn distance(self, other: Self) -> T {
+        let (from, to) = (self.get(), other.get());
+        let distance = to.wrapping_sub(from);
+        if to >= from { distance } else { distance.wrapping_sub(T::ONE) }
+    }
+
+    #[inline]
+    fn from_value(value: T) -> Self {
+        NonZeroSerial(value.to_non_zero().expect("value is not zero"))
+    }
+}
+
+impl<T: Repr> fmt::Debug for NonZeroSerial<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.get(), f)
+    }
+}
+
+impl<T: Repr> fmt::Display for NonZeroSerial<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.get(), f)
+    }
+}
+
+impl<T: Repr> Add<T> for NonZeroSerial<T> {
+    type Output = NonZeroSerial<T>;
+
+    /// Apply addition of a positive integer modulo `2^SERIAL_BITS - 1`
+    /// (e.g. it will *wrap* when overflowing, skipping zero).
+    ///
+    /// With the `strict` feature, this panics in debug builds if the addend
+    /// exceeds the range defined by RFC 1982 (see `checked_add`).
+    #[inline]
+    fn add(self, other: T) -> NonZeroSerial<T> {
+        strict_addend(other);
+        self.wrapping_add(other)
+    }
+}
+
+impl<T: Repr> AddAssign<T> for NonZeroSerial<T> {
+    /// Performs the `+=` operation modulo `2^SERIAL_BITS - 1` (e.g. it will
+    /// *wrap* when overflowing, skipping zero).
+    #[inline]
+    fn add_assign(&mut self, other: T) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Repr> PartialOrd for NonZeroSerial<T> {
+    /// Return an ordering between `self` and `other`, which is always
+    /// defined.
+    #[inline]
+    fn partial_cmp(&self, other: &NonZeroSerial<T>) -> Option<Ordering> {
+        let distance = self.distance(*other);
+        if distance == T::ZERO {
+            Some(Ordering::Equal)
+        } else if distance < T::HALF {
+            Some(Ordering::Less)
+        } else {
+            Some(Ordering::Greater)
+        }
+    }
+}
+
+macro_rules! repr_impl {
+    ($T:ty, $N:ty) => {
+        impl repr::Repr for $T {
+            type NonZero = $N;
+
+            #[inline]
+            fn to_non_zero(self) -> Option<$N> {
+                <$N>::new(self)
+            }
+
+            #[inline]
+            fn from_non_zero(value: $N) -> $T {
+                value.get()
+            }
+        }
+
+        impl From<$N> for NonZeroSerial<$T> {
+            /// Convert from the `NonZero` counterpart of the integer type.
+            #[inline]
+            fn from(value: $N) -> Self {
+                NonZeroSerial(value)
+            }
+        }
+
+        impl From<NonZeroSerial<$T>> for $N {
+            /// Convert into the `NonZero` counterpart of the integer type.
+            #[inline]
+            fn from(serial: NonZeroSerial<$T>) -> Self {
+                serial.0
+            }
+        }
+    }
+}
+
+repr_impl!(u8, NonZeroU8);
+repr_impl!(u16, NonZeroU16);
+repr_impl!(u32, NonZeroU32);
+repr_impl!(u64, NonZeroU64);
+repr_impl!(u128, NonZeroU128);
+repr_impl!(usize, NonZeroUsize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serial(value: u8) -> NonZeroSerial<u8> {
+        NonZeroSerial::new(value).unwrap()
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!((serial(254) + 1).get(), 255);
+        assert_eq!((serial(255) + 1).get(), 1);
+        assert_eq!((serial(200) + 100).get(), 45);
+        let mut value = serial(1);
+        value += 127;
+        assert_eq!(value.get(), 128);
+        assert_eq!(serial(1).pred().get(), 255);
+        assert_eq!(serial(2).pred().get(), 1);
+        assert_eq!(serial(1).pred().succ(), serial(1));
+        assert_eq!(NonZeroSerial::from(NonZeroU32::MAX).succ(), NonZeroSerial::new(1).unwrap());
+    }
+
+    #[test]
+    fn wrapping_add() {
+        assert_eq!(serial(200).wrapping_add(100).get(), 45);
+        assert_eq!(serial(1).wrapping_add(254).get(), 255);
+        assert_eq!(serial(255).wrapping_add(255).get(), 255);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+    fn add_assign_out_of_range() {
+        let mut value = serial(1);
+        value += 254;
+        assert_eq!(value.get(), 255);
+    }
+
+    #[test]
+    #[cfg_attr(all(feature = "strict", debug_assertions), should_panic)]
+    fn add_assign_max_out_of_range() {
+        let mut value = serial(255);
+        value += 255;
+        assert_eq!(value.get(), 255);
+    }
+
+    #[test]
+    fn cmp_exhaustive() {
+        // Compare against offsets in a space of 255 values
+        for a in 1..=u8::MAX {
+            for b in 1..=u8::MAX {
+                let distance = (i32::from(b) - i32::from(a)).rem_euclid(255);
+                let expected = match distance {
+                    0 => Ordering::Equal,
+                    1..=127 => Ordering::Less,
+                    _ => Ordering::Greater,
+                };
+                assert_eq!(serial(a).partial_cmp(&serial(b)), Some(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn size() {
+        use std::mem::size_of;
+
+        assert_eq!(size_of::<Option<NonZeroSerial<u8>>>(), 1);
+        assert_eq!(size_of::<Option<NonZeroSerial<u64>>>(), 8);
+        assert_eq!(size_of::<Option<NonZeroSerial<usize>>>(), size_of::<usize>());
+    }
+}
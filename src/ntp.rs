@@ -0,0 +1,175 @@
+//! NTP timestamps and their eras, see
+//! [chapter 6 of RFC 5905](https://tools.ietf.org/html/rfc5905#section-6).
+//!
+//! The 64-bit NTP timestamp format consists of 32 bits of seconds since the
+//! start of the current era and 32 bits of fraction, so it wraps every
+//! 2^32 seconds (approximately 136 years). The first era started at the
+//! prime epoch, 1900-01-01 00:00 UTC, and era 1 starts in 2036.
+//!
+//! Timestamps are compared with serial number arithmetic, so comparisons
+//! remain valid across the era boundary as long as both timestamps are
+//! less than 68 years apart. The era a timestamp belongs to is inferred
+//! relative to a local reference time, which yields a full time that
+//! increases monotonically across eras.
+//!
+//! Full times are signed 128-bit integers in the 32.32 fixed point format
+//! of NTP timestamps, i.e. the era is stored in the upper 64 bits and the
+//! timestamp in the lower 64 bits. Times before the prime epoch are
+//! negative.
+
+use std::cmp::Ordering;
+
+use SerialNumber;
+
+/// The duration of an era in seconds.
+pub const ERA_SECONDS: u64 = 1 << 32;
+
+/// A 64-bit NTP timestamp whose era is not known.
+///
+/// # Examples
+///
+/// ```
+/// use sna::ntp::EraTimestamp;
+///
+/// // Shortly before and after the start of era 1
+/// let before = EraTimestamp::new(u32::MAX, 0);
+/// let after = EraTimestamp::new(10, 0);
+/// assert!(before < after);
+///
+/// // A timestamp received after the wrap belongs to era 1, given a
+/// // reference time of era 0
+/// let reference = before.to_full(1 << 63);
+/// assert_eq!(before.era(reference), 0);
+/// assert_eq!(after.era(reference), 1);
+/// assert_eq!(after.to_full(reference) - reference, 11 << 32);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EraTimestamp {
+    bits: u64,
+}
+
+impl EraTimestamp {
+    /// Create a timestamp from the seconds since the start of its era and
+    /// the fraction of a second in units of 2^-32 seconds.
+    #[inline]
+    pub fn new(seconds: u32, fraction: u32) -> Self {
+        EraTimestamp { bits: u64::from(seconds) << 32 | u64::from(fraction) }
+    }
+
+    /// Create a timestamp from its 64-bit wire representation.
+    #[inline]
+    pub fn from_bits(bits: u64) -> Self {
+        EraTimestamp { bits }
+    }
+
+    /// Return the 64-bit wire representation of the timestamp.
+    #[inline]
+    pub fn to_bits(self) -> u64 {
+        self.bits
+    }
+
+    /// Return the timestamp of a full time, dropping its era.
+    #[inline]
+    pub fn from_full(full: i128) -> Self {
+        EraTimestamp { bits: full as u64 }
+    }
+
+    /// Return the seconds since the start of the era of the timestamp.
+    #[inline]
+    pub fn seconds(self) -> SerialNumber<u32> {
+        SerialNumber((self.bits >> 32) as u32)
+    }
+
+    /// Return the fraction of a second in units of 2^-32 seconds.
+    #[inline]
+    pub fn fraction(self) -> u32 {
+        self.bits as u32
+    }
+
+    /// Return the full time of the timestamp that is closest to the full
+    /// time `reference`.
+    ///
+    /// If the timestamp is exactly half an era away from `reference`, the
+    /// earlier candidate is returned.
+    #[inline]
+    pub fn to_full(self, reference: i128) -> i128 {
+        let offset = SerialNumber(self.bits) - SerialNumber(reference as u64);
+        reference + i128::from(offset)
+    }
+
+    /// Return the era of the timestamp that is closest to the full time
+    /// `reference`.
+    ///
+    /// See `to_full`.
+    #[inline]
+    pub fn era(self, reference: i128) -> i32 {
+        era(self.to_full(reference))
+    }
+}
+
+impl PartialOrd for EraTimestamp {
+    /// Return an ordering between `self` and `other` as if both were 64-bit
+    /// serial numbers, which is undefined if they are exactly half an era
+    /// apart.
+    #[inline]
+    fn partial_cmp(&self, other: &EraTimestamp) -> Option<Ordering> {
+        SerialNumber(self.bits).partial_cmp(&SerialNumber(other.bits))
+    }
+}
+
+/// Return the era of a full time.
+///
+/// # Examples
+///
+/// ```
+/// use sna::ntp;
+///
+/// assert_eq!(ntp::era(0), 0);
+/// assert_eq!(ntp::era(-1), -1);
+/// assert_eq!(ntp::era(1 << 64), 1);
+/// ```
+#[inline]
+pub fn era(full: i128) -> i32 {
+    (full >> 64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields() {
+        let timestamp = EraTimestamp::new(0x0102_0304, 0x0506_0708);
+        assert_eq!(timestamp.to_bits(), 0x0102_0304_0506_0708);
+        assert_eq!(timestamp.seconds(), SerialNumber(0x0102_0304));
+        assert_eq!(timestamp.fraction(), 0x0506_0708);
+        assert_eq!(EraTimestamp::from_bits(timestamp.to_bits()), timestamp);
+    }
+
+    #[test]
+    fn compare_across_era() {
+        let before = EraTimestamp::new(u32::MAX, u32::MAX);
+        let after = EraTimestamp::new(0, 0);
+        assert!(before < after);
+        assert!(EraTimestamp::new(7, 1) > EraTimestamp::new(7, 0));
+        assert_eq!(after.partial_cmp(&EraTimestamp::new(1 << 31, 0)), None);
+    }
+
+    #[test]
+    fn infer_era() {
+        // Shortly after the start of era 1
+        let reference = (1 << 64) + (5 << 32);
+
+        let late = EraTimestamp::new(u32::MAX - 4, 0);
+        assert_eq!(late.era(reference), 0);
+        assert_eq!(late.to_full(reference), (1 << 64) - (5 << 32));
+
+        let early = EraTimestamp::new(30, 0);
+        assert_eq!(early.era(reference), 1);
+        assert_eq!(EraTimestamp::from_full(early.to_full(reference)), early);
+
+        // Before the prime epoch
+        assert_eq!(late.era(0), -1);
+        assert!(late.to_full(0) < 0);
+    }
+}
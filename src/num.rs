@@ -0,0 +1,146 @@
+//! Implementations of the `num-traits` traits for `SerialNumber`, so it can
+//! be used in code that is generic over numeric types.
+//!
+//! `One` is not implemented since it requires multiplication, which is not
+//! defined for serial numbers.
+//!
+//! Requires the `num-traits` feature.
+
+use num_traits::{Bounded, CheckedAdd, FromPrimitive, ToPrimitive, WrappingAdd, Zero};
+
+use SerialNumber;
+
+macro_rules! num_impl {
+    ($T:ty) => {
+        impl Zero for SerialNumber<$T> {
+            #[inline]
+            fn zero() -> Self {
+                SerialNumber(0)
+            }
+
+            #[inline]
+            fn is_zero(&self) -> bool {
+                self.0 == 0
+            }
+        }
+
+        impl Bounded for SerialNumber<$T> {
+            /// Return zero.
+            ///
+            /// Note that serial numbers are not ordered linearly, so this
+            /// is only the smallest number of the underlying integer type.
+            #[inline]
+            fn min_value() -> Self {
+                SerialNumber(<$T>::MIN)
+            }
+
+            /// Return the largest representable number.
+            ///
+            /// Note that serial numbers are not ordered linearly, so this
+            /// is only the largest number of the underlying integer type.
+            #[inline]
+            fn max_value() -> Self {
+                SerialNumber(<$T>::MAX)
+            }
+        }
+
+        impl WrappingAdd for SerialNumber<$T> {
+            #[inline]
+            fn wrapping_add(&self, other: &Self) -> Self {
+                SerialNumber(self.0.wrapping_add(other.0))
+            }
+        }
+
+        impl CheckedAdd for SerialNumber<$T> {
+            /// Apply addition as defined by RFC 1982, returning `None` if
+            /// the addend is out of range (see `SerialNumber::checked_add`).
+            #[inline]
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                SerialNumber::<$T>::checked_add(*self, other.0).ok()
+            }
+        }
+
+        impl FromPrimitive for SerialNumber<$T> {
+            #[inline]
+            fn from_i64(value: i64) -> Option<Self> {
+                <$T>::from_i64(value).map(SerialNumber)
+            }
+
+            #[inline]
+            fn from_u64(value: u64) -> Option<Self> {
+                <$T>::from_u64(value).map(SerialNumber)
+            }
+
+            #[inline]
+            fn from_i128(value: i128) -> Option<Self> {
+                <$T>::from_i128(value).map(SerialNumber)
+            }
+
+            #[inline]
+            fn from_u128(value: u128) -> Option<Self> {
+                <$T>::from_u128(value).map(SerialNumber)
+            }
+        }
+
+        impl ToPrimitive for SerialNumber<$T> {
+            #[inline]
+            fn to_i64(&self) -> Option<i64> {
+                self.0.to_i64()
+            }
+
+            #[inline]
+            fn to_u64(&self) -> Option<u64> {
+                self.0.to_u64()
+            }
+
+            #[inline]
+            fn to_i128(&self) -> Option<i128> {
+                self.0.to_i128()
+            }
+
+            #[inline]
+            fn to_u128(&self) -> Option<u128> {
+                self.0.to_u128()
+            }
+        }
+    };
+}
+
+num_impl!(u8);
+num_impl!(u16);
+num_impl!(u32);
+num_impl!(u64);
+num_impl!(u128);
+num_impl!(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next<S: Zero + WrappingAdd + FromPrimitive>(serial: S) -> S {
+        serial.wrapping_add(&S::from_u8(1).unwrap())
+    }
+
+    #[test]
+    fn generic() {
+        assert_eq!(next(SerialNumber(u16::MAX)), SerialNumber::zero());
+        assert!(next(SerialNumber(u8::MAX)).is_zero());
+        assert_eq!(<SerialNumber<u32> as Bounded>::max_value(), SerialNumber(u32::MAX));
+    }
+
+    #[test]
+    fn checked_add() {
+        let max = SerialNumber(u8::MAX);
+        assert_eq!(CheckedAdd::checked_add(&max, &SerialNumber(127)), Some(SerialNumber(126)));
+        assert_eq!(CheckedAdd::checked_add(&max, &SerialNumber(128)), None);
+    }
+
+    #[test]
+    fn primitive() {
+        assert_eq!(SerialNumber::<u8>::from_i64(-1), None);
+        assert_eq!(SerialNumber::<u8>::from_u64(255), Some(SerialNumber(255)));
+        assert_eq!(SerialNumber::<u8>::from_u64(256), None);
+        assert_eq!(SerialNumber(u64::MAX).to_i64(), None);
+        assert_eq!(SerialNumber(u64::MAX).to_u128(), Some(u128::from(u64::MAX)));
+    }
+}
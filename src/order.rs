@@ -0,0 +1,606 @@
+//! Total orderings of serial numbers relative to an anchor.
+//!
+//! Serial numbers only have a partial order, so they can not be put into
+//! collections that require `Ord` (e.g. `BinaryHeap`). Relative to an anchor
+//! (e.g. the oldest outstanding serial number), every serial number has a
+//! well-defined distance and the serial numbers can be ordered by it.
+//!
+//! Alternatively, `TieBreak` orders serial numbers by serial number order
+//! and resolves the case RFC 1982 leaves undefined, which is a total order
+//! as long as the serial numbers span less than half of the serial number
+//! space. It requires the `total-order` feature.
+//!
+//! For the same reason, folding serial numbers with `partial_cmp` does not
+//! necessarily yield the latest or earliest one: `serial_max` and
+//! `serial_min` detect when there is none, while `serial_max_from` and
+//! `serial_min_from` resolve this relative to an anchor.
+
+use std::cmp::Ordering;
+
+use {Ambiguous, SerialNumber};
+#[cfg(feature = "total-order")]
+use SerialOps;
+use uint::Uint;
+
+/// A serial number ordered in *ascending* distance from an anchor, so the
+/// serial number closest to the anchor is the smallest.
+///
+/// For all serial numbers within half of the serial number space following
+/// the anchor, this matches the serial number order, which allows keeping
+/// out-of-order packets in a `BTreeMap` keyed by their serial number.
+///
+/// All serial numbers compared with each other must share the same anchor.
+/// Once the window advances, the collection has to be rebuilt with the
+/// serial numbers re-anchored (see `Anchored::reanchor`), as changing the
+/// anchor changes the order of serial numbers preceding the new anchor.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use sna::SerialNumber;
+/// use sna::order::Anchored;
+///
+/// let anchor = SerialNumber(250u8);
+/// let mut packets = BTreeMap::new();
+/// for &serial in &[3u8, 251, 0, 255] {
+///     packets.insert(Anchored::new(SerialNumber(serial), anchor), serial);
+/// }
+/// assert_eq!(packets.values().cloned().collect::<Vec<_>>(), vec![251, 255, 0, 3]);
+///
+/// // Advance the window past 255 and drop everything before it
+/// let anchor = SerialNumber(0u8);
+/// let packets: BTreeMap<_, _> = packets
+///     .into_iter()
+///     .map(|(key, value)| (key.reanchor(anchor), value))
+///     .filter(|(key, _)| key.serial() >= anchor)
+///     .collect();
+/// assert_eq!(packets.values().cloned().collect::<Vec<_>>(), vec![0, 3]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Anchored<T> {
+    serial: SerialNumber<T>,
+    anchor: SerialNumber<T>,
+}
+
+impl<T: Uint> Anchored<T> {
+    /// Order `serial` relative to `anchor`.
+    #[inline]
+    pub fn new(serial: SerialNumber<T>, anchor: SerialNumber<T>) -> Self {
+        Anchored { serial, anchor }
+    }
+
+    /// Return the serial number.
+    #[inline]
+    pub fn serial(&self) -> SerialNumber<T> {
+        self.serial
+    }
+
+    /// Return the anchor.
+    #[inline]
+    pub fn anchor(&self) -> SerialNumber<T> {
+        self.anchor
+    }
+
+    /// Return the distance of the serial number counted up from the anchor.
+    #[inline]
+    pub fn distance(&self) -> T {
+        self.serial.0.wrapping_sub(self.anchor.0)
+    }
+
+    /// Return the serial number ordered relative to `anchor` instead.
+    #[inline]
+    pub fn reanchor(self, anchor: SerialNumber<T>) -> Self {
+        Anchored { serial: self.serial, anchor }
+    }
+
+    /// Return the successor of the serial number, keeping the anchor.
+    #[inline]
+    pub fn succ(self) -> Self {
+        Anchored { serial: self.serial.succ(), anchor: self.anchor }
+    }
+
+    /// Return the predecessor of the serial number, keeping the anchor.
+    #[inline]
+    pub fn pred(self) -> Self {
+        Anchored { serial: self.serial.pred(), anchor: self.anchor }
+    }
+
+    /// Advance the serial number by `n`, keeping the anchor (see
+    /// `SerialNumber::advance`).
+    ///
+    /// Note that the result is smaller than `self` if it passes the anchor.
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        Anchored { serial: self.serial.advance(n), anchor: self.anchor }
+    }
+
+    /// Consume the adapter, returning the serial number.
+    #[inline]
+    pub fn into_inner(self) -> SerialNumber<T> {
+        self.serial
+    }
+}
+
+impl<T: Uint> PartialEq for Anchored<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.distance() == other.distance()
+    }
+}
+
+impl<T: Uint> Eq for Anchored<T> {}
+
+impl<T: Uint> PartialOrd for Anchored<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Uint> Ord for Anchored<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance().cmp(&other.distance())
+    }
+}
+
+/// A serial number ordered in *descending* distance from an anchor, so
+/// the serial number closest to the anchor is the greatest.
+///
+/// This is the reverse of `Anchored`: it inverts the serial number order
+/// for all serial numbers that follow the anchor, which turns a
+/// `BinaryHeap` (a max-heap) into a min-heap that yields the oldest serial
+/// number first.
+///
+/// All serial numbers compared with each other must share the same anchor.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BinaryHeap;
+///
+/// use sna::SerialNumber;
+/// use sna::order::Descending;
+///
+/// let anchor = SerialNumber(250u8);
+/// let mut heap: BinaryHeap<_> = [3u8, 251, 255, 0]
+///     .iter()
+///     .map(|&serial| Descending::new(SerialNumber(serial), anchor))
+///     .collect();
+///
+/// let oldest: Vec<_> = std::iter::from_fn(|| heap.pop())
+///     .map(|descending| descending.serial().0)
+///     .collect();
+/// assert_eq!(oldest, vec![251, 255, 0, 3]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Descending<T>(Anchored<T>);
+
+impl<T: Uint> Descending<T> {
+    /// Order `serial` relative to `anchor`.
+    #[inline]
+    pub fn new(serial: SerialNumber<T>, anchor: SerialNumber<T>) -> Self {
+        Descending(Anchored::new(serial, anchor))
+    }
+
+    /// Return the serial number.
+    #[inline]
+    pub fn serial(&self) -> SerialNumber<T> {
+        self.0.serial()
+    }
+
+    /// Return the anchor.
+    #[inline]
+    pub fn anchor(&self) -> SerialNumber<T> {
+        self.0.anchor()
+    }
+
+    /// Return the distance of the serial number counted up from the anchor.
+    #[inline]
+    pub fn distance(&self) -> T {
+        self.0.distance()
+    }
+
+    /// Return the successor of the serial number, keeping the anchor.
+    #[inline]
+    pub fn succ(self) -> Self {
+        Descending(self.0.succ())
+    }
+
+    /// Return the predecessor of the serial number, keeping the anchor.
+    #[inline]
+    pub fn pred(self) -> Self {
+        Descending(self.0.pred())
+    }
+
+    /// Advance the serial number by `n`, keeping the anchor (see
+    /// `SerialNumber::advance`).
+    ///
+    /// Note that the result is greater than `self` if it passes the anchor.
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        Descending(self.0.advance(n))
+    }
+
+    /// Consume the adapter, returning the serial number.
+    #[inline]
+    pub fn into_inner(self) -> SerialNumber<T> {
+        self.0.into_inner()
+    }
+}
+
+impl<T: Uint> From<Anchored<T>> for Descending<T> {
+    /// Invert the order of an anchored serial number.
+    #[inline]
+    fn from(anchored: Anchored<T>) -> Self {
+        Descending(anchored)
+    }
+}
+
+impl<T: Uint> PartialEq for Descending<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Uint> Eq for Descending<T> {}
+
+impl<T: Uint> PartialOrd for Descending<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Uint> Ord for Descending<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+/// A serial number ordered by serial number order, with a tie-break for
+/// serial numbers that are exactly half of the serial number space apart:
+/// of those, the one with the larger value is greater.
+///
+/// Unlike `SerialNumber`, this implements `Ord` consistently with
+/// `PartialOrd`, so it can be sorted and used as the key of ordered
+/// collections. However, serial number order is only transitive for serial
+/// numbers that span less than half of the serial number space. Sorting
+/// other serial numbers yields an unspecified order (or may panic) and
+/// ordered collections may behave unexpectedly, as with any inconsistent
+/// `Ord` implementation. Use `Anchored` if there is an anchor, as it is
+/// always a total order.
+///
+/// Requires the `total-order` feature.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::order::TieBreak;
+///
+/// assert!(TieBreak::new(SerialNumber(0u8)) < TieBreak::new(SerialNumber(128)));
+/// assert!(TieBreak::new(SerialNumber(0u8)) > TieBreak::new(SerialNumber(255)));
+///
+/// let mut serials: Vec<_> = [3u8, 250, 255].iter()
+///     .map(|&serial| TieBreak::new(SerialNumber(serial)))
+///     .collect();
+/// serials.sort();
+/// assert_eq!(serials.iter().map(|serial| serial.serial().0).collect::<Vec<_>>(), vec![250, 255, 3]);
+/// ```
+#[cfg(feature = "total-order")]
+#[derive(Clone, Copy, Debug)]
+pub struct TieBreak<T>(SerialNumber<T>);
+
+#[cfg(feature = "total-order")]
+impl<T: SerialOps> TieBreak<T> {
+    /// Order `serial` with the tie-break.
+    #[inline]
+    pub fn new(serial: SerialNumber<T>) -> Self {
+        TieBreak(serial)
+    }
+
+    /// Return the serial number.
+    #[inline]
+    pub fn serial(&self) -> SerialNumber<T> {
+        self.0
+    }
+
+    /// Consume the adapter, returning the serial number.
+    #[inline]
+    pub fn into_inner(self) -> SerialNumber<T> {
+        self.0
+    }
+}
+
+#[cfg(feature = "total-order")]
+impl<T: Uint> TieBreak<T> {
+    /// Return the successor of the serial number.
+    #[inline]
+    pub fn succ(self) -> Self {
+        TieBreak(self.0.succ())
+    }
+
+    /// Return the predecessor of the serial number.
+    #[inline]
+    pub fn pred(self) -> Self {
+        TieBreak(self.0.pred())
+    }
+
+    /// Advance the serial number by `n` (see `SerialNumber::advance`).
+    #[inline]
+    pub fn advance(self, n: T) -> Self {
+        TieBreak(self.0.advance(n))
+    }
+}
+
+#[cfg(feature = "total-order")]
+impl<T: SerialOps> From<SerialNumber<T>> for TieBreak<T> {
+    #[inline]
+    fn from(serial: SerialNumber<T>) -> Self {
+        TieBreak(serial)
+    }
+}
+
+#[cfg(feature = "total-order")]
+impl<T: SerialOps> PartialEq for TieBreak<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(feature = "total-order")]
+impl<T: SerialOps> Eq for TieBreak<T> {}
+
+#[cfg(feature = "total-order")]
+impl<T: SerialOps> PartialOrd for TieBreak<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "total-order")]
+impl<T: SerialOps> Ord for TieBreak<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or_else(|| (self.0).0.cmp(&(other.0).0))
+    }
+}
+
+/// Return the latest of `serials`, i.e. the one that every other one
+/// precedes or equals, or `None` if there are no serial numbers.
+///
+/// Returns an error if there is no such serial number, which is the case if
+/// the serial numbers span half of the serial number space or more, even
+/// if no two of them are exactly half of the serial number space apart.
+///
+/// # Examples
+///
+/// ```
+/// use sna::{Ambiguous, SerialNumber};
+/// use sna::order::serial_max;
+///
+/// let acknowledged = [SerialNumber(250u8), SerialNumber(3), SerialNumber(255)];
+/// assert_eq!(serial_max(acknowledged.iter().cloned()), Ok(Some(SerialNumber(3))));
+///
+/// // 0 precedes 100 precedes 200 precedes 0
+/// let serials = [SerialNumber(0u8), SerialNumber(100), SerialNumber(200)];
+/// assert_eq!(serial_max(serials.iter().cloned()), Err(Ambiguous));
+/// ```
+pub fn serial_max<T, I>(serials: I) -> Result<Option<SerialNumber<T>>, Ambiguous>
+    where T: Uint, I: IntoIterator<Item = SerialNumber<T>>, I::IntoIter: Clone
+{
+    extreme(serials.into_iter(), Ordering::Greater)
+}
+
+/// Return the earliest of `serials`, i.e. the one that precedes or equals
+/// every other one, or `None` if there are no serial numbers.
+///
+/// Returns an error if there is no such serial number, see `serial_max`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::order::serial_min;
+///
+/// let outstanding = [SerialNumber(3u8), SerialNumber(250), SerialNumber(255)];
+/// assert_eq!(serial_min(outstanding.iter().cloned()), Ok(Some(SerialNumber(250))));
+/// ```
+pub fn serial_min<T, I>(serials: I) -> Result<Option<SerialNumber<T>>, Ambiguous>
+    where T: Uint, I: IntoIterator<Item = SerialNumber<T>>, I::IntoIter: Clone
+{
+    extreme(serials.into_iter(), Ordering::Less)
+}
+
+/// Return the serial number of `serials` that is farthest from `anchor`,
+/// counted up from it, or `None` if there are no serial numbers.
+///
+/// This matches `serial_max` as long as all serial numbers are within half
+/// of the serial number space following `anchor`, but is always defined.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::order::serial_max_from;
+///
+/// let serials = [SerialNumber(0u8), SerialNumber(100), SerialNumber(200)];
+/// assert_eq!(serial_max_from(SerialNumber(150), serials.iter().cloned()), Some(SerialNumber(100)));
+/// ```
+pub fn serial_max_from<T, I>(anchor: SerialNumber<T>, serials: I) -> Option<SerialNumber<T>>
+    where T: Uint, I: IntoIterator<Item = SerialNumber<T>>
+{
+    serials.into_iter().map(|serial| Anchored::new(serial, anchor)).max().map(Anchored::into_inner)
+}
+
+/// Return the serial number of `serials` that is closest to `anchor`,
+/// counted up from it, or `None` if there are no serial numbers.
+///
+/// See `serial_max_from`.
+pub fn serial_min_from<T, I>(anchor: SerialNumber<T>, serials: I) -> Option<SerialNumber<T>>
+    where T: Uint, I: IntoIterator<Item = SerialNumber<T>>
+{
+    serials.into_iter().map(|serial| Anchored::new(serial, anchor)).min().map(Anchored::into_inner)
+}
+
+/// Return the serial number that every other one of `serials` is
+/// `ordering` than or equal to.
+fn extreme<T, I>(serials: I, ordering: Ordering) -> Result<Option<SerialNumber<T>>, Ambiguous>
+    where T: Uint, I: Iterator<Item = SerialNumber<T>> + Clone
+{
+    let mut candidate = None;
+    for serial in serials.clone() {
+        match candidate {
+            Some(current) if serial.partial_cmp(&current).ok_or(Ambiguous)? != ordering => {}
+            _ => candidate = Some(serial),
+        }
+    }
+
+    // The order is not transitive, so make sure the candidate is not
+    // exceeded by any serial number it has not been compared to
+    if let Some(candidate) = candidate {
+        for serial in serials {
+            if serial.partial_cmp(&candidate).ok_or(Ambiguous)? == ordering {
+                return Err(Ambiguous);
+            }
+        }
+    }
+    Ok(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn ordering() {
+        let anchor = SerialNumber(u32::MAX);
+        let oldest = Descending::new(anchor, anchor);
+        let newer = Descending::new(SerialNumber(0), anchor);
+        let newest = Descending::new(SerialNumber(u32::MAX - 1), anchor);
+        assert!(oldest > newer);
+        assert!(newer > newest);
+        assert_eq!(newest.distance(), u32::MAX);
+        assert_eq!(oldest, Descending::new(anchor, anchor));
+        assert_eq!(newer.into_inner(), SerialNumber(0));
+        assert_eq!(Descending::from(Anchored::new(SerialNumber(0), anchor)), newer);
+    }
+
+    #[test]
+    fn anchored() {
+        let anchor = SerialNumber(u32::MAX);
+        let oldest = Anchored::new(anchor, anchor);
+        let newer = Anchored::new(SerialNumber(0), anchor);
+        let newest = Anchored::new(SerialNumber(u32::MAX - 1), anchor);
+        assert!(oldest < newer);
+        assert!(newer < newest);
+        assert_eq!(newest.distance(), u32::MAX);
+
+        let oldest = oldest.reanchor(SerialNumber(0));
+        let newer = newer.reanchor(SerialNumber(0));
+        assert!(oldest > newer);
+        assert_eq!(oldest.anchor(), SerialNumber(0));
+        assert_eq!(oldest.into_inner(), anchor);
+    }
+
+    #[test]
+    fn anchored_succ_pred() {
+        let anchor = SerialNumber(u32::MAX);
+        let oldest = Anchored::new(anchor, anchor);
+        assert_eq!(oldest.succ().serial(), SerialNumber(0));
+        assert_eq!(oldest.succ().anchor(), anchor);
+        assert!(oldest.succ() > oldest);
+        assert_eq!(oldest.advance(3).distance(), 3);
+
+        // Passes the anchor
+        assert!(oldest.pred() > oldest);
+        assert_eq!(oldest.pred().distance(), u32::MAX);
+    }
+
+    #[test]
+    fn descending_succ_pred() {
+        let anchor = SerialNumber(u32::MAX);
+        let oldest = Descending::new(anchor, anchor);
+        assert_eq!(oldest.succ().serial(), SerialNumber(0));
+        assert_eq!(oldest.succ().anchor(), anchor);
+        assert!(oldest.succ() < oldest);
+        assert_eq!(oldest.advance(3).distance(), 3);
+
+        // Passes the anchor
+        assert!(oldest.pred() < oldest);
+        assert_eq!(oldest.pred().distance(), u32::MAX);
+        assert_eq!(oldest.succ().pred(), oldest);
+    }
+
+    #[test]
+    fn extremes() {
+        let none: [SerialNumber<u16>; 0] = [];
+        assert_eq!(serial_max(none.iter().cloned()), Ok(None));
+        assert_eq!(serial_min_from(SerialNumber(0), none.iter().cloned()), None);
+
+        let serials = [SerialNumber(65535u16), SerialNumber(65535), SerialNumber(3), SerialNumber(0)];
+        assert_eq!(serial_max(serials.iter().cloned()), Ok(Some(SerialNumber(3))));
+        assert_eq!(serial_min(serials.iter().cloned()), Ok(Some(SerialNumber(65535))));
+
+        // Exactly half of the serial number space apart
+        let serials = [SerialNumber(1u16), SerialNumber(1 + (1 << 15))];
+        assert_eq!(serial_max(serials.iter().cloned()), Err(Ambiguous));
+        assert_eq!(serial_min(serials.iter().cloned()), Err(Ambiguous));
+        assert_eq!(serial_max_from(SerialNumber(1), serials.iter().cloned()), Some(serials[1]));
+        assert_eq!(serial_min_from(SerialNumber(2), serials.iter().cloned()), Some(serials[1]));
+
+        // Not transitive, regardless of the order
+        let mut serials = [SerialNumber(0u8), SerialNumber(100), SerialNumber(200)];
+        for _ in 0..3 {
+            serials.rotate_left(1);
+            assert_eq!(serial_max(serials.iter().cloned()), Err(Ambiguous));
+            assert_eq!(serial_min(serials.iter().cloned()), Err(Ambiguous));
+        }
+    }
+
+    #[cfg(feature = "total-order")]
+    #[test]
+    fn tie_break() {
+        use alloc::collections::BTreeSet;
+
+        // 0 and 32768 are exactly half of the serial number space apart
+        let mut serials: Vec<_> = [32768u16, 1, 0, 100, 32767]
+            .iter()
+            .map(|&serial| TieBreak::new(SerialNumber(serial)))
+            .collect();
+        serials.sort();
+        let sorted: Vec<_> = serials.iter().map(|serial| serial.serial().0).collect();
+        assert_eq!(sorted, vec![0, 1, 100, 32767, 32768]);
+        assert!(TieBreak::new(SerialNumber(0u16)) < TieBreak::new(SerialNumber(32768)));
+        assert!(TieBreak::new(SerialNumber(32768u16)) > TieBreak::new(SerialNumber(0)));
+        assert_eq!(TieBreak::new(SerialNumber(1u32 << 31)).partial_cmp(&TieBreak::new(SerialNumber(0))),
+                   Some(Ordering::Greater));
+
+        let set: BTreeSet<_> = [32766u16, 0, 65535, 1].iter().cloned().map(SerialNumber).map(TieBreak::from).collect();
+        let set: Vec<_> = set.into_iter().map(TieBreak::into_inner).collect();
+        assert_eq!(set, vec![SerialNumber(65535), SerialNumber(0), SerialNumber(1), SerialNumber(32766)]);
+        assert_eq!(TieBreak::new(SerialNumber(255u8)).succ().serial(), SerialNumber(0));
+    }
+
+    #[test]
+    fn sort() {
+        let anchor = SerialNumber(65530u16);
+        let mut serials: Vec<_> = [2u16, 65535, 65530, 7]
+            .iter()
+            .map(|&serial| Descending::new(SerialNumber(serial), anchor))
+            .collect();
+        serials.sort();
+        let serials: Vec<_> = serials.iter().map(|descending| descending.serial().0).collect();
+        assert_eq!(serials, vec![7, 2, 65535, 65530]);
+    }
+}
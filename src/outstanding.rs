@@ -0,0 +1,195 @@
+//! Guarding against the exhaustion of the serial number space.
+//!
+//! Once more than `2^(n-1) - 1` serial numbers are outstanding, the oldest
+//! and the newest of them can not be ordered any more, so comparisons
+//! silently yield wrong results. Senders can use `OutstandingTracker` to
+//! stall before this happens.
+
+use std::error;
+use std::fmt;
+
+use SerialNumber;
+use range::SerialRange;
+use uint::Uint;
+
+/// The error returned by `OutstandingTracker::allocate` and
+/// `OutstandingTracker::allocate_range` if allocating would exceed the
+/// limit of outstanding serial numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Exhausted;
+
+impl fmt::Display for Exhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("too many serial numbers are outstanding")
+    }
+}
+
+impl error::Error for Exhausted {}
+
+/// Tracks the span of serial numbers that have been allocated but not
+/// acknowledged, yet, and refuses to allocate beyond a limit.
+///
+/// Serial numbers are allocated in order and acknowledged cumulatively, so
+/// only the oldest outstanding serial number and the next one to be
+/// allocated are stored.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::outstanding::{Exhausted, OutstandingTracker};
+///
+/// let mut tracker = OutstandingTracker::new(SerialNumber(250u8));
+/// for _ in 0..127 {
+///     tracker.allocate().unwrap();
+/// }
+/// assert_eq!(tracker.next(), SerialNumber(121));
+///
+/// // 127 serial numbers (250 up to 120) are outstanding, which is the limit
+/// assert_eq!(tracker.allocate(), Err(Exhausted));
+///
+/// assert!(tracker.acknowledge(SerialNumber(251)));
+/// assert_eq!(tracker.available(), 2);
+/// assert_eq!(tracker.allocate(), Ok(SerialNumber(121)));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct OutstandingTracker<T> {
+    oldest: SerialNumber<T>,
+    next: SerialNumber<T>,
+    limit: T,
+}
+
+impl<T: Uint> OutstandingTracker<T> where SerialNumber<T>: PartialOrd {
+    /// Create a tracker where `initial` is the first serial number to be
+    /// allocated, allowing up to `2^(n-1) - 1` outstanding serial numbers.
+    #[inline]
+    pub fn new(initial: SerialNumber<T>) -> Self {
+        Self::with_limit(initial, T::HALF.wrapping_sub(T::ONE))
+    }
+
+    /// Create a tracker where `initial` is the first serial number to be
+    /// allocated, allowing up to `limit` outstanding serial numbers, e.g. to
+    /// apply back-pressure well before the serial number space is exhausted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is not below half of the serial number space.
+    #[inline]
+    pub fn with_limit(initial: SerialNumber<T>, limit: T) -> Self {
+        assert!(limit < T::HALF, "limit exceeds the range defined by RFC 1982");
+        OutstandingTracker { oldest: initial, next: initial, limit }
+    }
+
+    /// Return the maximum amount of outstanding serial numbers.
+    #[inline]
+    pub fn limit(&self) -> T {
+        self.limit
+    }
+
+    /// Return the oldest outstanding serial number, if any.
+    #[inline]
+    pub fn oldest(&self) -> Option<SerialNumber<T>> {
+        if self.oldest == self.next { None } else { Some(self.oldest) }
+    }
+
+    /// Return the next serial number to be allocated.
+    #[inline]
+    pub fn next(&self) -> SerialNumber<T> {
+        self.next
+    }
+
+    /// Return the amount of outstanding serial numbers.
+    #[inline]
+    pub fn outstanding(&self) -> T {
+        self.next.0.wrapping_sub(self.oldest.0)
+    }
+
+    /// Return the amount of serial numbers that can be allocated before the
+    /// limit is reached.
+    #[inline]
+    pub fn available(&self) -> T {
+        self.limit.wrapping_sub(self.outstanding())
+    }
+
+    /// Return whether no serial number can be allocated, i.e. the sender
+    /// has to stall until serial numbers are acknowledged.
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.available() == T::ZERO
+    }
+
+    /// Return whether `serial` is outstanding.
+    #[inline]
+    pub fn is_outstanding(&self, serial: SerialNumber<T>) -> bool {
+        serial.0.wrapping_sub(self.oldest.0) < self.outstanding()
+    }
+
+    /// Allocate the next serial number.
+    pub fn allocate(&mut self) -> Result<SerialNumber<T>, Exhausted> {
+        self.allocate_range(T::ONE).map(|range| range.start())
+    }
+
+    /// Allocate the next `n` serial numbers.
+    ///
+    /// Allocates nothing if the limit would be exceeded.
+    pub fn allocate_range(&mut self, n: T) -> Result<SerialRange<T>, Exhausted> {
+        if n > self.available() {
+            return Err(Exhausted);
+        }
+        let start = self.next;
+        self.next = SerialNumber(start.0.wrapping_add(n));
+        Ok(SerialRange::new(start, self.next))
+    }
+
+    /// Acknowledge all serial numbers up to and including `serial`.
+    ///
+    /// Returns `false` and acknowledges nothing if `serial` is not
+    /// outstanding.
+    pub fn acknowledge(&mut self, serial: SerialNumber<T>) -> bool {
+        if !self.is_outstanding(serial) {
+            return false;
+        }
+        self.oldest = serial.succ();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit() {
+        let mut tracker = OutstandingTracker::new(SerialNumber(u16::MAX));
+        assert_eq!(tracker.oldest(), None);
+        let range = tracker.allocate_range(32767).unwrap();
+        assert_eq!(range.start(), SerialNumber(u16::MAX));
+        assert!(tracker.is_exhausted());
+        assert_eq!(tracker.allocate_range(1), Err(Exhausted));
+
+        // The oldest and the newest outstanding serial numbers can still be
+        // ordered
+        let newest = tracker.next().pred();
+        assert!(SerialNumber(u16::MAX) < newest);
+        assert!(tracker.is_outstanding(newest));
+        assert!(!tracker.is_outstanding(tracker.next()));
+
+        assert!(!tracker.acknowledge(tracker.next()));
+        assert!(tracker.acknowledge(newest));
+        assert_eq!(tracker.oldest(), None);
+        assert_eq!(tracker.available(), 32767);
+    }
+
+    #[test]
+    fn back_pressure() {
+        let mut tracker = OutstandingTracker::with_limit(SerialNumber(0u32), 3);
+        assert_eq!(tracker.allocate_range(2).map(|range| range.len()), Ok(2));
+        assert_eq!(tracker.allocate_range(2), Err(Exhausted));
+        assert_eq!(tracker.allocate(), Ok(SerialNumber(2)));
+        assert_eq!(tracker.allocate(), Err(Exhausted));
+        assert!(tracker.acknowledge(SerialNumber(0)));
+        assert_eq!(tracker.oldest(), Some(SerialNumber(1)));
+        assert_eq!(tracker.outstanding(), 2);
+        assert_eq!(tracker.allocate(), Ok(SerialNumber(3)));
+    }
+}
@@ -0,0 +1,150 @@
+//! Allocation of packet identifiers that must not collide with identifiers
+//! still in flight, e.g. the 16-bit packet identifiers of MQTT, see
+//! [chapter 2.2.1 of MQTT 5.0](https://docs.oasis-open.org/mqtt/mqtt/v5.0/os/mqtt-v5.0-os.html#_Toc3901026).
+//!
+//! Identifiers are handed out in serial number order, wrapping around and
+//! skipping those that have not been released yet. Zero is never handed
+//! out since MQTT reserves it.
+//!
+//! Requires the `alloc` feature.
+
+use alloc::collections::BTreeSet;
+
+use SerialNumber;
+use uint::Uint;
+
+/// An allocator of non-zero packet identifiers.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::packet_id::IdAllocator;
+///
+/// let mut ids = IdAllocator::<u16>::new();
+/// let first = ids.allocate().unwrap();
+/// assert_eq!(first, SerialNumber(1u16));
+///
+/// // Still in flight after wrapping around, so it is skipped
+/// ids.skip_to(SerialNumber(u16::MAX));
+/// assert_eq!(ids.allocate(), Some(SerialNumber(u16::MAX)));
+/// assert_eq!(ids.allocate(), Some(SerialNumber(2)));
+///
+/// assert!(ids.release(first));
+/// assert!(!ids.release(first));
+/// ```
+#[derive(Clone, Debug)]
+pub struct IdAllocator<T> {
+    next: SerialNumber<T>,
+    in_flight: BTreeSet<T>,
+}
+
+impl<T: Uint> IdAllocator<T> {
+    /// Create an allocator that starts with identifier `1`.
+    #[inline]
+    pub fn new() -> Self {
+        IdAllocator { next: SerialNumber(T::ONE), in_flight: BTreeSet::new() }
+    }
+
+    /// Return the number of identifiers in flight.
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Return whether `id` has been allocated and not released yet.
+    #[inline]
+    pub fn is_in_flight(&self, id: SerialNumber<T>) -> bool {
+        self.in_flight.contains(&id.0)
+    }
+
+    /// Return whether every identifier is in flight, i.e. `allocate` fails
+    /// until an identifier is released.
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.in_flight.len() as u128 >= T::MAX.to_u128()
+    }
+
+    /// Let the next allocation start searching at `id` instead of the
+    /// identifier following the last allocated one, e.g. to resume after a
+    /// reconnect. Zero is skipped.
+    #[inline]
+    pub fn skip_to(&mut self, id: SerialNumber<T>) {
+        self.next = id;
+    }
+
+    /// Allocate the next identifier that is not in flight.
+    ///
+    /// Returns `None` if every identifier is in flight.
+    pub fn allocate(&mut self) -> Option<SerialNumber<T>> {
+        if self.is_exhausted() {
+            return None;
+        }
+        let mut id = self.next.0;
+        while id == T::ZERO || self.in_flight.contains(&id) {
+            id = id.wrapping_add(T::ONE);
+        }
+        self.in_flight.insert(id);
+        self.next = SerialNumber(id.wrapping_add(T::ONE));
+        Some(SerialNumber(id))
+    }
+
+    /// Release `id`, so it can be allocated again.
+    ///
+    /// Returns whether `id` was in flight.
+    #[inline]
+    pub fn release(&mut self, id: SerialNumber<T>) -> bool {
+        self.in_flight.remove(&id.0)
+    }
+
+    /// Release all identifiers.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.in_flight.clear();
+    }
+}
+
+impl<T: Uint> Default for IdAllocator<T> {
+    #[inline]
+    fn default() -> Self {
+        IdAllocator::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    #[test]
+    fn exhaustion() {
+        let mut ids = IdAllocator::<u8>::new();
+        for expected in 1..=u8::MAX {
+            assert_eq!(ids.allocate(), Some(SerialNumber(expected)));
+        }
+        assert!(ids.is_exhausted());
+        assert_eq!(ids.allocate(), None);
+
+        assert!(ids.release(SerialNumber(7)));
+        assert!(!ids.is_exhausted());
+        assert_eq!(ids.allocate(), Some(SerialNumber(7)));
+        assert_eq!(ids.allocate(), None);
+
+        ids.clear();
+        assert_eq!(ids.in_flight(), 0);
+        assert_eq!(ids.allocate(), Some(SerialNumber(8)));
+    }
+
+    #[test]
+    fn wraps_in_order() {
+        let mut ids = IdAllocator::<u16>::new();
+        ids.skip_to(SerialNumber(u16::MAX - 1));
+        let allocated: Vec<_> = (0..4).map(|_| ids.allocate().unwrap()).collect();
+        assert_eq!(allocated, [SerialNumber(u16::MAX - 1), SerialNumber(u16::MAX),
+                               SerialNumber(1), SerialNumber(2)]);
+        assert!(allocated.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids.is_in_flight(SerialNumber(1)));
+        assert!(!ids.is_in_flight(SerialNumber(0)));
+    }
+}
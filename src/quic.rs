@@ -0,0 +1,166 @@
+//! Truncated packet numbers of QUIC.
+//!
+//! QUIC packet numbers are 62-bit integers that only ever increase, but
+//! packets only carry their 1 to 4 least significant bytes. The receiver
+//! reconstructs the full packet number relative to the largest one it has
+//! processed, see
+//! [appendix A of RFC 9000](https://www.rfc-editor.org/rfc/rfc9000#appendix-A).
+//!
+//! Decoding is W-LSB decoding (see `wlsb`) with an interpretation interval
+//! centered on the next expected packet number.
+
+use SerialNumber;
+use wlsb::{Shift, WlsbDecoder};
+
+/// The largest packet number (`2^62 - 1`).
+pub const MAX_PACKET_NUMBER: u64 = (1 << 62) - 1;
+
+/// Truncate `full_pn` for transmission, returning the truncated packet
+/// number and its length in bytes (1 to 4).
+///
+/// `largest_acked` is the largest packet number acknowledged by the peer so
+/// far, if any.
+///
+/// # Panics
+///
+/// Panics if `full_pn` exceeds `MAX_PACKET_NUMBER`, does not follow
+/// `largest_acked` or is too far ahead of it to be encoded in 4 bytes.
+///
+/// # Examples
+///
+/// ```
+/// use sna::quic::encode_truncated;
+///
+/// // The example of appendix A.2 of RFC 9000
+/// assert_eq!(encode_truncated(0xac5c02, Some(0xabe8b3)), (0x5c02, 2));
+/// assert_eq!(encode_truncated(0xace8fe, Some(0xabe8b3)), (0xace8fe, 3));
+/// ```
+pub fn encode_truncated(full_pn: u64, largest_acked: Option<u64>) -> (u32, usize) {
+    assert!(full_pn <= MAX_PACKET_NUMBER, "packet number exceeds 2^62 - 1");
+    let unacked = match largest_acked {
+        Some(largest_acked) => {
+            assert!(full_pn > largest_acked, "packet number must follow the largest acknowledged");
+            full_pn - largest_acked
+        }
+        None => full_pn + 1,
+    };
+    let bits = 64 - (unacked - 1).leading_zeros() + 1;
+    let len = bits.div_ceil(8) as usize;
+    assert!(len <= 4, "packet number too far ahead of the largest acknowledged");
+    let mask = (1u64 << (len * 8)) - 1;
+    ((full_pn & mask) as u32, len)
+}
+
+/// Reconstruct the full packet number from the `bits` least significant
+/// bits in `truncated`, given the largest packet number processed so far.
+///
+/// Bits of `truncated` above `bits` are ignored. The result is the packet
+/// number closest to `largest_pn + 1` within the range of packet numbers.
+///
+/// # Panics
+///
+/// Panics if `bits` is zero or exceeds 62.
+///
+/// # Examples
+///
+/// ```
+/// use sna::quic::decode_truncated;
+///
+/// // The example of appendix A.3 of RFC 9000
+/// assert_eq!(decode_truncated(0x9b32, 16, 0xa82f30ea), 0xa82f9b32);
+/// ```
+pub fn decode_truncated(truncated: u64, bits: u32, largest_pn: u64) -> u64 {
+    assert!(bits > 0 && bits <= 62, "bits must be between 1 and 62");
+    let window = 1u64 << bits;
+    // Center the interval on the expected packet number, so it covers
+    // `(expected - window / 2, expected + window / 2]`
+    let expected = SerialNumber(largest_pn.wrapping_add(1));
+    let decoder = WlsbDecoder::new(expected, Shift::Variable(|k| (1 << (k - 1)) - 1));
+    let candidate = decoder.decode(truncated, bits).0;
+    if candidate > MAX_PACKET_NUMBER {
+        // The interval crosses either zero or 2^62, use the adjacent window
+        // that is within range instead
+        if candidate >> 63 == 1 {
+            candidate.wrapping_add(window)
+        } else {
+            candidate - window
+        }
+    } else {
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The sample algorithm of appendix A.3 of RFC 9000.
+    fn reference_decode(truncated: u64, bits: u32, largest_pn: u64) -> u64 {
+        let expected = largest_pn as i128 + 1;
+        let window = 1i128 << bits;
+        let half = window / 2;
+        let mask = window - 1;
+        let candidate = (expected & !mask) | (truncated as i128 & mask);
+        if candidate <= expected - half && candidate < (1 << 62) - window {
+            (candidate + window) as u64
+        } else if candidate > expected + half && candidate >= window {
+            (candidate - window) as u64
+        } else {
+            candidate as u64
+        }
+    }
+
+    #[test]
+    fn matches_reference() {
+        // No packet can follow `MAX_PACKET_NUMBER`, so it is not a valid
+        // largest packet number to decode against
+        let largest = [0, 1, 127, 128, 255, 256, 65535, 1 << 31,
+                       MAX_PACKET_NUMBER - 256, MAX_PACKET_NUMBER - 2, MAX_PACKET_NUMBER - 1];
+        for &bits in &[1, 7, 8, 16] {
+            for &largest_pn in &largest {
+                for truncated in 0..(1u64 << bits) {
+                    assert_eq!(decode_truncated(truncated, bits, largest_pn),
+                               reference_decode(truncated, bits, largest_pn),
+                               "truncated {} bits {} largest {}", truncated, bits, largest_pn);
+                }
+            }
+        }
+        for &largest_pn in &largest {
+            for &truncated in &[0, 1, (1 << 31) - 1, 1 << 31, u32::MAX as u64] {
+                assert_eq!(decode_truncated(truncated, 32, largest_pn),
+                           reference_decode(truncated, 32, largest_pn));
+            }
+        }
+    }
+
+    #[test]
+    fn encode_lengths() {
+        assert_eq!(encode_truncated(0, None), (0, 1));
+        assert_eq!(encode_truncated(127, None), (127, 1));
+        assert_eq!(encode_truncated(128, None), (128, 2));
+        assert_eq!(encode_truncated(129, Some(1)), (129, 1));
+        assert_eq!(encode_truncated(130, Some(1)), (130, 2));
+        assert_eq!(encode_truncated(0x1_0000_0000, Some(0x1_0000_0000 - (1 << 31))), (0, 4));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let starts = [0, 200, 65530, (1 << 32) - 10, MAX_PACKET_NUMBER - 70000];
+        for &start in &starts {
+            for gap in &[1, 2, 127, 128, 32767, 32768, 65535] {
+                let full_pn = start + gap;
+                let (truncated, len) = encode_truncated(full_pn, Some(start));
+                assert_eq!(decode_truncated(u64::from(truncated), len as u32 * 8, start), full_pn);
+            }
+        }
+        let (truncated, len) = encode_truncated(MAX_PACKET_NUMBER, Some(MAX_PACKET_NUMBER - 1));
+        assert_eq!(decode_truncated(u64::from(truncated), len as u32 * 8, MAX_PACKET_NUMBER - 1),
+                   MAX_PACKET_NUMBER);
+    }
+
+    #[test]
+    #[should_panic]
+    fn encode_too_far_ahead() {
+        encode_truncated((1 << 31) + 1, Some(0));
+    }
+}
@@ -0,0 +1,154 @@
+//! Random sampling of serial numbers, e.g. for randomized initial sequence
+//! numbers or fuzzing protocol implementations.
+//!
+//! `Standard` samples from the whole serial number space. `Uniform` samples
+//! from a range that wraps if `high` is numerically smaller than `low`, and
+//! a `SerialRange` can be sampled from directly.
+//!
+//! Requires the `rand` feature.
+
+use rand::Rng;
+use rand::distributions::{Distribution, Standard};
+use rand::distributions::uniform::{SampleBorrow, SampleUniform, UniformInt, UniformSampler};
+
+use SerialNumber;
+use range::SerialRange;
+
+/// Samples serial numbers uniformly from a range that may cross the wrap
+/// point.
+///
+/// Created by `Uniform::new` and `Uniform::new_inclusive`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rand;
+/// # extern crate sna;
+/// use rand::distributions::{Distribution, Uniform};
+/// use sna::SerialNumber;
+///
+/// // Within the next 2^14 serial numbers following `x`
+/// let x = SerialNumber(60000u16);
+/// let within = Uniform::new(x, x + (1 << 14));
+/// let serial = within.sample(&mut rand::thread_rng());
+///
+/// assert!(serial >= x);
+/// assert!(serial < x + (1 << 14));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct UniformSerial<T: SampleUniform> {
+    low: T,
+    offset: UniformInt<T>,
+}
+
+macro_rules! rand_impl {
+    ($T:ty) => {
+        impl Distribution<SerialNumber<$T>> for Standard {
+            #[inline]
+            fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> SerialNumber<$T> {
+                SerialNumber(rng.gen())
+            }
+        }
+
+        impl SampleUniform for SerialNumber<$T> {
+            type Sampler = UniformSerial<$T>;
+        }
+
+        impl UniformSampler for UniformSerial<$T> {
+            type X = SerialNumber<$T>;
+
+            /// Sample from `[low, high)`, wrapping if `high` is numerically
+            /// smaller than `low`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the range is empty, i.e. `low` equals `high`.
+            fn new<B1, B2>(low: B1, high: B2) -> Self
+                where B1: SampleBorrow<Self::X> + Sized, B2: SampleBorrow<Self::X> + Sized
+            {
+                let (low, high) = (low.borrow().0, high.borrow().0);
+                assert!(low != high, "Uniform::new called with an empty range");
+                UniformSerial { low, offset: UniformInt::new(0, high.wrapping_sub(low)) }
+            }
+
+            /// Sample from `[low, high]`, wrapping if `high` is numerically
+            /// smaller than `low`.
+            fn new_inclusive<B1, B2>(low: B1, high: B2) -> Self
+                where B1: SampleBorrow<Self::X> + Sized, B2: SampleBorrow<Self::X> + Sized
+            {
+                let (low, high) = (low.borrow().0, high.borrow().0);
+                UniformSerial { low, offset: UniformInt::new_inclusive(0, high.wrapping_sub(low)) }
+            }
+
+            #[inline]
+            fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> SerialNumber<$T> {
+                SerialNumber(self.low.wrapping_add(self.offset.sample(rng)))
+            }
+        }
+
+        impl Distribution<SerialNumber<$T>> for SerialRange<$T> {
+            /// Sample a serial number of the range.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the range is empty.
+            #[inline]
+            fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> SerialNumber<$T> {
+                UniformSerial::<$T>::new(self.start(), self.end()).sample(rng)
+            }
+        }
+    };
+}
+
+rand_impl!(u8);
+rand_impl!(u16);
+rand_impl!(u32);
+rand_impl!(u64);
+rand_impl!(u128);
+rand_impl!(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::distributions::Uniform;
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn wrapping_range() {
+        let mut rng = StepRng::new(0, 0x0101_0101_0101_0101);
+        let range = SerialRange::new(SerialNumber(250u8), SerialNumber(5u8));
+        let uniform = Uniform::new(SerialNumber(250u8), SerialNumber(5u8));
+        for _ in 0..1000 {
+            assert!(range.contains(range.sample(&mut rng)));
+            assert!(range.contains(uniform.sample(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn inclusive() {
+        let mut rng = StepRng::new(0, 0x0123_4567_89ab_cdef);
+        let uniform = Uniform::new_inclusive(SerialNumber(u16::MAX), SerialNumber(0u16));
+        for _ in 0..100 {
+            let serial = uniform.sample(&mut rng);
+            assert!(serial == SerialNumber(u16::MAX) || serial == SerialNumber(0));
+        }
+
+        // The whole serial number space
+        let uniform = Uniform::new_inclusive(SerialNumber(1u32), SerialNumber(0u32));
+        let _ = uniform.sample(&mut rng);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty() {
+        Uniform::new(SerialNumber(3u64), SerialNumber(3u64));
+    }
+
+    #[test]
+    fn standard() {
+        let mut rng = StepRng::new(u64::MAX, 1);
+        let serial: SerialNumber<u32> = rng.gen();
+        assert_eq!(serial, SerialNumber(u32::MAX));
+    }
+}
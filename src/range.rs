@@ -0,0 +1,434 @@
+//! Half-open ranges of serial numbers that may cross the wrap point.
+
+use std::iter::Rev;
+use std::ops::{Bound, Range, RangeBounds, RangeInclusive};
+
+use SerialNumber;
+use uint::Uint;
+
+/// A half-open range `[start, end)` of serial numbers.
+///
+/// The range covers all serial numbers reached by repeatedly adding one to
+/// `start` until `end` is reached, so it *wraps* if `end` is numerically
+/// smaller than `start`. A range where `start` equals `end` is empty.
+///
+/// # Examples
+///
+/// ```
+/// use sna::{SerialNumber, SerialRange};
+///
+/// let range = SerialRange::new(SerialNumber(250u8), SerialNumber(5u8));
+///
+/// assert_eq!(range.len(), 11);
+/// assert!(range.contains(SerialNumber(255u8)));
+/// assert!(range.contains(SerialNumber(0u8)));
+/// assert!(!range.contains(SerialNumber(5u8)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub struct SerialRange<T> {
+    start: SerialNumber<T>,
+    end: SerialNumber<T>,
+}
+
+impl<T: Uint> SerialRange<T> {
+    /// Create a range from `start` (inclusive) to `end` (exclusive).
+    #[inline]
+    pub fn new(start: SerialNumber<T>, end: SerialNumber<T>) -> Self {
+        SerialRange { start, end }
+    }
+
+    /// Create a range from standard range syntax over serial numbers,
+    /// e.g. `a..b` or `a..=b`.
+    ///
+    /// The bounds are interpreted with wrapping, so `a..b` covers the
+    /// serial numbers reached by counting up from `a` until `b`. Returns
+    /// `None` if a bound is unbounded or if the range would cover the whole
+    /// serial number space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{SerialNumber, SerialRange};
+    ///
+    /// let range = SerialRange::from_bounds(SerialNumber(250u8)..=SerialNumber(4u8)).unwrap();
+    /// assert_eq!(range, SerialRange::new(SerialNumber(250u8), SerialNumber(5u8)));
+    ///
+    /// assert_eq!(SerialRange::from_bounds(SerialNumber(1u8)..=SerialNumber(0u8)), None);
+    /// assert_eq!(SerialRange::from_bounds(SerialNumber(1u8)..), None);
+    /// ```
+    pub fn from_bounds<R: RangeBounds<SerialNumber<T>>>(bounds: R) -> Option<Self> {
+        let start = match bounds.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => SerialNumber(start.0.wrapping_add(T::ONE)),
+            Bound::Unbounded => return None,
+        };
+        let end = match bounds.end_bound() {
+            Bound::Included(end) if end.0.wrapping_add(T::ONE) == start.0 => return None,
+            Bound::Included(end) => SerialNumber(end.0.wrapping_add(T::ONE)),
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => return None,
+        };
+        Some(SerialRange::new(start, end))
+    }
+
+    /// Return the first serial number of the range.
+    #[inline]
+    pub fn start(&self) -> SerialNumber<T> {
+        self.start
+    }
+
+    /// Return the serial number following the last one of the range.
+    #[inline]
+    pub fn end(&self) -> SerialNumber<T> {
+        self.end
+    }
+
+    /// Return the amount of serial numbers covered by the range.
+    #[inline]
+    pub fn len(&self) -> T {
+        self.end.0.wrapping_sub(self.start.0)
+    }
+
+    /// Return whether the range covers no serial numbers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Return whether `serial` is covered by the range.
+    #[inline]
+    pub fn contains(&self, serial: SerialNumber<T>) -> bool {
+        serial.0.wrapping_sub(self.start.0) < self.len()
+    }
+
+    /// Return an iterator over the serial numbers of the range, starting at
+    /// `start` and wrapping at the largest representable number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{SerialNumber, SerialRange};
+    ///
+    /// let range = SerialRange::new(SerialNumber(254u8), SerialNumber(1u8));
+    /// let serials: Vec<_> = range.iter().map(|serial| serial.0).collect();
+    /// assert_eq!(serials, vec![254, 255, 0]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<T> {
+        Iter { front: self.start, back: self.end }
+    }
+
+    /// Return an iterator over the serial numbers of the range in reverse,
+    /// i.e. newest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{SerialNumber, SerialRange};
+    ///
+    /// let range = SerialRange::new(SerialNumber(254u8), SerialNumber(1u8));
+    /// let serials: Vec<_> = range.rev().map(|serial| serial.0).collect();
+    /// assert_eq!(serials, vec![0, 255, 254]);
+    /// ```
+    #[inline]
+    pub fn rev(&self) -> Rev<Iter<T>> {
+        self.iter().rev()
+    }
+
+    /// Split the range at the wrap point into ordinary, non-wrapping ranges.
+    ///
+    /// Yields no range if the range is empty, one range if it does not wrap
+    /// and two ranges (the part up to the largest representable number
+    /// first) if it does. The ranges are inclusive because the segment up to
+    /// the largest representable number can not be expressed as a half-open
+    /// range of the same type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{SerialNumber, SerialRange};
+    ///
+    /// let range = SerialRange::new(SerialNumber(250u8), SerialNumber(5u8));
+    /// let segments: Vec<_> = range.to_linear_segments().collect();
+    /// assert_eq!(segments, vec![250..=255, 0..=4]);
+    ///
+    /// let range = SerialRange::new(SerialNumber(10u8), SerialNumber(20u8));
+    /// let segments: Vec<_> = range.to_linear_segments().collect();
+    /// assert_eq!(segments, vec![10..=19]);
+    /// ```
+    pub fn to_linear_segments(&self) -> LinearSegments<T> {
+        let last = self.end.0.wrapping_sub(T::ONE);
+        let (first, second) = if self.is_empty() {
+            (None, None)
+        } else if self.start.0 <= last {
+            (Some(self.start.0..=last), None)
+        } else if self.end.0 == T::ZERO {
+            (Some(self.start.0..=T::MAX), None)
+        } else {
+            (Some(self.start.0..=T::MAX), Some(T::ZERO..=last))
+        };
+        LinearSegments { first, second }
+    }
+}
+
+impl<T: Uint> From<Range<SerialNumber<T>>> for SerialRange<T> {
+    /// Convert from `start..end`, wrapping if `end` is numerically smaller
+    /// than `start`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::{SerialNumber, SerialRange};
+    ///
+    /// let range = SerialRange::from(SerialNumber(250u8)..SerialNumber(5u8));
+    /// assert_eq!(range.len(), 11);
+    /// ```
+    #[inline]
+    fn from(range: Range<SerialNumber<T>>) -> Self {
+        SerialRange::new(range.start, range.end)
+    }
+}
+
+impl<T> RangeBounds<SerialNumber<T>> for SerialRange<T> {
+    #[inline]
+    fn start_bound(&self) -> Bound<&SerialNumber<T>> {
+        Bound::Included(&self.start)
+    }
+
+    #[inline]
+    fn end_bound(&self) -> Bound<&SerialNumber<T>> {
+        Bound::Excluded(&self.end)
+    }
+}
+
+impl<T: Uint> IntoIterator for SerialRange<T> {
+    type Item = SerialNumber<T>;
+    type IntoIter = Iter<T>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<T> {
+        self.iter()
+    }
+}
+
+impl<T: Uint> SerialNumber<T> {
+    /// Return an iterator from `self` (inclusive) to `end` (exclusive) that
+    /// wraps at the end of the serial number space.
+    ///
+    /// This is a shorthand for iterating `SerialRange::new(self, end)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::SerialNumber;
+    ///
+    /// let serials: Vec<_> = SerialNumber(254u8).range_to(SerialNumber(2u8)).collect();
+    /// assert_eq!(serials, vec![SerialNumber(254), SerialNumber(255), SerialNumber(0), SerialNumber(1)]);
+    /// ```
+    #[inline]
+    pub fn range_to(self, end: Self) -> Iter<T> {
+        SerialRange::new(self, end).iter()
+    }
+
+    /// Return an endless iterator from `start` (inclusive) that wraps at the
+    /// end of the serial number space, e.g. to be limited with `take`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::SerialNumber;
+    ///
+    /// let serials: Vec<_> = SerialNumber::iter_from(SerialNumber(255u8)).take(3).collect();
+    /// assert_eq!(serials, vec![SerialNumber(255), SerialNumber(0), SerialNumber(1)]);
+    /// ```
+    #[inline]
+    pub fn iter_from(start: Self) -> IterFrom<T> {
+        IterFrom { next: start }
+    }
+}
+
+/// An iterator over the serial numbers of a `SerialRange`.
+///
+/// Created by `SerialRange::iter`.
+#[derive(Clone, Debug)]
+pub struct Iter<T> {
+    front: SerialNumber<T>,
+    back: SerialNumber<T>,
+}
+
+impl<T: Uint> Iterator for Iter<T> {
+    type Item = SerialNumber<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<SerialNumber<T>> {
+        if self.front == self.back {
+            return None;
+        }
+        let serial = self.front;
+        self.front = SerialNumber(serial.0.wrapping_add(T::ONE));
+        Some(serial)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back.0.wrapping_sub(self.front.0).to_u128();
+        if len > usize::MAX as u128 {
+            (usize::MAX, None)
+        } else {
+            (len as usize, Some(len as usize))
+        }
+    }
+}
+
+impl<T: Uint> DoubleEndedIterator for Iter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<SerialNumber<T>> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back = SerialNumber(self.back.0.wrapping_sub(T::ONE));
+        Some(self.back)
+    }
+}
+
+/// An endless iterator over the serial numbers following a start serial
+/// number.
+///
+/// Created by `SerialNumber::iter_from`.
+#[derive(Clone, Debug)]
+pub struct IterFrom<T> {
+    next: SerialNumber<T>,
+}
+
+impl<T: Uint> Iterator for IterFrom<T> {
+    type Item = SerialNumber<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<SerialNumber<T>> {
+        let serial = self.next;
+        self.next = SerialNumber(serial.0.wrapping_add(T::ONE));
+        Some(serial)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<SerialNumber<T>> {
+        self.next = SerialNumber(self.next.0.wrapping_add(T::from_u128(n as u128)));
+        self.next()
+    }
+}
+
+/// An iterator over the non-wrapping segments of a `SerialRange`.
+///
+/// Created by `SerialRange::to_linear_segments`.
+#[derive(Clone, Debug)]
+pub struct LinearSegments<T> {
+    first: Option<RangeInclusive<T>>,
+    second: Option<RangeInclusive<T>>,
+}
+
+impl<T> Iterator for LinearSegments<T> {
+    type Item = RangeInclusive<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<RangeInclusive<T>> {
+        match self.first.take() {
+            Some(segment) => Some(segment),
+            None => self.second.take(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn segments<T: Uint>(start: T, end: T) -> Vec<RangeInclusive<T>> {
+        SerialRange::new(SerialNumber(start), SerialNumber(end)).to_linear_segments().collect()
+    }
+
+    #[test]
+    fn empty() {
+        assert!(SerialRange::new(SerialNumber(7u16), SerialNumber(7u16)).is_empty());
+        assert_eq!(segments(7u16, 7), vec![]);
+        assert_eq!(segments(0u16, 0), vec![]);
+    }
+
+    #[test]
+    fn linear() {
+        assert_eq!(segments(0u32, 1), vec![0..=0]);
+        assert_eq!(segments(0u32, u32::MAX), vec![0..=u32::MAX - 1]);
+    }
+
+    #[test]
+    fn up_to_max() {
+        assert_eq!(segments(200u8, 0), vec![200..=255]);
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(segments(u64::MAX, 1), vec![u64::MAX..=u64::MAX, 0..=0]);
+        assert_eq!(segments(1u8, 0u8).len(), 1);
+        assert_eq!(segments(2u8, 1u8), vec![2..=255, 0..=0]);
+    }
+
+    #[test]
+    fn from_bounds() {
+        let range = |start: u32, end: u32| SerialRange::new(SerialNumber(start), SerialNumber(end));
+        let (zero, max) = (SerialNumber(0u32), SerialNumber(u32::MAX));
+        assert_eq!(SerialRange::from_bounds(max..zero), Some(range(u32::MAX, 0)));
+        assert_eq!(SerialRange::from_bounds(max..=zero), Some(range(u32::MAX, 1)));
+        assert_eq!(SerialRange::from_bounds(zero..=max), None);
+        assert_eq!(SerialRange::from_bounds(zero..zero), Some(range(0, 0)));
+        assert_eq!(SerialRange::from_bounds(..zero), None);
+        assert_eq!(SerialRange::from_bounds((Bound::Excluded(max), Bound::Included(max))), None);
+        assert_eq!(SerialRange::from_bounds((Bound::Excluded(max), Bound::Excluded(max))),
+                   Some(range(0, u32::MAX)));
+        let bounds = range(7, 9);
+        assert_eq!(SerialRange::from_bounds(bounds), Some(bounds));
+    }
+
+    #[test]
+    fn iter() {
+        let range = SerialRange::new(SerialNumber(u16::MAX - 1), SerialNumber(2u16));
+        let mut iter = range.into_iter();
+        assert_eq!(iter.next(), Some(SerialNumber(u16::MAX - 1)));
+        assert_eq!(iter.next_back(), Some(SerialNumber(1)));
+        assert_eq!(iter.next_back(), Some(SerialNumber(0)));
+        assert_eq!(iter.next(), Some(SerialNumber(u16::MAX)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(SerialRange::new(SerialNumber(3u8), SerialNumber(3u8)).rev().count(), 0);
+    }
+
+    #[test]
+    fn walk() {
+        let iter = SerialNumber(250u8).range_to(SerialNumber(5u8));
+        assert_eq!(iter.size_hint(), (11, Some(11)));
+        assert_eq!(iter.map(|serial| serial.0).collect::<Vec<_>>(),
+                   vec![250, 251, 252, 253, 254, 255, 0, 1, 2, 3, 4]);
+        assert_eq!(SerialNumber(7u8).range_to(SerialNumber(7u8)).count(), 0);
+
+        let mut iter = SerialNumber::iter_from(SerialNumber(u32::MAX - 1));
+        assert_eq!(iter.nth(2), Some(SerialNumber(0)));
+        assert_eq!(iter.next(), Some(SerialNumber(1)));
+        assert_eq!(SerialNumber::iter_from(SerialNumber(0u64)).take(1000).count(), 1000);
+    }
+
+    #[test]
+    fn contains() {
+        let range = SerialRange::new(SerialNumber(u16::MAX), SerialNumber(2u16));
+        assert_eq!(range.len(), 3);
+        assert!(range.contains(SerialNumber(u16::MAX)));
+        assert!(range.contains(SerialNumber(1)));
+        assert!(!range.contains(SerialNumber(2)));
+        assert!(!range.contains(SerialNumber(u16::MAX - 1)));
+    }
+}
@@ -0,0 +1,344 @@
+//! Sets of serial numbers stored as disjoint ranges, e.g. for TCP SACK
+//! blocks or QUIC ACK ranges.
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+use std::slice;
+
+use alloc::vec::Vec;
+
+use SerialNumber;
+use range::SerialRange;
+use uint::Uint;
+
+/// The outcome of compacting a `SerialRangeSet` or a `gap::GapTracker`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Compaction {
+    /// The amount of ranges merged into the preceding adjacent range.
+    pub coalesced: usize,
+    /// The amount of oldest ranges dropped to enforce the maximum.
+    pub dropped: usize,
+}
+
+/// A set of serial numbers stored as sorted, disjoint and non-adjacent
+/// `SerialRange`s that may cross the wrap point.
+///
+/// The ranges are ordered relative to an *anchor*, which is the lowest
+/// serial number ever inserted or the point the set has been trimmed to.
+/// All serial numbers in the set must lie within half of the serial number
+/// space from the anchor, e.g. by regularly trimming the set below the
+/// cumulatively acknowledged serial number.
+///
+/// # Examples
+///
+/// ```
+/// use sna::{SerialNumber, SerialRange};
+/// use sna::rangeset::SerialRangeSet;
+///
+/// let range = |start: u8, end: u8| SerialRange::new(SerialNumber(start), SerialNumber(end));
+/// let mut set = SerialRangeSet::new();
+///
+/// set.insert(range(250, 253));
+/// set.insert(range(0, 2));
+/// set.insert(range(253, 255));
+/// assert_eq!(set.ranges().collect::<Vec<_>>(), vec![range(250, 255), range(0, 2)]);
+/// assert_eq!(set.holes().collect::<Vec<_>>(), vec![range(255, 0)]);
+///
+/// set.insert(range(255, 0));
+/// assert_eq!(set.ranges().collect::<Vec<_>>(), vec![range(250, 2)]);
+///
+/// set.trim_below(SerialNumber(1));
+/// assert!(!set.contains(SerialNumber(0)));
+/// assert!(set.contains(SerialNumber(1)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SerialRangeSet<T> {
+    anchor: Option<SerialNumber<T>>,
+    ranges: Vec<SerialRange<T>>,
+}
+
+impl<T: Uint> SerialRangeSet<T> where SerialNumber<T>: PartialOrd {
+    /// Create an empty set.
+    #[inline]
+    pub fn new() -> Self {
+        SerialRangeSet { anchor: None, ranges: Vec::new() }
+    }
+
+    /// Return whether the set contains no serial numbers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Return the amount of disjoint ranges.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Return the lowest serial number of the set.
+    #[inline]
+    pub fn first(&self) -> Option<SerialNumber<T>> {
+        self.ranges.first().map(SerialRange::start)
+    }
+
+    /// Return the serial number following the highest one of the set.
+    #[inline]
+    pub fn end(&self) -> Option<SerialNumber<T>> {
+        self.ranges.last().map(SerialRange::end)
+    }
+
+    /// Return whether `serial` is in the set.
+    pub fn contains(&self, serial: SerialNumber<T>) -> bool {
+        self.ranges.iter().any(|range| range.contains(serial))
+    }
+
+    /// Insert all serial numbers of `range`, merging it with overlapping
+    /// and adjacent ranges.
+    ///
+    /// Returns whether any serial number has been added.
+    pub fn insert(&mut self, range: SerialRange<T>) -> bool {
+        if range.is_empty() {
+            return false;
+        }
+        let anchor = match self.anchor {
+            Some(anchor) if range.start().partial_cmp(&anchor) != Some(Ordering::Less) => anchor,
+            _ => range.start(),
+        };
+        self.anchor = Some(anchor);
+
+        let offset = |serial: SerialNumber<T>| serial.0.wrapping_sub(anchor.0);
+        let (mut start, mut end) = (offset(range.start()), offset(range.start()).wrapping_add(range.len()));
+        let first = self.ranges.iter().position(|other| offset(other.end()) >= start);
+        let first = match first {
+            Some(first) => first,
+            None => {
+                self.ranges.push(range);
+                return true;
+            }
+        };
+        let last = self.ranges[first..].iter()
+            .position(|other| offset(other.start()) > end)
+            .map_or(self.ranges.len(), |position| first + position);
+        if first < last {
+            let (merged_start, merged_end) = (offset(self.ranges[first].start()),
+                                              offset(self.ranges[last - 1].end()));
+            if merged_start <= start && end <= merged_end && last - first == 1 {
+                return false;
+            }
+            start = start.min(merged_start);
+            end = end.max(merged_end);
+        }
+        let merged = SerialRange::new(SerialNumber(anchor.0.wrapping_add(start)),
+                                      SerialNumber(anchor.0.wrapping_add(end)));
+        self.ranges.splice(first..last, Some(merged));
+        true
+    }
+
+    /// Remove all serial numbers preceding `cumulative`, e.g. once they have
+    /// been cumulatively acknowledged, and use `cumulative` as the anchor.
+    pub fn trim_below(&mut self, cumulative: SerialNumber<T>) {
+        if let Some(anchor) = self.anchor {
+            if cumulative.partial_cmp(&anchor) != Some(Ordering::Greater) {
+                return;
+            }
+            let offset = |serial: SerialNumber<T>| serial.0.wrapping_sub(anchor.0);
+            let cut = offset(cumulative);
+            self.ranges.retain(|range| offset(range.end()) > cut);
+            if let Some(range) = self.ranges.first_mut() {
+                if offset(range.start()) < cut {
+                    *range = SerialRange::new(cumulative, range.end());
+                }
+            }
+        }
+        self.anchor = Some(cumulative);
+    }
+
+    /// Remove all serial numbers.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+        self.anchor = None;
+    }
+
+    /// Merge adjacent ranges and drop the lowest ranges until at most
+    /// `max_ranges` remain, e.g. to bound the size of an ACK frame under
+    /// heavy loss.
+    ///
+    /// `insert` coalesces ranges already, so only ranges that became
+    /// adjacent otherwise are merged.
+    pub fn compact(&mut self, max_ranges: usize) -> Compaction {
+        let len = self.ranges.len();
+        self.ranges.dedup_by(|next, previous| {
+            let adjacent = previous.end() == next.start();
+            if adjacent {
+                *previous = SerialRange::new(previous.start(), next.end());
+            }
+            adjacent
+        });
+        let coalesced = len - self.ranges.len();
+        let dropped = self.ranges.len().saturating_sub(max_ranges);
+        self.ranges.drain(..dropped);
+        Compaction { coalesced, dropped }
+    }
+
+    /// Return an iterator over the ranges of the set, lowest first.
+    #[inline]
+    pub fn ranges(&self) -> Ranges<'_, T> {
+        Ranges { ranges: self.ranges.iter() }
+    }
+
+    /// Return an iterator over the missing ranges between the ranges of the
+    /// set, lowest first.
+    #[inline]
+    pub fn holes(&self) -> Holes<'_, T> {
+        Holes { ranges: self.ranges.windows(2) }
+    }
+}
+
+impl<T: Uint> Extend<SerialRange<T>> for SerialRangeSet<T> where SerialNumber<T>: PartialOrd {
+    /// Insert all serial numbers of the ranges as if by `insert`.
+    fn extend<I: IntoIterator<Item = SerialRange<T>>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.insert(range);
+        }
+    }
+}
+
+/// An iterator over the ranges of a `SerialRangeSet`.
+///
+/// Created by `SerialRangeSet::ranges`.
+#[derive(Clone, Debug)]
+pub struct Ranges<'a, T: 'a> {
+    ranges: slice::Iter<'a, SerialRange<T>>,
+}
+
+impl<'a, T: Uint> Iterator for Ranges<'a, T> {
+    type Item = SerialRange<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<SerialRange<T>> {
+        self.ranges.next().cloned()
+    }
+}
+
+impl<'a, T: Uint> DoubleEndedIterator for Ranges<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<SerialRange<T>> {
+        self.ranges.next_back().cloned()
+    }
+}
+
+/// An iterator over the holes of a `SerialRangeSet`.
+///
+/// Created by `SerialRangeSet::holes`.
+#[derive(Clone, Debug)]
+pub struct Holes<'a, T: 'a> {
+    ranges: slice::Windows<'a, SerialRange<T>>,
+}
+
+impl<'a, T: Uint> Iterator for Holes<'a, T> {
+    type Item = SerialRange<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<SerialRange<T>> {
+        self.ranges.next().map(|pair| SerialRange::new(pair[0].end(), pair[1].start()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    fn range(start: u16, end: u16) -> SerialRange<u16> {
+        SerialRange::new(SerialNumber(start), SerialNumber(end))
+    }
+
+    fn ranges(set: &SerialRangeSet<u16>) -> Vec<(u16, u16)> {
+        set.ranges().map(|range| (range.start().0, range.end().0)).collect()
+    }
+
+    #[test]
+    fn merge() {
+        let mut set = SerialRangeSet::new();
+        assert!(set.insert(range(10, 20)));
+        assert!(set.insert(range(30, 40)));
+        assert!(set.insert(range(50, 60)));
+        assert!(!set.insert(range(12, 18)));
+        assert!(!set.insert(range(5, 5)));
+        assert_eq!(set.len(), 3);
+
+        // Adjacent to the first, overlapping the second
+        assert!(set.insert(range(20, 35)));
+        assert_eq!(ranges(&set), vec![(10, 40), (50, 60)]);
+
+        // Spanning everything and extending the anchor downwards
+        assert!(set.insert(range(5, 70)));
+        assert_eq!(ranges(&set), vec![(5, 70)]);
+        assert!(set.holes().next().is_none());
+
+        set.extend(vec![range(80, 90), range(70, 75), range(75, 80)]);
+        assert_eq!(ranges(&set), vec![(5, 90)]);
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut set = SerialRangeSet::new();
+        set.insert(range(65530, 65534));
+        set.insert(range(2, 4));
+        set.insert(range(65520, 65525));
+        assert_eq!(ranges(&set), vec![(65520, 65525), (65530, 65534), (2, 4)]);
+        let holes: Vec<_> = set.holes().collect();
+        assert_eq!(holes, vec![range(65525, 65530), range(65534, 2)]);
+        assert_eq!(set.first(), Some(SerialNumber(65520)));
+        assert_eq!(set.end(), Some(SerialNumber(4)));
+
+        set.insert(range(65534, 2));
+        assert_eq!(ranges(&set), vec![(65520, 65525), (65530, 4)]);
+        assert!(set.contains(SerialNumber(0)));
+        assert!(!set.contains(SerialNumber(4)));
+    }
+
+    #[test]
+    fn compact() {
+        let mut set = SerialRangeSet::new();
+        set.extend(vec![range(65530, 65534), range(0, 10), range(20, 30), range(40, 50)]);
+        assert_eq!(set.compact(4), Compaction::default());
+        assert_eq!(set.compact(2), Compaction { coalesced: 0, dropped: 2 });
+        assert_eq!(ranges(&set), vec![(20, 30), (40, 50)]);
+
+        set.ranges.push(range(50, 55));
+        set.ranges.push(range(55, 60));
+        assert_eq!(set.compact(1), Compaction { coalesced: 2, dropped: 1 });
+        assert_eq!(ranges(&set), vec![(40, 60)]);
+        assert!(!set.contains(SerialNumber(25)));
+    }
+
+    #[test]
+    fn trim() {
+        let mut set = SerialRangeSet::new();
+        set.insert(range(65530, 65534));
+        set.insert(range(0, 10));
+        set.insert(range(20, 30));
+
+        set.trim_below(SerialNumber(5));
+        assert_eq!(ranges(&set), vec![(5, 10), (20, 30)]);
+
+        // Trimming backwards is a no-op
+        set.trim_below(SerialNumber(65535));
+        assert_eq!(ranges(&set), vec![(5, 10), (20, 30)]);
+
+        set.trim_below(SerialNumber(25));
+        assert_eq!(ranges(&set), vec![(25, 30)]);
+        set.trim_below(SerialNumber(30));
+        assert!(set.is_empty());
+
+        // The anchor stays at the trim point
+        set.insert(range(40, 41));
+        assert!(set.insert(range(35, 36)));
+        assert_eq!(ranges(&set), vec![(35, 36), (40, 41)]);
+    }
+}
@@ -0,0 +1,256 @@
+//! Buffers that release out-of-order items in serial number order.
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+
+use alloc::collections::VecDeque;
+
+use SerialNumber;
+use uint::Uint;
+
+/// Holds items that arrived out of order and releases them in serial
+/// number order, starting at the next expected serial number.
+///
+/// Items may be at most `distance - 1` ahead of the next expected serial
+/// number. Inserting an item further ahead gives up on the oldest missing
+/// serial numbers: the next expected serial number is advanced and all
+/// buffered items it passes become ready (in order, with gaps). Items
+/// preceding the next expected serial number are rejected.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::reorder::ReorderBuffer;
+///
+/// let mut buffer = ReorderBuffer::new(SerialNumber(254u8), 4);
+///
+/// buffer.insert(SerialNumber(255), "b").unwrap();
+/// assert_eq!(buffer.pop(), None);
+/// buffer.insert(SerialNumber(254), "a").unwrap();
+/// assert_eq!(buffer.ready().collect::<Vec<_>>(),
+///            vec![(SerialNumber(254), "a"), (SerialNumber(255), "b")]);
+///
+/// // 0 is missing, inserting 4 gives up on it
+/// buffer.insert(SerialNumber(1), "d").unwrap();
+/// buffer.insert(SerialNumber(4), "e").unwrap();
+/// assert_eq!(buffer.pop(), Some((SerialNumber(1), "d")));
+/// assert_eq!(buffer.next_expected(), SerialNumber(2));
+/// assert_eq!(buffer.lost(), 1);
+/// assert_eq!(buffer.insert(SerialNumber(0), "c"), Err("c"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReorderBuffer<T, V> {
+    distance: usize,
+    next: SerialNumber<T>,
+    slots: VecDeque<Option<V>>,
+    ready: VecDeque<(SerialNumber<T>, V)>,
+    lost: u64,
+}
+
+impl<T: Uint, V> ReorderBuffer<T, V> where SerialNumber<T>: PartialOrd {
+    /// Create a buffer expecting `next` first that holds items up to
+    /// `distance - 1` ahead of the next expected serial number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `distance` is zero or exceeds half of the serial number
+    /// space.
+    pub fn new(next: SerialNumber<T>, distance: usize) -> Self {
+        assert!(distance > 0, "distance must be greater than zero");
+        assert!(distance as u128 <= T::HALF.to_u128(),
+                "distance exceeds half of the serial number space");
+        ReorderBuffer { distance, next, slots: VecDeque::new(), ready: VecDeque::new(), lost: 0 }
+    }
+
+    /// Return the next expected serial number.
+    #[inline]
+    pub fn next_expected(&self) -> SerialNumber<T> {
+        self.next
+    }
+
+    /// Return the amount of items held back or ready.
+    pub fn len(&self) -> usize {
+        self.ready.len() + self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// Return whether no items are held back or ready.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the number of serial numbers given up on so far.
+    #[inline]
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    /// Buffer `value` with serial number `serial`.
+    ///
+    /// Returns the value back if `serial` precedes the next expected
+    /// serial number, has already been buffered or can not be ordered
+    /// relative to the next expected serial number.
+    pub fn insert(&mut self, serial: SerialNumber<T>, value: V) -> Result<(), V> {
+        match serial.partial_cmp(&self.next) {
+            Some(Ordering::Equal) | Some(Ordering::Greater) => {}
+            _ => return Err(value),
+        }
+        let offset = serial.0.wrapping_sub(self.next.0).to_u128();
+        if offset >= self.distance as u128 {
+            self.skip(offset - self.distance as u128 + 1);
+        }
+        let index = serial.0.wrapping_sub(self.next.0).to_u128() as usize;
+        if self.slots.len() <= index {
+            self.slots.resize_with(index + 1, || None);
+        }
+        match self.slots[index] {
+            Some(_) => Err(value),
+            None => {
+                self.slots[index] = Some(value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove and return the next item in serial number order, if it is
+    /// ready.
+    pub fn pop(&mut self) -> Option<(SerialNumber<T>, V)> {
+        if let Some(item) = self.ready.pop_front() {
+            return Some(item);
+        }
+        match self.slots.front() {
+            Some(&Some(_)) => {}
+            _ => return None,
+        }
+        let value = self.slots.pop_front().and_then(|slot| slot)?;
+        let serial = self.next;
+        self.next = SerialNumber(serial.0.wrapping_add(T::ONE));
+        Some((serial, value))
+    }
+
+    /// Return an iterator that removes and returns the ready items.
+    #[inline]
+    pub fn ready(&mut self) -> Ready<'_, T, V> {
+        Ready { buffer: self }
+    }
+
+    /// Give up on the missing serial numbers up to the oldest buffered
+    /// item, so it becomes ready.
+    ///
+    /// Returns the number of serial numbers given up on.
+    pub fn flush_gap(&mut self) -> u64 {
+        let missing = self.slots.iter().take_while(|slot| slot.is_none()).count();
+        if missing == self.slots.len() {
+            return 0;
+        }
+        self.skip(missing as u128);
+        missing as u64
+    }
+
+    /// Advance the next expected serial number by `count`, moving buffered
+    /// items it passes to the ready queue.
+    fn skip(&mut self, count: u128) {
+        let drained = (count.min(self.slots.len() as u128)) as usize;
+        let mut serial = self.next;
+        for slot in self.slots.drain(..drained) {
+            match slot {
+                Some(value) => self.ready.push_back((serial, value)),
+                None => self.lost += 1,
+            }
+            serial = SerialNumber(serial.0.wrapping_add(T::ONE));
+        }
+        self.lost += (count - drained as u128) as u64;
+        self.next = SerialNumber(self.next.0.wrapping_add(T::from_u128(count)));
+    }
+}
+
+/// An iterator over the ready items of a `ReorderBuffer`.
+///
+/// Created by `ReorderBuffer::ready`.
+#[derive(Debug)]
+pub struct Ready<'a, T: 'a, V: 'a> {
+    buffer: &'a mut ReorderBuffer<T, V>,
+}
+
+impl<'a, T: Uint, V> Iterator for Ready<'a, T, V> where SerialNumber<T>: PartialOrd {
+    type Item = (SerialNumber<T>, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(SerialNumber<T>, V)> {
+        self.buffer.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn ready(buffer: &mut ReorderBuffer<u16, u16>) -> Vec<u16> {
+        buffer.ready().map(|(serial, value)| {
+            assert_eq!(serial.0, value);
+            value
+        }).collect()
+    }
+
+    #[test]
+    fn in_order() {
+        let mut buffer = ReorderBuffer::new(SerialNumber(65534), 8);
+        for serial in SerialNumber(65534u16).range_to(SerialNumber(3)) {
+            buffer.insert(serial, serial.0).unwrap();
+            assert_eq!(ready(&mut buffer), vec![serial.0]);
+        }
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.lost(), 0);
+    }
+
+    #[test]
+    fn reordered() {
+        let mut buffer = ReorderBuffer::new(SerialNumber(10), 8);
+        for &serial in &[12u16, 11, 14] {
+            buffer.insert(SerialNumber(serial), serial).unwrap();
+        }
+        assert_eq!(buffer.len(), 3);
+        assert!(ready(&mut buffer).is_empty());
+        assert_eq!(buffer.insert(SerialNumber(12), 12), Err(12));
+
+        buffer.insert(SerialNumber(10), 10).unwrap();
+        assert_eq!(ready(&mut buffer), vec![10, 11, 12]);
+        assert_eq!(buffer.insert(SerialNumber(11), 11), Err(11));
+
+        assert_eq!(buffer.flush_gap(), 1);
+        assert_eq!(ready(&mut buffer), vec![14]);
+        assert_eq!(buffer.flush_gap(), 0);
+        assert_eq!(buffer.next_expected(), SerialNumber(15));
+    }
+
+    #[test]
+    fn too_far_ahead() {
+        let mut buffer = ReorderBuffer::new(SerialNumber(0), 4);
+        buffer.insert(SerialNumber(1), 1).unwrap();
+        buffer.insert(SerialNumber(3), 3).unwrap();
+
+        // Gives up on 0 and 2, but 1 and 3 are still delivered
+        buffer.insert(SerialNumber(6), 6).unwrap();
+        assert_eq!(buffer.next_expected(), SerialNumber(3));
+        assert_eq!(ready(&mut buffer), vec![1, 3]);
+        assert_eq!(buffer.lost(), 2);
+
+        // Jumps past everything
+        buffer.insert(SerialNumber(100), 100).unwrap();
+        assert_eq!(buffer.lost(), 2 + 2 + 90);
+        assert_eq!(ready(&mut buffer), vec![6]);
+        assert_eq!(buffer.next_expected(), SerialNumber(97));
+        assert_eq!(buffer.len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn distance_too_large() {
+        ReorderBuffer::<u8, ()>::new(SerialNumber(0), 129);
+    }
+}
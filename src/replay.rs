@@ -0,0 +1,674 @@
+//! Anti-replay windows as used by IPsec, DTLS and SRTP.
+//!
+//! The receiver remembers the highest serial number seen so far and a
+//! bitmap of the serial numbers seen within a window below it, see
+//! [chapter 3.4.3 of RFC 4303](https://tools.ietf.org/html/rfc4303#section-3.4.3).
+//!
+//! `DuplicateDetector` uses the same window to deduplicate long-running
+//! streams, e.g. of at-least-once messaging, in bounded memory.
+//! `TimedReplayWindow` additionally expires its state by age for bursty
+//! traffic and `PersistentReplayWindow` keeps refusing replays across
+//! restarts. See `esn` for the extended sequence numbers of IPsec.
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::time::Duration;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use SerialNumber;
+use bitmap::Window;
+use uint::Uint;
+
+pub use bitmap::Replay;
+
+/// A bitmap-backed sliding window that rejects replayed serial numbers.
+///
+/// Serial numbers that are exactly half of the serial number space away
+/// from the highest one seen can not be ordered and are considered too old.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::replay::{Replay, ReplayWindow};
+///
+/// let mut window = ReplayWindow::new(64);
+///
+/// assert_eq!(window.check_and_update(SerialNumber(u32::MAX)), Replay::Accepted);
+/// assert_eq!(window.check_and_update(SerialNumber(100u32)), Replay::Accepted);
+/// assert_eq!(window.check_and_update(SerialNumber(100u32)), Replay::Duplicate);
+///
+/// // Reordered, but still within the window
+/// assert_eq!(window.check(SerialNumber(50u32)), Replay::Accepted);
+/// assert_eq!(window.check(SerialNumber(u32::MAX)), Replay::TooOld);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReplayWindow<T = u32> {
+    window: Window<T>,
+    bitmap: Vec<u64>,
+}
+
+impl<T: Uint> ReplayWindow<T> where SerialNumber<T>: PartialOrd {
+    /// Create a window that tracks the `size` most recent serial numbers
+    /// (including the highest one seen), e.g. 64, 128 or 1024.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero or exceeds half of the serial number space.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "window size must be greater than zero");
+        assert!(size as u128 <= T::HALF.to_u128(),
+                "window size exceeds half of the serial number space");
+        ReplayWindow { window: Window { size, highest: None }, bitmap: vec![0; size.div_ceil(64)] }
+    }
+
+    /// Return the size of the window.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.window.size
+    }
+
+    /// Return the highest serial number seen so far.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.window.highest
+    }
+
+    /// Check whether `serial` would be accepted without updating the
+    /// window.
+    #[inline]
+    pub fn check(&self, serial: SerialNumber<T>) -> Replay {
+        self.window.check(&self.bitmap, serial)
+    }
+
+    /// Check the serial numbers of a burst, e.g. of a GRO batch, without
+    /// updating the window.
+    ///
+    /// Returns a bitmask whose bit `i` is set if `serials[i]` would be
+    /// accepted, i.e. it is accepted by the window and does not repeat an
+    /// earlier serial number of the burst. Once the accepted packets have
+    /// been authenticated, mark them as seen with `extend`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `serials` holds more than 64 serial numbers.
+    #[inline]
+    pub fn check_batch(&self, serials: &[SerialNumber<T>]) -> u64 {
+        self.window.check_batch(&self.bitmap, serials)
+    }
+
+    /// Check whether `serial` is accepted and, if so, mark it as seen.
+    ///
+    /// Only call this once the packet has been authenticated, as otherwise
+    /// forged packets could advance the window.
+    #[inline]
+    pub fn check_and_update(&mut self, serial: SerialNumber<T>) -> Replay {
+        self.window.check_and_update(&mut self.bitmap, serial)
+    }
+
+    /// Forget all serial numbers seen so far.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.window.reset(&mut self.bitmap);
+    }
+
+    /// Consider `highest` and every serial number of the window below it
+    /// seen.
+    fn fill(&mut self, highest: SerialNumber<T>) {
+        self.window.fill(&mut self.bitmap, highest);
+    }
+}
+
+impl<T: Uint> Extend<SerialNumber<T>> for ReplayWindow<T> where SerialNumber<T>: PartialOrd {
+    /// Mark the serial numbers as seen as if by `check_and_update`, e.g.
+    /// the authenticated packets of a burst checked by `check_batch`.
+    fn extend<I: IntoIterator<Item = SerialNumber<T>>>(&mut self, serials: I) {
+        for serial in serials {
+            self.check_and_update(serial);
+        }
+    }
+}
+
+/// How a `DuplicateDetector` or a `TimedReplayWindow` treats serial numbers
+/// that are older than its window, i.e. for which it can not be determined
+/// whether they have already been seen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OldPolicy {
+    /// Consider them duplicates, so nothing is processed twice but very late
+    /// serial numbers are dropped (at-most-once).
+    AssumeDuplicate,
+    /// Consider them new, so nothing is dropped but very late duplicates are
+    /// processed again (at-least-once).
+    AssumeNew,
+}
+
+/// Answers whether a serial number has been seen before in bounded memory.
+///
+/// This combines the highest serial number seen with a bitmap of the
+/// `size` most recent serial numbers (see `ReplayWindow`) and treats older
+/// serial numbers according to an `OldPolicy`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::replay::{DuplicateDetector, OldPolicy};
+///
+/// let mut detector = DuplicateDetector::new(128, OldPolicy::AssumeDuplicate);
+///
+/// assert!(detector.insert(SerialNumber(7u32)));
+/// assert!(!detector.insert(SerialNumber(7u32)));
+/// assert!(detector.insert(SerialNumber(1000u32)));
+///
+/// // Older than the window
+/// assert!(detector.is_duplicate(SerialNumber(8u32)));
+/// assert!(!detector.insert(SerialNumber(8u32)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct DuplicateDetector<T = u32> {
+    window: ReplayWindow<T>,
+    policy: OldPolicy,
+}
+
+impl<T: Uint> DuplicateDetector<T> where SerialNumber<T>: PartialOrd {
+    /// Create a detector that remembers the `size` most recent serial
+    /// numbers and treats older ones according to `policy`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero or exceeds half of the serial number space.
+    #[inline]
+    pub fn new(size: usize, policy: OldPolicy) -> Self {
+        DuplicateDetector { window: ReplayWindow::new(size), policy }
+    }
+
+    /// Return the policy for serial numbers older than the window.
+    #[inline]
+    pub fn policy(&self) -> OldPolicy {
+        self.policy
+    }
+
+    /// Return the highest serial number seen so far.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.window.highest()
+    }
+
+    /// Return whether `serial` has been seen before (or is considered to
+    /// have been seen) without recording it.
+    pub fn is_duplicate(&self, serial: SerialNumber<T>) -> bool {
+        match self.window.check(serial) {
+            Replay::Accepted => false,
+            Replay::Duplicate => true,
+            Replay::TooOld => self.policy == OldPolicy::AssumeDuplicate,
+        }
+    }
+
+    /// Record `serial` as seen.
+    ///
+    /// Returns whether `serial` is new, i.e. `false` if it is a duplicate.
+    pub fn insert(&mut self, serial: SerialNumber<T>) -> bool {
+        match self.window.check_and_update(serial) {
+            Replay::Accepted => true,
+            Replay::Duplicate => false,
+            Replay::TooOld => self.policy == OldPolicy::AssumeNew,
+        }
+    }
+
+    /// Forget all serial numbers seen so far.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.window.reset();
+    }
+}
+
+/// A `ReplayWindow` whose state also expires by age.
+///
+/// Once no serial number has been accepted for `max_age`, the window is
+/// considered stale and the next serial number is accepted as if the window
+/// had been reset, e.g. after the peer has been silent for long enough that
+/// a replay of its old packets is harmless. As long as the window is fresh,
+/// serial numbers older than the window are treated according to an
+/// `OldPolicy`, e.g. to still accept stragglers of a burst that overran the
+/// window.
+///
+/// Time is passed in by the caller as the duration since an arbitrary but
+/// fixed point in time, so the window can be driven by a monotonic clock or
+/// by a tick count.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use sna::SerialNumber;
+/// use sna::replay::{OldPolicy, Replay, TimedReplayWindow};
+///
+/// let mut window = TimedReplayWindow::new(64, Duration::from_secs(5), OldPolicy::AssumeNew);
+/// let now = Duration::from_secs(100);
+///
+/// assert_eq!(window.check_and_update(SerialNumber(10u32), now), Replay::Accepted);
+/// assert_eq!(window.check_and_update(SerialNumber(1000u32), now), Replay::Accepted);
+///
+/// // Older than the window, but the window is fresh
+/// assert_eq!(window.check(SerialNumber(500u32), now), Replay::Accepted);
+/// assert_eq!(window.check(SerialNumber(1000u32), now), Replay::Duplicate);
+///
+/// // The window is stale
+/// let later = now + Duration::from_secs(5);
+/// assert!(window.is_expired(later));
+/// assert_eq!(window.check(SerialNumber(1000u32), later), Replay::Accepted);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TimedReplayWindow<T = u32> {
+    window: ReplayWindow<T>,
+    max_age: Duration,
+    policy: OldPolicy,
+    last: Option<Duration>,
+}
+
+impl<T: Uint> TimedReplayWindow<T> where SerialNumber<T>: PartialOrd {
+    /// Create a window that tracks the `size` most recent serial numbers
+    /// for at most `max_age` after the last accepted one and treats older
+    /// serial numbers according to `policy`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero or exceeds half of the serial number space.
+    #[inline]
+    pub fn new(size: usize, max_age: Duration, policy: OldPolicy) -> Self {
+        TimedReplayWindow { window: ReplayWindow::new(size), max_age, policy, last: None }
+    }
+
+    /// Return the size of the window.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.window.size()
+    }
+
+    /// Return the age after which the window expires.
+    #[inline]
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Return the policy for serial numbers older than the window.
+    #[inline]
+    pub fn policy(&self) -> OldPolicy {
+        self.policy
+    }
+
+    /// Return the highest serial number seen so far, even if the window
+    /// has expired.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.window.highest()
+    }
+
+    /// Return when a serial number has last been accepted.
+    #[inline]
+    pub fn last_accepted(&self) -> Option<Duration> {
+        self.last
+    }
+
+    /// Return whether no serial number has been accepted for `max_age` at
+    /// `now`.
+    #[inline]
+    pub fn is_expired(&self, now: Duration) -> bool {
+        self.last.is_some_and(|last| now.saturating_sub(last) >= self.max_age)
+    }
+
+    /// Check whether `serial` would be accepted at `now` without updating
+    /// the window.
+    pub fn check(&self, serial: SerialNumber<T>, now: Duration) -> Replay {
+        if self.is_expired(now) {
+            return Replay::Accepted;
+        }
+        self.apply_policy(self.window.check(serial))
+    }
+
+    /// Check whether `serial` is accepted at `now` and, if so, mark it as
+    /// seen.
+    ///
+    /// A serial number older than the window that is accepted due to
+    /// `OldPolicy::AssumeNew` can not be marked as seen. Only call this
+    /// once the packet has been authenticated.
+    pub fn check_and_update(&mut self, serial: SerialNumber<T>, now: Duration) -> Replay {
+        if self.is_expired(now) {
+            self.window.reset();
+        }
+        let replay = self.window.check_and_update(serial);
+        let replay = self.apply_policy(replay);
+        if replay == Replay::Accepted {
+            self.last = Some(now);
+        }
+        replay
+    }
+
+    /// Forget all serial numbers seen so far.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.window.reset();
+        self.last = None;
+    }
+
+    fn apply_policy(&self, replay: Replay) -> Replay {
+        match replay {
+            Replay::TooOld if self.policy == OldPolicy::AssumeNew => Replay::Accepted,
+            replay => replay,
+        }
+    }
+}
+
+/// Stores the highest serial number accepted by a `PersistentReplayWindow`
+/// so it survives a restart, e.g. in a file or in flash memory.
+pub trait SerialStore<T> {
+    /// The error of loading or storing a serial number.
+    type Error;
+
+    /// Load the serial number stored last, if any.
+    fn load(&mut self) -> Result<Option<SerialNumber<T>>, Self::Error>;
+
+    /// Store `serial`, which must survive a crash once this returns.
+    fn store(&mut self, serial: SerialNumber<T>) -> Result<(), Self::Error>;
+}
+
+/// An in-memory store, e.g. for tests.
+impl<T: Copy> SerialStore<T> for Option<SerialNumber<T>> {
+    type Error = Infallible;
+
+    #[inline]
+    fn load(&mut self) -> Result<Option<SerialNumber<T>>, Infallible> {
+        Ok(*self)
+    }
+
+    #[inline]
+    fn store(&mut self, serial: SerialNumber<T>) -> Result<(), Infallible> {
+        *self = Some(serial);
+        Ok(())
+    }
+}
+
+/// A `ReplayWindow` that refuses replays of serial numbers accepted before
+/// a restart, like WireGuard.
+///
+/// Storing every accepted serial number would be too slow, so the highest
+/// one is only stored once it exceeds the one stored last by more than
+/// `margin`, before it is accepted. Thus, no serial number beyond the
+/// stored one plus `margin` has ever been accepted. After a restart, all
+/// serial numbers up to it are refused.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::replay::{PersistentReplayWindow, Replay};
+///
+/// let mut window = PersistentReplayWindow::open(64, 1000u32, None).unwrap();
+/// assert_eq!(window.check_and_update(SerialNumber(10)), Ok(Replay::Accepted));
+/// assert_eq!(window.check_and_update(SerialNumber(500)), Ok(Replay::Accepted));
+///
+/// // Restart with the stored serial number
+/// let store = window.into_store();
+/// assert_eq!(store, Some(SerialNumber(10)));
+/// let mut window = PersistentReplayWindow::open(64, 1000u32, store).unwrap();
+/// assert_eq!(window.check(SerialNumber(500)), Replay::TooOld);
+/// assert_eq!(window.check(SerialNumber(1010)), Replay::Duplicate);
+/// assert_eq!(window.check_and_update(SerialNumber(1011)), Ok(Replay::Accepted));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PersistentReplayWindow<T, S> {
+    window: ReplayWindow<T>,
+    margin: T,
+    stored: Option<SerialNumber<T>>,
+    store: S,
+}
+
+impl<T: Uint, S: SerialStore<T>> PersistentReplayWindow<T, S> where SerialNumber<T>: PartialOrd {
+    /// Create a window that tracks the `size` most recent serial numbers
+    /// and stores the highest one in `store` whenever it exceeds the one
+    /// stored last by more than `margin`.
+    ///
+    /// If `store` holds a serial number, e.g. from before a restart, all
+    /// serial numbers up to it plus `margin` are refused.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, `size` exceeds half of the serial number
+    /// space or `margin` is not less than half of the serial number space.
+    pub fn open(size: usize, margin: T, mut store: S) -> Result<Self, S::Error> {
+        assert!(margin < T::HALF, "margin must be less than half of the serial number space");
+        let mut window = ReplayWindow::new(size);
+        let stored = store.load()?;
+        if let Some(stored) = stored {
+            window.fill(SerialNumber(stored.0.wrapping_add(margin)));
+        }
+        Ok(PersistentReplayWindow { window, margin, stored, store })
+    }
+
+    /// Return the size of the window.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.window.size()
+    }
+
+    /// Return the highest serial number seen so far, which is the stored
+    /// one plus `margin` right after opening the window.
+    #[inline]
+    pub fn highest(&self) -> Option<SerialNumber<T>> {
+        self.window.highest()
+    }
+
+    /// Return the serial number stored last.
+    #[inline]
+    pub fn stored(&self) -> Option<SerialNumber<T>> {
+        self.stored
+    }
+
+    /// Check whether `serial` would be accepted without updating the
+    /// window.
+    #[inline]
+    pub fn check(&self, serial: SerialNumber<T>) -> Replay {
+        self.window.check(serial)
+    }
+
+    /// Check whether `serial` is accepted and, if so, mark it as seen.
+    ///
+    /// If `serial` exceeds the serial number stored last by more than
+    /// `margin`, it is stored first. If storing fails, the error is returned
+    /// and `serial` is not marked as seen. Only call this once the packet
+    /// has been authenticated.
+    pub fn check_and_update(&mut self, serial: SerialNumber<T>) -> Result<Replay, S::Error> {
+        let replay = self.window.check(serial);
+        if replay != Replay::Accepted {
+            return Ok(replay);
+        }
+        let covered = self.stored.map(|stored| SerialNumber(stored.0.wrapping_add(self.margin)));
+        match covered.and_then(|covered| serial.partial_cmp(&covered)) {
+            Some(Ordering::Less) | Some(Ordering::Equal) => {}
+            _ => {
+                self.store.store(serial)?;
+                self.stored = Some(serial);
+            }
+        }
+        Ok(self.window.check_and_update(serial))
+    }
+
+    /// Return the store.
+    #[inline]
+    pub fn into_store(self) -> S {
+        self.store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliding() {
+        let mut window = ReplayWindow::new(128);
+        assert_eq!(window.check_and_update(SerialNumber(10u16)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(75u16)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(138u16)), Replay::Accepted);
+
+        // 10 is now outside of the window, 75 and 138 still inside
+        assert_eq!(window.check(SerialNumber(10)), Replay::TooOld);
+        assert_eq!(window.check(SerialNumber(75)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(138)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(74)), Replay::Accepted);
+        assert_eq!(window.check(SerialNumber(9)), Replay::TooOld);
+        assert_eq!(window.check(SerialNumber(11)), Replay::Accepted);
+
+        assert_eq!(window.check_and_update(SerialNumber(202)), Replay::Accepted);
+        assert_eq!(window.check(SerialNumber(75)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(74)), Replay::TooOld);
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut window = ReplayWindow::new(10);
+        assert_eq!(window.check_and_update(SerialNumber(250u8)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(3u8)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(250u8)), Replay::Duplicate);
+        assert_eq!(window.check_and_update(SerialNumber(255u8)), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(255u8)), Replay::Duplicate);
+        assert_eq!(window.check_and_update(SerialNumber(249u8)), Replay::TooOld);
+
+        // Incomparable to the highest serial number seen
+        assert_eq!(window.check(SerialNumber(131u8)), Replay::TooOld);
+    }
+
+    #[test]
+    fn jump_clears_window() {
+        let mut window = ReplayWindow::new(64);
+        for serial in 0..64u32 {
+            assert_eq!(window.check_and_update(SerialNumber(serial)), Replay::Accepted);
+        }
+        assert_eq!(window.check_and_update(SerialNumber(1000)), Replay::Accepted);
+        assert_eq!(window.check(SerialNumber(999)), Replay::Accepted);
+        assert_eq!(window.check(SerialNumber(1000)), Replay::Duplicate);
+
+        window.reset();
+        assert_eq!(window.highest(), None);
+        assert_eq!(window.check(SerialNumber(1000)), Replay::Accepted);
+    }
+
+    #[test]
+    fn batch() {
+        let mut window = ReplayWindow::new(16);
+        window.extend(vec![SerialNumber(65530u16), SerialNumber(65535)]);
+        let burst = [65535, 65531, 2, 65531, 65514, 32767].map(SerialNumber);
+        assert_eq!(window.check_batch(&burst), 0b000110);
+        assert_eq!(window.highest(), Some(SerialNumber(65535)));
+
+        // 32767 is ahead of the new highest serial number
+        window.extend(vec![SerialNumber(65531), SerialNumber(2)]);
+        assert_eq!(window.check_batch(&burst), 0b100000);
+        assert_eq!(window.check(SerialNumber(65530)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(65532)), Replay::Accepted);
+    }
+
+    struct Flaky {
+        stored: Option<SerialNumber<u16>>,
+        fail: bool,
+    }
+
+    impl SerialStore<u16> for Flaky {
+        type Error = ();
+
+        fn load(&mut self) -> Result<Option<SerialNumber<u16>>, ()> {
+            Ok(self.stored)
+        }
+
+        fn store(&mut self, serial: SerialNumber<u16>) -> Result<(), ()> {
+            if self.fail {
+                return Err(());
+            }
+            self.stored = Some(serial);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn persistent() {
+        let store = Flaky { stored: Some(SerialNumber(65530)), fail: false };
+        let mut window = PersistentReplayWindow::open(16, 10, store).unwrap();
+        assert_eq!(window.highest(), Some(SerialNumber(4)));
+        assert_eq!(window.check(SerialNumber(65524)), Replay::TooOld);
+        assert_eq!(window.check(SerialNumber(65530)), Replay::Duplicate);
+        assert_eq!(window.check(SerialNumber(4)), Replay::Duplicate);
+
+        // Stored before being accepted, then covered by the margin
+        assert_eq!(window.check_and_update(SerialNumber(3)), Ok(Replay::Duplicate));
+        assert_eq!(window.check_and_update(SerialNumber(5)), Ok(Replay::Accepted));
+        assert_eq!(window.stored(), Some(SerialNumber(5)));
+        assert_eq!(window.check_and_update(SerialNumber(15)), Ok(Replay::Accepted));
+        assert_eq!(window.stored(), Some(SerialNumber(5)));
+
+        // Not accepted unless stored
+        window.store.fail = true;
+        assert_eq!(window.check_and_update(SerialNumber(16)), Err(()));
+        assert_eq!(window.check(SerialNumber(16)), Replay::Accepted);
+        window.store.fail = false;
+        assert_eq!(window.check_and_update(SerialNumber(16)), Ok(Replay::Accepted));
+
+        let mut window = PersistentReplayWindow::open(16, 10, window.into_store()).unwrap();
+        assert_eq!(window.check(SerialNumber(26)), Replay::Duplicate);
+        assert_eq!(window.check_and_update(SerialNumber(27)), Ok(Replay::Accepted));
+    }
+
+    #[test]
+    fn old_policy() {
+        let mut detector = DuplicateDetector::new(16, OldPolicy::AssumeNew);
+        assert!(detector.insert(SerialNumber(65530u16)));
+        assert!(detector.insert(SerialNumber(100u16)));
+        assert!(!detector.is_duplicate(SerialNumber(65530u16)));
+        assert!(detector.insert(SerialNumber(65530u16)));
+        assert!(!detector.insert(SerialNumber(100u16)));
+        assert_eq!(detector.highest(), Some(SerialNumber(100)));
+
+        detector.reset();
+        assert_eq!(detector.highest(), None);
+        assert!(detector.insert(SerialNumber(100u16)));
+    }
+
+    #[test]
+    fn timed() {
+        let max_age = Duration::from_millis(500);
+        let mut window = TimedReplayWindow::new(8, max_age, OldPolicy::AssumeDuplicate);
+        let now = Duration::from_secs(1);
+        assert_eq!(window.check_and_update(SerialNumber(250u8), now), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(10u8), now), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(250u8), now), Replay::TooOld);
+        assert_eq!(window.check_and_update(SerialNumber(10u8), now), Replay::Duplicate);
+
+        // Rejected serial numbers do not keep the window fresh
+        let later = now + max_age / 2;
+        assert_eq!(window.check_and_update(SerialNumber(10u8), later), Replay::Duplicate);
+        assert_eq!(window.last_accepted(), Some(now));
+        assert!(!window.is_expired(later));
+        assert!(window.is_expired(now + max_age));
+
+        // Expired, so 250 starts a new window
+        let later = now + max_age * 2;
+        assert_eq!(window.check_and_update(SerialNumber(250u8), later), Replay::Accepted);
+        assert_eq!(window.highest(), Some(SerialNumber(250)));
+        assert_eq!(window.check_and_update(SerialNumber(10u8), later), Replay::Accepted);
+        assert_eq!(window.check_and_update(SerialNumber(250u8), later), Replay::TooOld);
+
+        window.reset();
+        assert_eq!(window.last_accepted(), None);
+        assert!(!window.is_expired(later * 10));
+    }
+
+    #[test]
+    #[should_panic]
+    fn window_too_large() {
+        ReplayWindow::<u8>::new(129);
+    }
+}
@@ -0,0 +1,248 @@
+//! Reception statistics of RTP streams as reported in RTCP receiver reports,
+//! see [chapter 6.4.1](https://tools.ietf.org/html/rfc3550#section-6.4.1)
+//! and [appendix A.3 of RFC 3550](https://tools.ietf.org/html/rfc3550#appendix-A.3).
+//!
+//! Unlike RFC 3550, duplicates are detected (as long as they are at most 63
+//! sequence numbers behind the highest one) and not counted as received,
+//! so they do not hide losses.
+
+use std::cmp;
+use std::convert::TryFrom;
+
+use SerialNumber;
+use extended::Extender;
+use uint::Uint;
+
+/// The number of sequence numbers below the highest one for which
+/// duplicates are detected (including the highest one).
+const RECENT: u64 = 64;
+
+/// Accumulates the reception statistics of a stream of serial numbers of
+/// type `T`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::rtcp::StreamStats;
+///
+/// let mut stats = StreamStats::new();
+/// for &seq in &[65534u16, 65535, 2, 1, 1, 4] {
+///     stats.receive(SerialNumber(seq));
+/// }
+///
+/// assert_eq!(stats.extended_highest(), Some(65536 + 4));
+/// assert_eq!(stats.expected(), 7);
+/// assert_eq!(stats.received(), 5);
+/// assert_eq!(stats.cumulative_lost(), 2);
+/// assert_eq!(stats.reordered(), 1);
+/// assert_eq!(stats.duplicates(), 1);
+///
+/// // 2 of 7 packets lost, in units of 1/256
+/// assert_eq!(stats.fraction_lost(), 73);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StreamStats<T> {
+    extender: Extender<T>,
+    base: u64,
+    received: u64,
+    reordered: u64,
+    duplicates: u64,
+    recent: u64,
+    expected_prior: u64,
+    received_prior: u64,
+}
+
+impl<T: Uint> StreamStats<T> where SerialNumber<T>: PartialOrd {
+    /// Create statistics that start with the first serial number received.
+    #[inline]
+    pub fn new() -> Self {
+        StreamStats {
+            extender: Extender::new(),
+            base: 0,
+            received: 0,
+            reordered: 0,
+            duplicates: 0,
+            recent: 0,
+            expected_prior: 0,
+            received_prior: 0,
+        }
+    }
+
+    /// Account for the reception of `serial`.
+    ///
+    /// Returns `false` if `serial` is a duplicate or precedes the first
+    /// serial number by more than it can be placed before, in which case it
+    /// is not counted as received.
+    pub fn receive(&mut self, serial: SerialNumber<T>) -> bool {
+        let highest = self.extender.highest();
+        let extended = match self.extender.extend(serial) {
+            Some(extended) => extended,
+            None => return false,
+        };
+        match highest {
+            None => {
+                self.base = extended;
+                self.recent = 1;
+            }
+            Some(highest) if extended > highest => {
+                let shifted = u32::try_from(extended - highest).ok()
+                    .and_then(|ahead| self.recent.checked_shl(ahead));
+                self.recent = shifted.unwrap_or(0) | 1;
+            }
+            Some(highest) => {
+                let behind = highest - extended;
+                if behind < RECENT {
+                    if self.recent & 1 << behind != 0 {
+                        self.duplicates += 1;
+                        return false;
+                    }
+                    self.recent |= 1 << behind;
+                }
+                if behind > 0 {
+                    self.reordered += 1;
+                }
+                self.base = cmp::min(self.base, extended);
+            }
+        }
+        self.received += 1;
+        true
+    }
+
+    /// Return the extended highest serial number received.
+    #[inline]
+    pub fn extended_highest(&self) -> Option<u64> {
+        self.extender.highest()
+    }
+
+    /// Return the number of serial numbers expected from the first to the
+    /// highest one received.
+    #[inline]
+    pub fn expected(&self) -> u64 {
+        self.extender.highest().map_or(0, |highest| highest - self.base + 1)
+    }
+
+    /// Return the number of serial numbers received, excluding duplicates.
+    #[inline]
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Return the number of serial numbers received after a higher one.
+    #[inline]
+    pub fn reordered(&self) -> u64 {
+        self.reordered
+    }
+
+    /// Return the number of duplicates detected.
+    #[inline]
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// Return the cumulative number of packets lost.
+    ///
+    /// Note that the field of a receiver report is a signed 24-bit integer,
+    /// so the value has to be clamped to `-0x80_0000..=0x7f_ffff`.
+    #[inline]
+    pub fn cumulative_lost(&self) -> i64 {
+        self.expected() as i64 - self.received as i64
+    }
+
+    /// Return the fraction of packets lost since the previous call in units
+    /// of 1/256, as reported in a receiver report, and start a new
+    /// interval.
+    ///
+    /// The fraction is zero if more packets have been received than expected
+    /// within the interval, e.g. due to reordering.
+    pub fn fraction_lost(&mut self) -> u8 {
+        let expected = self.expected();
+        let expected_interval = expected - self.expected_prior;
+        let received_interval = self.received - self.received_prior;
+        self.expected_prior = expected;
+        self.received_prior = self.received;
+        match expected_interval.checked_sub(received_interval) {
+            Some(lost) if expected_interval > 0 => ((lost << 8) / expected_interval) as u8,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals() {
+        let mut stats = StreamStats::new();
+        for seq in 0..100u16 {
+            if seq % 4 != 0 {
+                stats.receive(SerialNumber(seq));
+            }
+        }
+        assert_eq!(stats.expected(), 99);
+        assert_eq!(stats.cumulative_lost(), 24);
+        assert_eq!(stats.fraction_lost(), (24 * 256 / 99) as u8);
+
+        // No losses in the next interval
+        for seq in 100..200u16 {
+            stats.receive(SerialNumber(seq));
+        }
+        assert_eq!(stats.fraction_lost(), 0);
+        assert_eq!(stats.fraction_lost(), 0);
+        assert_eq!(stats.cumulative_lost(), 24);
+    }
+
+    #[test]
+    fn late_loss_recovery() {
+        let mut stats = StreamStats::new();
+        stats.receive(SerialNumber(10u8));
+        stats.receive(SerialNumber(12u8));
+        assert_eq!(stats.fraction_lost(), 85);
+
+        // The missing packet arrives in the next interval
+        stats.receive(SerialNumber(11u8));
+        assert_eq!(stats.fraction_lost(), 0);
+        assert_eq!(stats.cumulative_lost(), 0);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut stats = StreamStats::new();
+        assert!(stats.receive(SerialNumber(u32::MAX)));
+        assert!(stats.receive(SerialNumber(20u32)));
+        assert!(!stats.receive(SerialNumber(u32::MAX)));
+        assert!(!stats.receive(SerialNumber(20u32)));
+        assert_eq!(stats.duplicates(), 2);
+
+        // Too far behind to be detected
+        assert!(stats.receive(SerialNumber(100u32)));
+        assert!(stats.receive(SerialNumber(30u32)));
+        assert!(stats.receive(SerialNumber(30u32)));
+        assert_eq!(stats.reordered(), 2);
+        assert_eq!(stats.received(), 5);
+    }
+
+    #[test]
+    fn before_first() {
+        let mut stats = StreamStats::new();
+        stats.receive(SerialNumber(5u16));
+        stats.receive(SerialNumber(3u16));
+        assert_eq!(stats.expected(), 3);
+        assert_eq!(stats.cumulative_lost(), 1);
+        assert!(!stats.receive(SerialNumber(u16::MAX)));
+    }
+
+    #[test]
+    fn jump_beyond_u32() {
+        let mut stats = StreamStats::new();
+        assert!(stats.receive(SerialNumber(0u64)));
+        assert!(stats.receive(SerialNumber(1u64)));
+        assert!(stats.receive(SerialNumber((1 << 32) + 1)));
+        assert!(stats.receive(SerialNumber(1 << 32)));
+        assert!(stats.receive(SerialNumber(u64::from(u32::MAX) + (1 << 32) + 1)));
+        assert!(stats.receive(SerialNumber(u64::from(u32::MAX) + (1 << 32))));
+        assert_eq!(stats.duplicates(), 0);
+        assert_eq!(stats.reordered(), 2);
+    }
+}
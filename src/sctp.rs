@@ -0,0 +1,244 @@
+//! Cumulative acknowledgement of SCTP, see
+//! [chapter 6.2 of RFC 9260](https://www.rfc-editor.org/rfc/rfc9260#section-6.2).
+//!
+//! The receiver acknowledges the highest TSN received in sequence (the
+//! cumulative TSN ack point) and reports the TSNs received out of order as
+//! gap ack blocks, whose offsets are relative to the cumulative TSN ack
+//! point, along with the duplicate TSNs received since the last SACK.
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+use std::mem;
+
+use alloc::vec::Vec;
+
+use SerialNumber;
+use range::SerialRange;
+use rangeset::SerialRangeSet;
+use uint::Uint;
+
+/// The outcome of `CumulativeAckTracker::receive`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Receipt {
+    /// The TSN advanced the cumulative TSN ack point.
+    Advanced,
+    /// The TSN has been received out of order, so a SACK should be sent
+    /// immediately.
+    OutOfOrder,
+    /// The TSN has already been received and has been recorded as a
+    /// duplicate.
+    Duplicate,
+    /// The TSN is at least half of the TSN space away from the cumulative
+    /// TSN ack point and has been ignored.
+    Invalid,
+}
+
+/// A gap ack block of a SACK chunk, see
+/// [chapter 3.3.4 of RFC 9260](https://www.rfc-editor.org/rfc/rfc9260#section-3.3.4).
+///
+/// The block covers the TSNs from `cumulative + start` up to and including
+/// `cumulative + end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GapAckBlock<T> {
+    /// The offset of the first TSN of the block.
+    pub start: T,
+    /// The offset of the last TSN of the block.
+    pub end: T,
+}
+
+/// Tracks the received TSNs to populate SACK chunks.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::sctp::{CumulativeAckTracker, GapAckBlock, Receipt};
+///
+/// let mut tracker = CumulativeAckTracker::new(SerialNumber(u32::MAX - 1));
+/// assert_eq!(tracker.cumulative(), SerialNumber(u32::MAX - 2));
+///
+/// assert_eq!(tracker.receive(SerialNumber(u32::MAX - 1)), Receipt::Advanced);
+/// assert_eq!(tracker.receive(SerialNumber(1)), Receipt::OutOfOrder);
+/// assert_eq!(tracker.receive(SerialNumber(2)), Receipt::OutOfOrder);
+/// assert_eq!(tracker.receive(SerialNumber(1)), Receipt::Duplicate);
+/// assert_eq!(tracker.gap_blocks().collect::<Vec<_>>(), vec![GapAckBlock { start: 3, end: 4 }]);
+/// assert_eq!(tracker.take_duplicates(), vec![SerialNumber(1)]);
+///
+/// // Fills the gap across the wrap
+/// assert_eq!(tracker.receive(SerialNumber(u32::MAX)), Receipt::Advanced);
+/// assert_eq!(tracker.receive(SerialNumber(0)), Receipt::Advanced);
+/// assert_eq!(tracker.cumulative(), SerialNumber(2));
+/// assert_eq!(tracker.gap_blocks().count(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CumulativeAckTracker<T> {
+    cumulative: SerialNumber<T>,
+    received: SerialRangeSet<T>,
+    duplicates: Vec<SerialNumber<T>>,
+}
+
+impl<T: Uint> CumulativeAckTracker<T> where SerialNumber<T>: PartialOrd {
+    /// Create a tracker expecting `initial` as the first TSN, so the
+    /// cumulative TSN ack point starts at `initial - 1`.
+    pub fn new(initial: SerialNumber<T>) -> Self {
+        CumulativeAckTracker {
+            cumulative: initial.pred(),
+            received: SerialRangeSet::new(),
+            duplicates: Vec::new(),
+        }
+    }
+
+    /// Return the cumulative TSN ack point, i.e. the highest TSN up to which
+    /// all TSNs have been received.
+    #[inline]
+    pub fn cumulative(&self) -> SerialNumber<T> {
+        self.cumulative
+    }
+
+    /// Return the highest TSN received so far.
+    #[inline]
+    pub fn highest(&self) -> SerialNumber<T> {
+        self.received.end().map_or(self.cumulative, SerialNumber::pred)
+    }
+
+    /// Return whether TSNs are missing between the cumulative TSN ack point
+    /// and the highest TSN received so far.
+    #[inline]
+    pub fn has_gaps(&self) -> bool {
+        !self.received.is_empty()
+    }
+
+    /// Return whether `tsn` has been received.
+    pub fn contains(&self, tsn: SerialNumber<T>) -> bool {
+        match tsn.partial_cmp(&self.cumulative) {
+            Some(Ordering::Greater) => self.received.contains(tsn),
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Record the receipt of `tsn`.
+    ///
+    /// A TSN at or below the cumulative TSN ack point or one that has
+    /// already been received out of order is recorded as a duplicate.
+    pub fn receive(&mut self, tsn: SerialNumber<T>) -> Receipt {
+        match tsn.partial_cmp(&self.cumulative) {
+            Some(Ordering::Greater) => {}
+            Some(_) => return self.duplicate(tsn),
+            None => return Receipt::Invalid,
+        }
+        if tsn != self.cumulative.succ() {
+            let range = SerialRange::new(tsn, tsn.succ());
+            return if self.received.insert(range) { Receipt::OutOfOrder } else { self.duplicate(tsn) };
+        }
+
+        // Absorb the TSNs received out of order that now follow in sequence
+        self.cumulative = tsn;
+        self.received.trim_below(tsn.succ());
+        if let Some(range) = self.received.ranges().next() {
+            if range.start() == tsn.succ() {
+                self.cumulative = range.end().pred();
+                self.received.trim_below(range.end());
+            }
+        }
+        Receipt::Advanced
+    }
+
+    /// Advance the cumulative TSN ack point to `cumulative` as if all TSNs
+    /// up to it had been received, e.g. for a FORWARD TSN chunk of
+    /// [RFC 3758](https://tools.ietf.org/html/rfc3758).
+    ///
+    /// Returns whether the cumulative TSN ack point has been advanced.
+    pub fn forward(&mut self, cumulative: SerialNumber<T>) -> bool {
+        if cumulative.partial_cmp(&self.cumulative) != Some(Ordering::Greater) {
+            return false;
+        }
+        self.cumulative = cumulative.pred();
+        self.receive(cumulative);
+        true
+    }
+
+    /// Return an iterator over the gap ack blocks, lowest first.
+    pub fn gap_blocks(&self) -> impl Iterator<Item = GapAckBlock<T>> + '_ {
+        let cumulative = self.cumulative;
+        self.received.ranges().map(move |range| GapAckBlock {
+            start: range.start().0.wrapping_sub(cumulative.0),
+            end: range.end().0.wrapping_sub(cumulative.0).wrapping_sub(T::ONE),
+        })
+    }
+
+    /// Return the duplicate TSNs received since the last call, in the
+    /// order they have been received.
+    #[inline]
+    pub fn take_duplicates(&mut self) -> Vec<SerialNumber<T>> {
+        mem::take(&mut self.duplicates)
+    }
+
+    fn duplicate(&mut self, tsn: SerialNumber<T>) -> Receipt {
+        self.duplicates.push(tsn);
+        Receipt::Duplicate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    fn blocks<T: Uint>(tracker: &CumulativeAckTracker<T>) -> Vec<(T, T)>
+        where SerialNumber<T>: PartialOrd
+    {
+        tracker.gap_blocks().map(|block| (block.start, block.end)).collect()
+    }
+
+    #[test]
+    fn gap_blocks() {
+        let mut tracker = CumulativeAckTracker::new(SerialNumber(65530));
+        for &tsn in &[65530u16, 65531, 65533, 65534, 0, 3, 4, 5] {
+            assert_ne!(tracker.receive(SerialNumber(tsn)), Receipt::Duplicate);
+        }
+        assert_eq!(tracker.cumulative(), SerialNumber(65531));
+        assert_eq!(tracker.highest(), SerialNumber(5));
+        assert_eq!(blocks(&tracker), vec![(2, 3), (5, 5), (8, 10)]);
+        assert!(tracker.contains(SerialNumber(65534)));
+        assert!(!tracker.contains(SerialNumber(65535)));
+
+        // Merges the first two blocks into the cumulative TSN ack point
+        assert_eq!(tracker.receive(SerialNumber(65532)), Receipt::Advanced);
+        assert_eq!(tracker.cumulative(), SerialNumber(65534));
+        assert_eq!(blocks(&tracker), vec![(2, 2), (5, 7)]);
+        assert_eq!(tracker.receive(SerialNumber(65535)), Receipt::Advanced);
+        assert_eq!(tracker.cumulative(), SerialNumber(0));
+        assert_eq!(blocks(&tracker), vec![(3, 5)]);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut tracker = CumulativeAckTracker::new(SerialNumber(10u32));
+        assert_eq!(tracker.receive(SerialNumber(9)), Receipt::Duplicate);
+        assert_eq!(tracker.receive(SerialNumber(10)), Receipt::Advanced);
+        assert_eq!(tracker.receive(SerialNumber(12)), Receipt::OutOfOrder);
+        assert_eq!(tracker.receive(SerialNumber(12)), Receipt::Duplicate);
+        assert_eq!(tracker.receive(SerialNumber(10)), Receipt::Duplicate);
+        assert_eq!(tracker.receive(SerialNumber(10 + (1 << 31))), Receipt::Invalid);
+        assert_eq!(tracker.take_duplicates(), vec![SerialNumber(9), SerialNumber(12), SerialNumber(10)]);
+        assert!(tracker.take_duplicates().is_empty());
+    }
+
+    #[test]
+    fn forward() {
+        let mut tracker = CumulativeAckTracker::new(SerialNumber(0u8));
+        tracker.receive(SerialNumber(0));
+        tracker.receive(SerialNumber(3));
+        tracker.receive(SerialNumber(5));
+        assert!(!tracker.forward(SerialNumber(0)));
+
+        // Skips 1 and 2, then absorbs 3
+        assert!(tracker.forward(SerialNumber(2)));
+        assert_eq!(tracker.cumulative(), SerialNumber(3));
+        assert_eq!(blocks(&tracker), vec![(2, 2)]);
+        assert!(tracker.take_duplicates().is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+//! Serial number spaces with reserved sentinel values.
+//!
+//! Several wire formats reserve values of their sequence number field, e.g.
+//! `0` meaning "no sequence number" or the largest representable number
+//! meaning "unset". A `SentinelSpace` removes such values from the cycle.
+//!
+//! Requires the `alloc` feature.
+
+use std::cmp::Ordering;
+
+use alloc::vec::Vec;
+
+use SerialNumber;
+use uint::Uint;
+
+/// A serial number space that excludes reserved sentinel values.
+///
+/// Incrementing jumps over reserved values and comparisons can optionally
+/// treat reserved values as invalid.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::sentinel::SentinelSpace;
+///
+/// let space = SentinelSpace::new().with_reserved(0u16);
+///
+/// assert_eq!(space.next(SerialNumber(65535u16)), SerialNumber(1u16));
+/// assert!(space.is_reserved(SerialNumber(0u16)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SentinelSpace<T> {
+    reserved: Vec<T>,
+    reserved_incomparable: bool,
+}
+
+impl<T: Uint> SentinelSpace<T> {
+    /// Create a serial number space without reserved values.
+    #[inline]
+    pub fn new() -> Self {
+        SentinelSpace {
+            reserved: Vec::new(),
+            reserved_incomparable: false,
+        }
+    }
+
+    /// Exclude `value` from the serial number space.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this would reserve every value of the serial number
+    /// space.
+    pub fn with_reserved(mut self, value: T) -> Self {
+        if !self.reserved.contains(&value) {
+            self.reserved.push(value);
+        }
+        assert!(self.reserved.len() < Self::space_len(),
+                "at least one value must not be reserved");
+        self
+    }
+
+    /// Set whether comparisons involving a reserved value yield no
+    /// ordering (like the ambiguous case of RFC 1982) instead of comparing
+    /// the raw serial numbers.
+    #[inline]
+    pub fn with_reserved_incomparable(mut self, incomparable: bool) -> Self {
+        self.reserved_incomparable = incomparable;
+        self
+    }
+
+    /// Return the reserved values.
+    #[inline]
+    pub fn reserved(&self) -> &[T] {
+        &self.reserved
+    }
+
+    /// Return whether `serial` is a reserved value.
+    #[inline]
+    pub fn is_reserved(&self, serial: SerialNumber<T>) -> bool {
+        self.reserved.contains(&serial.0)
+    }
+
+    /// Return the serial number following `serial`, skipping reserved
+    /// values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::SerialNumber;
+    /// use sna::sentinel::SentinelSpace;
+    ///
+    /// let space = SentinelSpace::new().with_reserved(255u8).with_reserved(0u8);
+    ///
+    /// assert_eq!(space.next(SerialNumber(253u8)), SerialNumber(254u8));
+    /// assert_eq!(space.next(SerialNumber(254u8)), SerialNumber(1u8));
+    /// ```
+    pub fn next(&self, serial: SerialNumber<T>) -> SerialNumber<T> {
+        self.first_from(SerialNumber(serial.0.wrapping_add(T::ONE)))
+    }
+
+    /// Return `serial` advanced by `n` non-reserved steps.
+    ///
+    /// This takes time depending on the amount of reserved values only, not
+    /// on `n`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::SerialNumber;
+    /// use sna::sentinel::SentinelSpace;
+    ///
+    /// let space = SentinelSpace::new().with_reserved(0u8);
+    ///
+    /// assert_eq!(space.advance(SerialNumber(250u8), 10), SerialNumber(5u8));
+    /// ```
+    pub fn advance(&self, serial: SerialNumber<T>, n: usize) -> SerialNumber<T> {
+        if n == 0 {
+            return serial;
+        }
+        // Advance from the first non-reserved value, modulo the amount of
+        // non-reserved values
+        let start = self.next(serial);
+        let mut steps = (n - 1) as u128;
+        if T::BITS < 128 {
+            steps %= (1 << T::BITS) - self.reserved.len() as u128;
+        }
+
+        // Skip the reserved values up to the target by their rank
+        let mut offsets: Vec<u128> = self.reserved.iter()
+            .map(|&value| value.wrapping_sub(start.0).to_u128())
+            .collect();
+        offsets.sort_unstable();
+        let distance = offsets.into_iter()
+            .fold(steps, |distance, offset| if offset <= distance { distance + 1 } else { distance });
+        SerialNumber(start.0.wrapping_add(T::from_u128(distance)))
+    }
+
+    /// Return `serial` if it is not reserved, otherwise the next
+    /// non-reserved value following it.
+    ///
+    /// This is useful to pick a valid initial serial number.
+    pub fn first_from(&self, serial: SerialNumber<T>) -> SerialNumber<T> {
+        let mut serial = serial;
+        while self.is_reserved(serial) {
+            serial = SerialNumber(serial.0.wrapping_add(T::ONE));
+        }
+        serial
+    }
+
+    /// Return an ordering between `left` and `right`.
+    ///
+    /// Returns `None` if the comparison is ambiguous or, if configured, one
+    /// of the values is reserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use sna::SerialNumber;
+    /// use sna::sentinel::SentinelSpace;
+    ///
+    /// let space = SentinelSpace::new().with_reserved(0u8).with_reserved_incomparable(true);
+    ///
+    /// assert_eq!(space.partial_cmp(SerialNumber(1u8), SerialNumber(255u8)), Some(Ordering::Greater));
+    /// assert_eq!(space.partial_cmp(SerialNumber(0u8), SerialNumber(255u8)), None);
+    /// ```
+    pub fn partial_cmp(&self, left: SerialNumber<T>, right: SerialNumber<T>) -> Option<Ordering>
+        where SerialNumber<T>: PartialOrd
+    {
+        if self.reserved_incomparable && (self.is_reserved(left) || self.is_reserved(right)) {
+            None
+        } else {
+            left.partial_cmp(&right)
+        }
+    }
+
+    fn space_len() -> usize {
+        if T::BITS as usize >= 8 * ::std::mem::size_of::<usize>() {
+            usize::MAX
+        } else {
+            1 << T::BITS
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_reserved() {
+        let space = SentinelSpace::new();
+        assert_eq!(space.next(SerialNumber(u32::MAX)), SerialNumber(0));
+        assert_eq!(space.advance(SerialNumber(u32::MAX), 3), SerialNumber(2));
+    }
+
+    #[test]
+    fn skip_consecutive() {
+        let space = SentinelSpace::new().with_reserved(u64::MAX).with_reserved(0).with_reserved(1);
+        assert_eq!(space.next(SerialNumber(u64::MAX - 1)), SerialNumber(2));
+        assert_eq!(space.first_from(SerialNumber(0)), SerialNumber(2));
+        assert_eq!(space.first_from(SerialNumber(3)), SerialNumber(3));
+    }
+
+    #[test]
+    fn advance_matches_stepping() {
+        let space = SentinelSpace::new().with_reserved(0u8).with_reserved(3).with_reserved(4)
+            .with_reserved(200).with_reserved(255);
+        for &start in &[0u8, 1, 3, 199, 254, 255] {
+            let mut expected = SerialNumber(start);
+            for n in 0..600 {
+                assert_eq!(space.advance(SerialNumber(start), n), expected);
+                expected = space.next(expected);
+            }
+        }
+    }
+
+    #[test]
+    fn advance_far() {
+        let space = SentinelSpace::new().with_reserved(0u64).with_reserved(u64::MAX);
+        // A full cycle of the 2^64 - 2 non-reserved values
+        assert_eq!(space.advance(SerialNumber(1), usize::MAX - 1), SerialNumber(1));
+        assert_eq!(space.advance(SerialNumber(1), usize::MAX), SerialNumber(2));
+        assert_eq!(space.advance(SerialNumber(u64::MAX - 1), 2), SerialNumber(2));
+
+        let space = SentinelSpace::new().with_reserved(5u128);
+        assert_eq!(space.advance(SerialNumber(0), usize::MAX),
+                   SerialNumber(usize::MAX as u128 + 1));
+    }
+
+    #[test]
+    fn duplicates() {
+        let space = SentinelSpace::new().with_reserved(0u8).with_reserved(0u8);
+        assert_eq!(space.reserved(), &[0]);
+    }
+
+    #[test]
+    fn comparable_by_default() {
+        let space = SentinelSpace::new().with_reserved(0u16);
+        assert_eq!(space.partial_cmp(SerialNumber(0), SerialNumber(u16::MAX)), Some(Ordering::Greater));
+        assert_eq!(space.partial_cmp(SerialNumber(0), SerialNumber(1u16 << 15)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be reserved")]
+    fn reserve_everything() {
+        (0..256u32).fold(SentinelSpace::new(), |space, value| space.with_reserved(value as u8));
+    }
+}
@@ -0,0 +1,172 @@
+//! A sequence counter for lock-free snapshot reads of shared state.
+
+use std::hint;
+use std::sync::atomic::{fence, Ordering};
+
+use SerialNumber;
+use atomic::AtomicSerialNumber;
+
+/// A sequence counter (the counter part of a seqlock).
+///
+/// The writer increments the sequence before and after mutating the
+/// protected state, so the sequence is odd while a write is in progress.
+/// Readers take a snapshot of the state and retry if the sequence was odd or
+/// changed in the meantime.
+///
+/// The sequence is an `AtomicSerialNumber`, so it may wrap. Writers must be
+/// serialised externally (e.g. by a mutex or by having a single writer).
+/// The protected state itself must be accessed with atomics, as readers may
+/// observe it while it is being written.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use sna::seqcount::SeqCount;
+///
+/// let sequence = SeqCount::new();
+/// let (low, high) = (AtomicU32::new(0), AtomicU32::new(0));
+///
+/// sequence.write(|| {
+///     low.store(1, Ordering::Relaxed);
+///     high.store(2, Ordering::Relaxed);
+/// });
+///
+/// let snapshot = sequence.read(|| (low.load(Ordering::Relaxed), high.load(Ordering::Relaxed)));
+/// assert_eq!(snapshot, (1, 2));
+/// ```
+#[derive(Debug, Default)]
+pub struct SeqCount {
+    sequence: AtomicSerialNumber<u32>,
+}
+
+impl SeqCount {
+    /// Create a sequence counter starting at zero.
+    #[inline]
+    pub fn new() -> Self {
+        SeqCount {
+            sequence: AtomicSerialNumber::new(SerialNumber(0)),
+        }
+    }
+
+    /// Return the current sequence.
+    ///
+    /// The sequence is odd while a write is in progress. Since it only
+    /// moves forward, two sequences can be compared to determine which
+    /// snapshot is newer.
+    #[inline]
+    pub fn sequence(&self) -> SerialNumber<u32> {
+        self.sequence.load(Ordering::Acquire)
+    }
+
+    /// Begin a read section and return the sequence to be passed to
+    /// `read_retry`.
+    ///
+    /// Spins while a write is in progress.
+    #[inline]
+    pub fn read_begin(&self) -> SerialNumber<u32> {
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence.0 & 1 == 0 {
+                return sequence;
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// End a read section and return whether it has to be retried because
+    /// a write happened since `read_begin` returned `start`.
+    #[inline]
+    pub fn read_retry(&self, start: SerialNumber<u32>) -> bool {
+        fence(Ordering::Acquire);
+        self.sequence.load(Ordering::Relaxed) != start
+    }
+
+    /// Begin a write section.
+    #[inline]
+    pub fn write_begin(&self) {
+        self.sequence.fetch_next(Ordering::Relaxed);
+        fence(Ordering::Release);
+    }
+
+    /// End a write section.
+    #[inline]
+    pub fn write_end(&self) {
+        self.sequence.fetch_next(Ordering::Release);
+    }
+
+    /// Run `f` until it produced a consistent snapshot and return it.
+    pub fn read<F: FnMut() -> R, R>(&self, mut f: F) -> R {
+        loop {
+            let start = self.read_begin();
+            let result = f();
+            if !self.read_retry(start) {
+                return result;
+            }
+        }
+    }
+
+    /// Run `f` inside a write section.
+    pub fn write<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        self.write_begin();
+        let result = f();
+        self.write_end();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence() {
+        let sequence = SeqCount::new();
+        let start = sequence.read_begin();
+        assert_eq!(start, SerialNumber(0));
+        sequence.write_begin();
+        assert_eq!(sequence.sequence(), SerialNumber(1));
+        assert!(sequence.read_retry(start));
+        sequence.write_end();
+        assert!(sequence.read_retry(start));
+        assert!(sequence.sequence() > start);
+        assert!(!sequence.read_retry(sequence.read_begin()));
+    }
+
+    #[test]
+    fn wraps() {
+        let sequence = SeqCount { sequence: AtomicSerialNumber::new(SerialNumber(u32::MAX - 1)) };
+        let start = sequence.read_begin();
+        sequence.write(|| ());
+        assert_eq!(sequence.sequence(), SerialNumber(0));
+        assert!(sequence.sequence() > start);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn consistent_snapshots() {
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicU64;
+        use std::thread;
+
+        let shared = Arc::new((SeqCount::new(), AtomicU64::new(0), AtomicU64::new(0)));
+        let writer = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for value in 1..10_000 {
+                    shared.0.write(|| {
+                        shared.1.store(value, Ordering::Relaxed);
+                        shared.2.store(value, Ordering::Relaxed);
+                    });
+                }
+            })
+        };
+        for _ in 0..10_000 {
+            let (a, b) = shared.0.read(|| {
+                (shared.1.load(Ordering::Relaxed), shared.2.load(Ordering::Relaxed))
+            });
+            assert_eq!(a, b);
+        }
+        writer.join().unwrap();
+    }
+}
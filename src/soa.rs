@@ -0,0 +1,151 @@
+//! Helpers for the serial numbers of DNS zones (the `SERIAL` field of the
+//! `SOA` record).
+//!
+//! Secondary servers only transfer a zone if its serial number increased in
+//! terms of RFC 1982. A single change may only increase the serial number
+//! by at most `2^31 - 1`, so moving it to an arbitrary value (e.g. back to a
+//! date-based serial number) can take several steps, each of which must
+//! have propagated to all secondaries before the next one is published, see
+//! [chapter 7 of RFC 1982](https://tools.ietf.org/html/rfc1982#section-7).
+
+use std::cmp::Ordering;
+
+use SerialNumber;
+#[cfg(feature = "time")]
+use date::{self, DateSerialError};
+#[cfg(feature = "time")]
+use time::Date;
+
+/// The largest amount a zone serial number may be increased by at once.
+pub const MAX_INCREMENT: u32 = (1 << 31) - 1;
+
+/// Return whether a secondary with serial number `secondary` should
+/// transfer the zone with serial number `primary`.
+///
+/// This is `false` if both are exactly `2^31` apart, as RFC 1982 leaves the
+/// comparison undefined in that case.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::soa::is_newer;
+///
+/// assert!(is_newer(SerialNumber(5), SerialNumber(u32::MAX)));
+/// assert!(!is_newer(SerialNumber(1 << 31), SerialNumber(0)));
+/// ```
+#[inline]
+pub fn is_newer(primary: SerialNumber<u32>, secondary: SerialNumber<u32>) -> bool {
+    primary.partial_cmp(&secondary) == Some(Ordering::Greater)
+}
+
+/// Return the serial number that should follow `current` on `date` in
+/// `YYYYMMDDnn` format.
+///
+/// Requires the `time` feature. See `date::next_serial`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate sna;
+/// # extern crate time;
+/// use sna::SerialNumber;
+/// use sna::soa::next_date_serial;
+/// use time::{Date, Month};
+///
+/// let date = Date::from_calendar_date(2017, Month::December, 24).unwrap();
+/// assert_eq!(next_date_serial(SerialNumber(2017122301), date), Ok(SerialNumber(2017122400)));
+/// ```
+#[cfg(feature = "time")]
+#[inline]
+pub fn next_date_serial(current: SerialNumber<u32>, date: Date)
+    -> Result<SerialNumber<u32>, DateSerialError>
+{
+    date::next_serial(current, date)
+}
+
+/// Return the serial numbers that need to be published one after another
+/// to move a zone from `current` to `target`.
+///
+/// Yields nothing if `current` equals `target`, `target` if it can be
+/// reached by a single increment and up to two intermediate serial numbers
+/// before `target` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::soa::bump_to;
+///
+/// // 2017122400 is lower than 3000000000, so go the long way round
+/// let steps: Vec<_> = bump_to(SerialNumber(3000000000), SerialNumber(2017122400)).collect();
+/// assert_eq!(steps, vec![SerialNumber(852516351), SerialNumber(2017122400)]);
+/// ```
+#[inline]
+pub fn bump_to(current: SerialNumber<u32>, target: SerialNumber<u32>) -> BumpTo {
+    BumpTo { current, target }
+}
+
+/// An iterator over the serial numbers to publish to reach a target.
+///
+/// Created by `bump_to`.
+#[derive(Clone, Debug)]
+pub struct BumpTo {
+    current: SerialNumber<u32>,
+    target: SerialNumber<u32>,
+}
+
+impl Iterator for BumpTo {
+    type Item = SerialNumber<u32>;
+
+    fn next(&mut self) -> Option<SerialNumber<u32>> {
+        let remaining = self.target.0.wrapping_sub(self.current.0);
+        if remaining == 0 {
+            return None;
+        }
+        self.current = SerialNumber(self.current.0.wrapping_add(remaining.min(MAX_INCREMENT)));
+        Some(self.current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.target.0.wrapping_sub(self.current.0);
+        let steps = remaining.div_ceil(MAX_INCREMENT) as usize;
+        (steps, Some(steps))
+    }
+}
+
+impl ExactSizeIterator for BumpTo {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn steps(current: u32, target: u32) -> Vec<u32> {
+        bump_to(SerialNumber(current), SerialNumber(target)).map(|serial| serial.0).collect()
+    }
+
+    #[test]
+    fn bump() {
+        assert!(steps(7, 7).is_empty());
+        assert_eq!(steps(7, 8), vec![8]);
+        assert_eq!(steps(0, MAX_INCREMENT), vec![MAX_INCREMENT]);
+        assert_eq!(steps(0, 1 << 31), vec![MAX_INCREMENT, 1 << 31]);
+        assert_eq!(steps(1, 0), vec![1 << 31, u32::MAX, 0]);
+        assert_eq!(bump_to(SerialNumber(1), SerialNumber(0)).len(), 3);
+    }
+
+    #[test]
+    fn every_step_is_newer() {
+        for &(current, target) in &[(1u32, 0u32), (3000000000, 2017122400), (0, 1 << 31)] {
+            let mut previous = SerialNumber(current);
+            for step in bump_to(SerialNumber(current), SerialNumber(target)) {
+                assert!(is_newer(step, previous));
+                previous = step;
+            }
+            assert_eq!(previous, SerialNumber(target));
+        }
+    }
+}
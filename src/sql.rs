@@ -0,0 +1,148 @@
+//! Mappings of `SerialNumber<u32>` and `SerialNumber<u64>` to SQL integer
+//! columns, e.g. for storing DNS zone serials.
+//!
+//! Postgres (like most SQL databases) has no unsigned integer types, so
+//! both are mapped to `BIGINT` (`INT8`) columns:
+//!
+//! * `SerialNumber<u32>` is stored as its value, so it remains readable in
+//!   queries. Decoding fails if a value exceeds the range of `u32`.
+//! * `SerialNumber<u64>` is stored as its bits reinterpreted as `i64`, i.e.
+//!   values from `2^63` are stored as negative numbers.
+//!
+//! Note that ordering by such a column does not follow RFC 1982 in either
+//! case.
+//!
+//! The `sqlx` feature implements `Type`, `Encode` and `Decode` for every
+//! `sqlx` database that supports `i64`. The `diesel` feature implements
+//! `ToSql` and `FromSql` for the `BigInt` type of the Postgres backend.
+
+use std::convert::TryFrom;
+use std::num::TryFromIntError;
+
+#[cfg(feature = "diesel")]
+use diesel::deserialize::{self, FromSql};
+#[cfg(feature = "diesel")]
+use diesel::pg::{Pg, PgValue};
+#[cfg(feature = "diesel")]
+use diesel::serialize::{self, Output, ToSql};
+#[cfg(feature = "diesel")]
+use diesel::sql_types::BigInt;
+#[cfg(feature = "sqlx")]
+use sqlx::{Database, Decode, Encode, Type};
+#[cfg(feature = "sqlx")]
+use sqlx::encode::IsNull;
+#[cfg(feature = "sqlx")]
+use sqlx::error::BoxDynError;
+
+use SerialNumber;
+
+/// Conversion from and to the `i64` stored in a `BIGINT` column.
+trait BigIntColumn: Sized {
+    fn to_column(self) -> i64;
+    fn from_column(value: i64) -> Result<Self, TryFromIntError>;
+}
+
+impl BigIntColumn for SerialNumber<u32> {
+    #[inline]
+    fn to_column(self) -> i64 {
+        i64::from(self.0)
+    }
+
+    #[inline]
+    fn from_column(value: i64) -> Result<Self, TryFromIntError> {
+        u32::try_from(value).map(SerialNumber)
+    }
+}
+
+impl BigIntColumn for SerialNumber<u64> {
+    #[inline]
+    fn to_column(self) -> i64 {
+        self.0 as i64
+    }
+
+    #[inline]
+    fn from_column(value: i64) -> Result<Self, TryFromIntError> {
+        Ok(SerialNumber(value as u64))
+    }
+}
+
+macro_rules! sql_impl {
+    ($T:ty) => {
+        #[cfg(feature = "sqlx")]
+        impl<DB: Database> Type<DB> for SerialNumber<$T> where i64: Type<DB> {
+            #[inline]
+            fn type_info() -> DB::TypeInfo {
+                <i64 as Type<DB>>::type_info()
+            }
+
+            #[inline]
+            fn compatible(ty: &DB::TypeInfo) -> bool {
+                <i64 as Type<DB>>::compatible(ty)
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'q, DB: Database> Encode<'q, DB> for SerialNumber<$T> where i64: Encode<'q, DB> {
+            #[inline]
+            fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>)
+                             -> Result<IsNull, BoxDynError> {
+                self.to_column().encode_by_ref(buf)
+            }
+
+            #[inline]
+            fn size_hint(&self) -> usize {
+                self.to_column().size_hint()
+            }
+        }
+
+        #[cfg(feature = "sqlx")]
+        impl<'r, DB: Database> Decode<'r, DB> for SerialNumber<$T> where i64: Decode<'r, DB> {
+            #[inline]
+            fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok(Self::from_column(i64::decode(value)?)?)
+            }
+        }
+
+        #[cfg(feature = "diesel")]
+        impl ToSql<BigInt, Pg> for SerialNumber<$T> {
+            #[inline]
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                ToSql::<BigInt, Pg>::to_sql(&self.to_column(), &mut out.reborrow())
+            }
+        }
+
+        #[cfg(feature = "diesel")]
+        impl FromSql<BigInt, Pg> for SerialNumber<$T> {
+            #[inline]
+            fn from_sql(value: PgValue) -> deserialize::Result<Self> {
+                Ok(Self::from_column(FromSql::<BigInt, Pg>::from_sql(value)?)?)
+            }
+        }
+    }
+}
+
+sql_impl!(u32);
+sql_impl!(u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u32_column() {
+        assert_eq!(SerialNumber(u32::MAX).to_column(), 4_294_967_295);
+        assert_eq!(SerialNumber::<u32>::from_column(4_294_967_295), Ok(SerialNumber(u32::MAX)));
+        assert!(SerialNumber::<u32>::from_column(1 << 32).is_err());
+        assert!(SerialNumber::<u32>::from_column(-1).is_err());
+    }
+
+    #[test]
+    fn u64_column() {
+        assert_eq!(SerialNumber(u64::MAX).to_column(), -1);
+        assert_eq!(SerialNumber(1u64 << 63).to_column(), i64::MIN);
+        for &value in &[0, 1, i64::MAX as u64, 1 << 63, u64::MAX] {
+            let serial = SerialNumber(value);
+            assert_eq!(SerialNumber::<u64>::from_column(serial.to_column()), Ok(serial));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Packet index estimation of SRTP, see
+//! [chapter 3.3.1 of RFC 3711](https://tools.ietf.org/html/rfc3711#section-3.3.1).
+//!
+//! The 48-bit SRTP packet index consists of the 32-bit rollover counter
+//! (ROC), which counts the wraps of the RTP sequence number, and the 16-bit
+//! sequence number. Only the sequence number is transmitted, so the
+//! receiver estimates the index relative to the highest sequence number
+//! received so far (`s_l`) and its current ROC.
+//!
+//! The ROC must only be updated once the packet has been authenticated.
+
+use SerialNumber;
+
+/// Half of the sequence number space.
+const HALF: i32 = 1 << 15;
+
+/// Estimate the packet index of `seq` as specified in
+/// [appendix A of RFC 3711](https://tools.ietf.org/html/rfc3711#appendix-A),
+/// returning the index and the adjustment (`-1`, `0` or `1`) applied to
+/// `roc` to obtain the ROC of the index.
+///
+/// `s_l` is the highest sequence number received so far and `roc` its ROC.
+/// Like in RFC 3711, a sequence number exactly half of the sequence number
+/// space away from `s_l` belongs to the ROC of `s_l`, and the ROC wraps
+/// modulo `2^32`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::srtp::estimate_index;
+///
+/// // The sequence number wrapped
+/// assert_eq!(estimate_index(65535, 2, SerialNumber(3)), ((3 << 16) + 3, 1));
+///
+/// // A late packet from before the wrap
+/// assert_eq!(estimate_index(3, 3, SerialNumber(65530)), ((2 << 16) + 65530, -1));
+///
+/// assert_eq!(estimate_index(1000, 3, SerialNumber(900)), ((3 << 16) + 900, 0));
+/// ```
+pub fn estimate_index(s_l: u16, roc: u32, seq: SerialNumber<u16>) -> (u64, i8) {
+    let (s_l, seq) = (i32::from(s_l), seq.0);
+    let adjustment = if s_l < HALF {
+        if i32::from(seq) - s_l > HALF { -1 } else { 0 }
+    } else if s_l - HALF > i32::from(seq) {
+        1
+    } else {
+        0
+    };
+    let v = roc.wrapping_add(adjustment as u32);
+    (u64::from(v) << 16 | u64::from(seq), adjustment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundaries() {
+        // Exactly half of the sequence number space apart
+        assert_eq!(estimate_index(100, 5, SerialNumber(32868)), ((5 << 16) + 32868, 0));
+        assert_eq!(estimate_index(100, 5, SerialNumber(32869)), ((4 << 16) + 32869, -1));
+        assert_eq!(estimate_index(40000, 5, SerialNumber(7232)), ((5 << 16) + 7232, 0));
+        assert_eq!(estimate_index(40000, 5, SerialNumber(7231)), ((6 << 16) + 7231, 1));
+        assert_eq!(estimate_index(32768, 5, SerialNumber(0)), (5 << 16, 0));
+        assert_eq!(estimate_index(32767, 5, SerialNumber(65535)), ((5 << 16) + 65535, 0));
+        assert_eq!(estimate_index(32766, 5, SerialNumber(65535)), ((4 << 16) + 65535, -1));
+
+        // The ROC wraps
+        assert_eq!(estimate_index(65535, u32::MAX, SerialNumber(0)), (0, 1));
+        assert_eq!(estimate_index(0, 0, SerialNumber(65535)), ((1 << 48) - 1, -1));
+    }
+
+    #[test]
+    fn closest_to_highest() {
+        let roc = 7;
+        for s_l in (0..=u16::MAX).step_by(251) {
+            let reference = i64::from(roc) << 16 | i64::from(s_l);
+            for seq in 0..=u16::MAX {
+                let (index, adjustment) = estimate_index(s_l, roc, SerialNumber(seq));
+                assert!((index as i64 - reference).abs() <= i64::from(HALF));
+                assert_eq!(index >> 16, (i64::from(roc) + i64::from(adjustment)) as u64);
+                assert_eq!(index as u16, seq);
+            }
+        }
+    }
+}
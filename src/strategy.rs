@@ -0,0 +1,96 @@
+//! Strategies for property-based testing with `proptest`.
+//!
+//! Arbitrary serial numbers are rarely close enough to each other (or to
+//! exactly half of the serial number space apart) to exercise the edge
+//! cases of RFC 1982, so `near_boundary_pairs` favours these.
+//!
+//! Requires the `proptest` feature.
+
+use proptest::arbitrary::{any, any_with, Arbitrary};
+use proptest::strategy::{Map, Strategy};
+
+use SerialNumber;
+use uint::Uint;
+
+/// The largest offset from a boundary that `near_boundary_pairs` generates.
+const SPREAD: i64 = 16;
+
+impl<T: Arbitrary> Arbitrary for SerialNumber<T> {
+    type Parameters = T::Parameters;
+    type Strategy = Map<T::Strategy, fn(T) -> Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        any_with::<T>(args).prop_map(SerialNumber)
+    }
+}
+
+/// Generate pairs of serial numbers that are close to each other or close
+/// to half of the serial number space apart, i.e. close to where the
+/// comparison is undefined.
+///
+/// The first serial number of a pair is arbitrary or close to the wrap
+/// point or to half of the serial number space.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate proptest;
+/// # extern crate sna;
+/// # fn main() {
+/// use sna::strategy::near_boundary_pairs;
+///
+/// proptest!(|((a, b) in near_boundary_pairs::<u16>())| {
+///     prop_assert_eq!(a < b, b > a);
+/// });
+/// # }
+/// ```
+pub fn near_boundary_pairs<T>() -> impl Strategy<Value = (SerialNumber<T>, SerialNumber<T>)>
+    where T: Uint + Arbitrary
+{
+    let first = prop_oneof![any::<T>(), near(T::ZERO), near(T::HALF)];
+    let distance = prop_oneof![near(T::ZERO), near(T::HALF)];
+    (first, distance).prop_map(|(first, distance)| {
+        (SerialNumber(first), SerialNumber(first.wrapping_add(distance)))
+    })
+}
+
+fn near<T: Uint>(boundary: T) -> impl Strategy<Value = T> {
+    (-SPREAD..=SPREAD).prop_map(move |offset| boundary.wrapping_add(T::from_i64(offset)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::strategy::ValueTree;
+    use proptest::test_runner::TestRunner;
+
+    #[test]
+    fn hits_boundaries() {
+        let mut runner = TestRunner::deterministic();
+        let strategy = near_boundary_pairs::<u8>();
+        let (mut ambiguous, mut wrapping) = (false, false);
+        for _ in 0..1000 {
+            let (a, b) = strategy.new_tree(&mut runner).unwrap().current();
+            let distance = b.0.wrapping_sub(a.0);
+            assert!(distance <= 16 || distance >= 240 || (112..=144).contains(&distance));
+            ambiguous |= distance == 128;
+            wrapping |= b.0 < a.0 && distance <= 16;
+        }
+        assert!(ambiguous);
+        assert!(wrapping);
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary(serial in any::<SerialNumber<u32>>()) {
+            prop_assert_eq!(serial + 1, SerialNumber(serial.0.wrapping_add(1)));
+        }
+
+        #[test]
+        fn antisymmetric((a, b) in near_boundary_pairs::<u64>()) {
+            prop_assert_eq!(a < b, b > a);
+            prop_assert_eq!(a.partial_cmp(&b).is_none(), b.0.wrapping_sub(a.0) == 1 << 63);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Sequence number checks of TCP, see
+//! [chapter 3.4 of RFC 9293](https://www.rfc-editor.org/rfc/rfc9293#section-3.4).
+//!
+//! TCP compares 32-bit sequence numbers modulo `2^32`. A window is the
+//! range of `size` sequence numbers starting at `lower`, which is only
+//! well-defined if `size` is at most half of the sequence number space (the
+//! largest scaled window of TCP is `2^30`).
+
+use SerialNumber;
+
+/// Return whether `lower <= serial < lower + size` modulo `2^32`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::tcp::seq_in_window;
+///
+/// let lower = SerialNumber(u32::MAX - 1);
+/// assert!(seq_in_window(SerialNumber(u32::MAX - 1), lower, 4));
+/// assert!(seq_in_window(SerialNumber(1), lower, 4));
+/// assert!(!seq_in_window(SerialNumber(2), lower, 4));
+/// assert!(!seq_in_window(SerialNumber(u32::MAX - 2), lower, 4));
+/// ```
+#[inline]
+pub fn seq_in_window(serial: SerialNumber<u32>, lower: SerialNumber<u32>, size: u32) -> bool {
+    serial.is_within(lower, size)
+}
+
+/// Return whether an incoming segment occupying `seg_len` sequence numbers
+/// from `seg_seq` is acceptable for a receive window of `rcv_wnd` sequence
+/// numbers from `rcv_nxt`.
+///
+/// This implements the four cases of the acceptability test of
+/// [chapter 3.10.7.4 of RFC 9293](https://www.rfc-editor.org/rfc/rfc9293#section-3.10.7.4).
+/// `seg_len` includes the `SYN` and `FIN` flags. A segment is acceptable
+/// if any part of it lies within the window, so its data may still need
+/// to be trimmed to the window.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::tcp::segment_acceptable;
+///
+/// let rcv_nxt = SerialNumber(u32::MAX - 99);
+///
+/// // Retransmission overlapping the start of the window
+/// assert!(segment_acceptable(SerialNumber(u32::MAX - 199), 101, rcv_nxt, 1000));
+/// // Entirely old duplicate
+/// assert!(!segment_acceptable(SerialNumber(u32::MAX - 199), 100, rcv_nxt, 1000));
+/// // Zero window only accepts empty segments at `rcv_nxt`, e.g. ACKs
+/// assert!(segment_acceptable(rcv_nxt, 0, rcv_nxt, 0));
+/// assert!(!segment_acceptable(rcv_nxt, 1, rcv_nxt, 0));
+/// ```
+pub fn segment_acceptable(seg_seq: SerialNumber<u32>, seg_len: u32,
+                          rcv_nxt: SerialNumber<u32>, rcv_wnd: u32) -> bool {
+    match (seg_len, rcv_wnd) {
+        (0, 0) => seg_seq == rcv_nxt,
+        (0, _) => seq_in_window(seg_seq, rcv_nxt, rcv_wnd),
+        (_, 0) => false,
+        (_, _) => {
+            let last = SerialNumber(seg_seq.0.wrapping_add(seg_len - 1));
+            seq_in_window(seg_seq, rcv_nxt, rcv_wnd) || seq_in_window(last, rcv_nxt, rcv_wnd)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window() {
+        let lower = SerialNumber(100);
+        assert!(!seq_in_window(SerialNumber(100), lower, 0));
+        assert!(seq_in_window(SerialNumber(100), lower, 1));
+        assert!(!seq_in_window(SerialNumber(99), lower, 1 << 30));
+        assert!(seq_in_window(SerialNumber(99 + (1 << 30)), lower, 1 << 30));
+        assert!(!seq_in_window(SerialNumber(100 + (1 << 30)), lower, 1 << 30));
+    }
+
+    #[test]
+    fn acceptability() {
+        let rcv_nxt = SerialNumber(u32::MAX - 9);
+        let at = |offset: i64| SerialNumber((i64::from(rcv_nxt.0) + offset) as u32);
+
+        // Empty segments
+        assert!(segment_acceptable(at(0), 0, rcv_nxt, 0));
+        assert!(!segment_acceptable(at(1), 0, rcv_nxt, 0));
+        assert!(segment_acceptable(at(19), 0, rcv_nxt, 20));
+        assert!(!segment_acceptable(at(20), 0, rcv_nxt, 20));
+        assert!(!segment_acceptable(at(-1), 0, rcv_nxt, 20));
+
+        // Non-empty segments
+        assert!(!segment_acceptable(at(0), 1, rcv_nxt, 0));
+        assert!(segment_acceptable(at(-5), 6, rcv_nxt, 20));
+        assert!(!segment_acceptable(at(-5), 5, rcv_nxt, 20));
+        assert!(segment_acceptable(at(19), 100, rcv_nxt, 20));
+        assert!(!segment_acceptable(at(20), 1, rcv_nxt, 20));
+    }
+}
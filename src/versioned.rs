@@ -0,0 +1,179 @@
+//! Optimistic concurrency control with wrapping version numbers.
+
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+
+use SerialNumber;
+use uint::Uint;
+
+/// A value tagged with a serial version number.
+///
+/// Updates are only applied if the caller read the current version
+/// (compare-and-set), and each update increments the version. Since the
+/// version is a `SerialNumber`, it may wrap without breaking staleness
+/// checks.
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::versioned::Versioned;
+///
+/// let mut row: Versioned<&str> = Versioned::with_version("a", SerialNumber(u32::MAX));
+///
+/// let read = row.version();
+/// assert_eq!(row.compare_and_set(read, "b"), Ok(SerialNumber(0)));
+/// assert!(row.is_stale(read));
+///
+/// let conflict = row.compare_and_set(read, "c").unwrap_err();
+/// assert_eq!(conflict.current(), SerialNumber(0));
+/// assert_eq!(*row.value(), "b");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Versioned<V, T = u32> {
+    value: V,
+    version: SerialNumber<T>,
+}
+
+/// The error returned by `Versioned::compare_and_set` if the expected
+/// version does not match the current version.
+///
+/// Contains the rejected value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionConflict<V, T = u32> {
+    current: SerialNumber<T>,
+    value: V,
+}
+
+impl<V, T: Uint> Versioned<V, T> {
+    /// Create a value with version zero.
+    #[inline]
+    pub fn new(value: V) -> Self {
+        Versioned::with_version(value, SerialNumber(T::ZERO))
+    }
+
+    /// Create a value with a specific version, e.g. one loaded from
+    /// storage.
+    #[inline]
+    pub fn with_version(value: V, version: SerialNumber<T>) -> Self {
+        Versioned { value, version }
+    }
+
+    /// Return the value.
+    #[inline]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Return the current version.
+    #[inline]
+    pub fn version(&self) -> SerialNumber<T> {
+        self.version
+    }
+
+    /// Consume the cell, returning the value and its version.
+    #[inline]
+    pub fn into_inner(self) -> (V, SerialNumber<T>) {
+        (self.value, self.version)
+    }
+
+    /// Replace the value if `expected` equals the current version and return
+    /// the new (incremented) version.
+    pub fn compare_and_set(&mut self, expected: SerialNumber<T>, value: V)
+        -> Result<SerialNumber<T>, VersionConflict<V, T>>
+    {
+        if expected != self.version {
+            return Err(VersionConflict {
+                current: self.version,
+                value,
+            });
+        }
+        self.value = value;
+        self.version = SerialNumber(self.version.0.wrapping_add(T::ONE));
+        Ok(self.version)
+    }
+
+    /// Return whether a value read at `read_version` is outdated.
+    ///
+    /// This is the case if `read_version` precedes the current version. As
+    /// a precaution, versions exactly half of the version space apart (for
+    /// which RFC 1982 defines no ordering) are considered stale as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::SerialNumber;
+    /// use sna::versioned::Versioned;
+    ///
+    /// let row = Versioned::with_version((), SerialNumber(1u8));
+    ///
+    /// assert!(row.is_stale(SerialNumber(255u8)));
+    /// assert!(row.is_stale(SerialNumber(129u8)));
+    /// assert!(!row.is_stale(SerialNumber(1u8)));
+    /// assert!(!row.is_stale(SerialNumber(2u8)));
+    /// ```
+    pub fn is_stale(&self, read_version: SerialNumber<T>) -> bool
+        where SerialNumber<T>: PartialOrd
+    {
+        match read_version.partial_cmp(&self.version) {
+            Some(Ordering::Less) | None => true,
+            Some(Ordering::Equal) | Some(Ordering::Greater) => false,
+        }
+    }
+}
+
+impl<V, T: Uint> VersionConflict<V, T> {
+    /// Return the current version of the value that failed to be updated.
+    #[inline]
+    pub fn current(&self) -> SerialNumber<T> {
+        self.current
+    }
+
+    /// Return the rejected value.
+    #[inline]
+    pub fn into_value(self) -> V {
+        self.value
+    }
+}
+
+impl<V, T: Uint> fmt::Display for VersionConflict<V, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "version conflict, current version is {:?}", self.current.0)
+    }
+}
+
+impl<V: fmt::Debug, T: Uint> error::Error for VersionConflict<V, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn compare_and_set() {
+        let mut cell: Versioned<u8, u16> = Versioned::new(1);
+        assert_eq!(cell.compare_and_set(SerialNumber(1), 2),
+                   Err(VersionConflict { current: SerialNumber(0), value: 2 }));
+        assert_eq!(cell.compare_and_set(SerialNumber(0), 3), Ok(SerialNumber(1)));
+        assert_eq!(cell.into_inner(), (3, SerialNumber(1)));
+    }
+
+    #[test]
+    fn version_wraps() {
+        let mut cell = Versioned::with_version("a", SerialNumber(u64::MAX));
+        assert_eq!(cell.compare_and_set(SerialNumber(u64::MAX), "b"), Ok(SerialNumber(0)));
+        assert!(cell.is_stale(SerialNumber(u64::MAX)));
+        assert!(!cell.is_stale(SerialNumber(0)));
+    }
+
+    #[test]
+    fn conflict() {
+        let mut cell = Versioned::new(vec![1]);
+        let conflict = cell.compare_and_set(SerialNumber(7u32), vec![2]).unwrap_err();
+        assert_eq!(conflict.to_string(), "version conflict, current version is 0");
+        assert_eq!(conflict.into_value(), vec![2]);
+    }
+}
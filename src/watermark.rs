@@ -0,0 +1,805 @@
+//! Asynchronous barriers over serial numbers.
+//!
+//! Requires the `async` feature.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use SerialNumber;
+use uint::Uint;
+
+/// A watermark that only moves forward and that tasks can wait on.
+///
+/// A producer `advance`s the watermark and every task waiting for a serial
+/// number at or below the new watermark (in serial number order) is woken.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// use sna::SerialNumber;
+/// use sna::watermark::Watermark;
+///
+/// let watermark = Watermark::new(SerialNumber(250u8));
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// let mut waiting = pin!(watermark.wait_until(SerialNumber(2u8)));
+/// assert_eq!(waiting.as_mut().poll(&mut cx), Poll::Pending);
+///
+/// assert!(watermark.advance(SerialNumber(3u8)));
+/// assert_eq!(waiting.as_mut().poll(&mut cx), Poll::Ready(()));
+/// ```
+#[derive(Debug)]
+pub struct Watermark<T> {
+    inner: Mutex<Inner<T>>,
+}
+
+#[derive(Debug)]
+struct Inner<T> {
+    current: SerialNumber<T>,
+    next_id: u64,
+    waiters: Vec<Waiter<T>>,
+}
+
+#[derive(Debug)]
+struct Waiter<T> {
+    /// The id of the future waiting, or `None` if registered by a task
+    /// polling directly.
+    id: Option<u64>,
+    target: SerialNumber<T>,
+    waker: Waker,
+}
+
+impl<T: Uint> Watermark<T> where SerialNumber<T>: PartialOrd {
+    /// Create a watermark at `initial`.
+    #[inline]
+    pub fn new(initial: SerialNumber<T>) -> Self {
+        Watermark {
+            inner: Mutex::new(Inner {
+                current: initial,
+                next_id: 0,
+                waiters: Vec::new(),
+            }),
+        }
+    }
+
+    /// Return the current watermark.
+    #[inline]
+    pub fn current(&self) -> SerialNumber<T> {
+        self.lock().current
+    }
+
+    /// Move the watermark forward to `serial` and wake all tasks waiting
+    /// for a serial number that has now been reached.
+    ///
+    /// Returns `false` and leaves the watermark unchanged if `serial` does
+    /// not follow the current watermark.
+    pub fn advance(&self, serial: SerialNumber<T>) -> bool {
+        let mut inner = self.lock();
+        if serial.partial_cmp(&inner.current) != Some(Ordering::Greater) {
+            return false;
+        }
+        inner.current = serial;
+        let mut index = 0;
+        while index < inner.waiters.len() {
+            if reached(inner.waiters[index].target, serial) {
+                inner.waiters.swap_remove(index).waker.wake();
+            } else {
+                index += 1;
+            }
+        }
+        true
+    }
+
+    /// Return a future that resolves once the watermark is at or above
+    /// `target`.
+    ///
+    /// Targets exactly half of the serial number space ahead of the
+    /// watermark are only reached once the watermark moves closer.
+    #[inline]
+    pub fn wait_until(&self, target: SerialNumber<T>) -> WaitUntil<'_, T> {
+        WaitUntil {
+            watermark: self,
+            target,
+            id: None,
+        }
+    }
+
+    /// Return whether `target` has been reached and otherwise register the
+    /// waker of `cx` as the waiter `id` to be woken once it has.
+    ///
+    /// Updates the target and waker if the waiter is still registered and
+    /// deregisters it once the target has been reached.
+    fn poll_waiter(&self, target: SerialNumber<T>, id: &mut Option<u64>, cx: &mut Context) -> Poll<()> {
+        let mut inner = self.lock();
+        if reached(target, inner.current) {
+            if let Some(id) = id.take() {
+                inner.waiters.retain(|waiter| waiter.id != Some(id));
+            }
+            return Poll::Ready(());
+        }
+        let registered = id.and_then(|id| inner.waiters.iter_mut().find(|waiter| waiter.id == Some(id)));
+        match registered {
+            Some(waiter) => {
+                waiter.target = target;
+                waiter.waker.clone_from(cx.waker());
+            }
+            None => {
+                // The waiter may have been woken for a previous target
+                let waiter_id = match *id {
+                    Some(id) => id,
+                    None => {
+                        inner.next_id += 1;
+                        inner.next_id - 1
+                    }
+                };
+                inner.waiters.push(Waiter {
+                    id: Some(waiter_id),
+                    target,
+                    waker: cx.waker().clone(),
+                });
+                *id = Some(waiter_id);
+            }
+        }
+        Poll::Pending
+    }
+
+    /// Return whether `target` has been reached and otherwise register the
+    /// waker of `cx` to be woken once it has.
+    ///
+    /// Each task has at most one such waiter, which is replaced on every
+    /// poll and removed once the target has been reached.
+    fn poll_reached(&self, target: SerialNumber<T>, cx: &mut Context) -> Poll<()> {
+        let mut inner = self.lock();
+        let registered = inner.waiters.iter()
+            .position(|waiter| waiter.id.is_none() && waiter.waker.will_wake(cx.waker()));
+        if reached(target, inner.current) {
+            if let Some(index) = registered {
+                inner.waiters.swap_remove(index);
+            }
+            return Poll::Ready(());
+        }
+        match registered {
+            Some(index) => inner.waiters[index].target = target,
+            None => inner.waiters.push(Waiter {
+                id: None,
+                target,
+                waker: cx.waker().clone(),
+            }),
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Watermark<T> {
+    /// Remove the waiter `id`, e.g. when its future is dropped.
+    fn deregister(&self, id: u64) {
+        self.lock().waiters.retain(|waiter| waiter.id != Some(id));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        self.inner.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+fn reached<T>(target: SerialNumber<T>, current: SerialNumber<T>) -> bool
+    where SerialNumber<T>: PartialOrd
+{
+    match target.partial_cmp(&current) {
+        Some(Ordering::Less) | Some(Ordering::Equal) => true,
+        Some(Ordering::Greater) | None => false,
+    }
+}
+
+/// Notifies tasks once serial numbers have been cumulatively acknowledged
+/// (sender side).
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// use sna::SerialNumber;
+/// use sna::watermark::Acknowledgements;
+///
+/// let acknowledgements = Acknowledgements::new(SerialNumber(99u32));
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// let mut flushed = pin!(acknowledgements.wait_for(SerialNumber(105u32)));
+/// assert_eq!(flushed.as_mut().poll(&mut cx), Poll::Pending);
+///
+/// acknowledgements.acknowledge(SerialNumber(110u32));
+/// assert_eq!(flushed.as_mut().poll(&mut cx), Poll::Ready(()));
+/// ```
+#[derive(Debug)]
+pub struct Acknowledgements<T> {
+    watermark: Watermark<T>,
+}
+
+impl<T: Uint> Acknowledgements<T> where SerialNumber<T>: PartialOrd {
+    /// Create a tracker where `acknowledged` is the last serial number that
+    /// has already been acknowledged.
+    #[inline]
+    pub fn new(acknowledged: SerialNumber<T>) -> Self {
+        Acknowledgements {
+            watermark: Watermark::new(acknowledged),
+        }
+    }
+
+    /// Return the last cumulatively acknowledged serial number.
+    #[inline]
+    pub fn acknowledged(&self) -> SerialNumber<T> {
+        self.watermark.current()
+    }
+
+    /// Acknowledge all serial numbers up to and including `serial`.
+    ///
+    /// Returns `false` if `serial` does not follow the last acknowledged
+    /// serial number (e.g. a duplicate or old acknowledgement).
+    #[inline]
+    pub fn acknowledge(&self, serial: SerialNumber<T>) -> bool {
+        self.watermark.advance(serial)
+    }
+
+    /// Return a future that resolves once `serial` has been acknowledged.
+    #[inline]
+    pub fn wait_for(&self, serial: SerialNumber<T>) -> WaitUntil<'_, T> {
+        self.watermark.wait_until(serial)
+    }
+}
+
+/// Notifies tasks once serial numbers have been delivered in order
+/// (receiver side).
+///
+/// Serial numbers may be delivered out of order. They are held back until
+/// all preceding serial numbers have been delivered as well, but only if
+/// they are less than `window` ahead of the next expected serial number, so
+/// at most `window - 1` serial numbers are held back.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// use sna::SerialNumber;
+/// use sna::watermark::InOrderNotify;
+///
+/// let delivered = InOrderNotify::new(SerialNumber(65535u16), 64);
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// let mut waiting = pin!(delivered.wait_for(SerialNumber(0u16)));
+/// assert!(delivered.deliver(SerialNumber(0u16)));
+/// assert_eq!(waiting.as_mut().poll(&mut cx), Poll::Pending);
+///
+/// assert!(delivered.deliver(SerialNumber(65535u16)));
+/// assert_eq!(delivered.delivered(), SerialNumber(0u16));
+/// assert_eq!(waiting.as_mut().poll(&mut cx), Poll::Ready(()));
+/// ```
+#[derive(Debug)]
+pub struct InOrderNotify<T> {
+    watermark: Watermark<T>,
+    window: T,
+    pending: Mutex<HashSet<T>>,
+}
+
+impl<T: Uint> InOrderNotify<T> where SerialNumber<T>: PartialOrd {
+    /// Create a tracker where `next` is the first serial number expected to
+    /// be delivered and serial numbers less than `window` ahead of the next
+    /// expected one may be delivered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` exceeds half of the serial number space, as the
+    /// serial numbers held back could not be ordered any more.
+    #[inline]
+    pub fn new(next: SerialNumber<T>, window: T) -> Self {
+        assert!(window <= T::HALF, "window exceeds half of the serial number space");
+        InOrderNotify {
+            watermark: Watermark::new(SerialNumber(next.0.wrapping_sub(T::ONE))),
+            window,
+            pending: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Return the last serial number up to which all serial numbers have
+    /// been delivered in order.
+    ///
+    /// Initially, this is the serial number preceding the first expected
+    /// one.
+    #[inline]
+    pub fn delivered(&self) -> SerialNumber<T> {
+        self.watermark.current()
+    }
+
+    /// Mark `serial` as delivered and wake all tasks waiting for serial
+    /// numbers that are now delivered in order.
+    ///
+    /// Returns `false` if `serial` has already been delivered, does not
+    /// follow the serial numbers delivered in order or is `window` or more
+    /// ahead of the next expected serial number.
+    pub fn deliver(&self, serial: SerialNumber<T>) -> bool {
+        let mut pending = self.lock();
+        let current = self.watermark.current();
+        if serial.partial_cmp(&current) != Some(Ordering::Greater)
+            || serial.0.wrapping_sub(current.0) > self.window
+            || !pending.insert(serial.0)
+        {
+            return false;
+        }
+        let mut next = current.0.wrapping_add(T::ONE);
+        while pending.remove(&next) {
+            next = next.wrapping_add(T::ONE);
+        }
+        let last = SerialNumber(next.wrapping_sub(T::ONE));
+        if last != current {
+            self.watermark.advance(last);
+        }
+        true
+    }
+
+    /// Return a future that resolves once all serial numbers up to and
+    /// including `serial` have been delivered.
+    #[inline]
+    pub fn wait_for(&self, serial: SerialNumber<T>) -> WaitUntil<'_, T> {
+        self.watermark.wait_until(serial)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<T>> {
+        self.pending.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// Limits the amount of serial numbers in flight (sent but not yet
+/// cumulatively acknowledged) to a window (sender side).
+///
+/// Producers `acquire` the next serial number and are suspended while the
+/// window is full, so they can not overrun the sequence window.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// use sna::SerialNumber;
+/// use sna::watermark::InFlight;
+///
+/// let in_flight = InFlight::new(SerialNumber(255u8), 2);
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// assert_eq!(in_flight.try_acquire(), Some(SerialNumber(255u8)));
+/// assert_eq!(in_flight.try_acquire(), Some(SerialNumber(0u8)));
+/// assert_eq!(in_flight.poll_ready(&mut cx), Poll::Pending);
+///
+/// let mut acquire = pin!(in_flight.acquire());
+/// assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Pending);
+///
+/// assert!(in_flight.acknowledge(SerialNumber(255u8)));
+/// assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Ready(SerialNumber(1u8)));
+/// ```
+#[derive(Debug)]
+pub struct InFlight<T> {
+    next: Mutex<SerialNumber<T>>,
+    window: T,
+    acknowledged: Watermark<T>,
+}
+
+impl<T: Uint> InFlight<T> where SerialNumber<T>: PartialOrd {
+    /// Create a tracker where `next` is the first serial number to be
+    /// allocated and at most `window` serial numbers may be in flight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` exceeds half of the serial number space, as the
+    /// serial numbers in flight could not be ordered any more.
+    #[inline]
+    pub fn new(next: SerialNumber<T>, window: T) -> Self {
+        assert!(window <= T::HALF, "window exceeds half of the serial number space");
+        InFlight {
+            next: Mutex::new(next),
+            window,
+            acknowledged: Watermark::new(SerialNumber(next.0.wrapping_sub(T::ONE))),
+        }
+    }
+
+    /// Return the last cumulatively acknowledged serial number.
+    #[inline]
+    pub fn acknowledged(&self) -> SerialNumber<T> {
+        self.acknowledged.current()
+    }
+
+    /// Return the amount of serial numbers in flight.
+    pub fn in_flight(&self) -> T {
+        let next = self.lock();
+        next.0.wrapping_sub(self.acknowledged.current().0).wrapping_sub(T::ONE)
+    }
+
+    /// Acknowledge all serial numbers up to and including `serial`,
+    /// opening the window.
+    ///
+    /// Returns `false` if `serial` has not been allocated, yet, or does not
+    /// follow the last acknowledged serial number.
+    pub fn acknowledge(&self, serial: SerialNumber<T>) -> bool {
+        let next = self.lock();
+        serial.partial_cmp(&next) == Some(Ordering::Less) && self.acknowledged.advance(serial)
+    }
+
+    /// Return whether there is room for another serial number within the
+    /// window and otherwise register the waker of `cx` to be woken once
+    /// there is.
+    ///
+    /// Each task is registered at most once, no matter how often it polls.
+    pub fn poll_ready(&self, cx: &mut Context) -> Poll<()> {
+        let next = self.lock();
+        self.poll_room(*next, cx)
+    }
+
+    /// Allocate the next serial number if there is room within the window
+    /// and otherwise register the waker of `cx` to be woken once there is.
+    pub fn poll_acquire(&self, cx: &mut Context) -> Poll<SerialNumber<T>> {
+        let mut next = self.lock();
+        match self.poll_room(*next, cx) {
+            Poll::Ready(()) => {
+                let serial = *next;
+                *next = SerialNumber(serial.0.wrapping_add(T::ONE));
+                Poll::Ready(serial)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Allocate the next serial number if there is room within the window.
+    pub fn try_acquire(&self) -> Option<SerialNumber<T>> {
+        let mut next = self.lock();
+        let target = SerialNumber(next.0.wrapping_sub(self.window));
+        if self.window != T::ZERO && reached(target, self.acknowledged.current()) {
+            let serial = *next;
+            *next = SerialNumber(serial.0.wrapping_add(T::ONE));
+            Some(serial)
+        } else {
+            None
+        }
+    }
+
+    /// Return a future that allocates the next serial number once there is
+    /// room within the window.
+    #[inline]
+    pub fn acquire(&self) -> Acquire<'_, T> {
+        Acquire { in_flight: self, id: None }
+    }
+
+    fn poll_room(&self, next: SerialNumber<T>, cx: &mut Context) -> Poll<()> {
+        if self.window == T::ZERO {
+            return Poll::Pending;
+        }
+        self.acknowledged.poll_reached(SerialNumber(next.0.wrapping_sub(self.window)), cx)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SerialNumber<T>> {
+        self.next.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// The future returned by `InFlight::acquire`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Acquire<'a, T: 'a> {
+    in_flight: &'a InFlight<T>,
+    id: Option<u64>,
+}
+
+impl<'a, T: Uint> Future for Acquire<'a, T> where SerialNumber<T>: PartialOrd {
+    type Output = SerialNumber<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<SerialNumber<T>> {
+        let this = &mut *self;
+        let in_flight = this.in_flight;
+        let mut next = in_flight.lock();
+        if in_flight.window == T::ZERO {
+            return Poll::Pending;
+        }
+        let target = SerialNumber(next.0.wrapping_sub(in_flight.window));
+        match in_flight.acknowledged.poll_waiter(target, &mut this.id, cx) {
+            Poll::Ready(()) => {
+                let serial = *next;
+                *next = SerialNumber(serial.0.wrapping_add(T::ONE));
+                Poll::Ready(serial)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<'a, T> Drop for Acquire<'a, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.in_flight.acknowledged.deregister(id);
+        }
+    }
+}
+
+/// The future returned by `Watermark::wait_until`.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct WaitUntil<'a, T: 'a> {
+    watermark: &'a Watermark<T>,
+    target: SerialNumber<T>,
+    id: Option<u64>,
+}
+
+// The future is never pin-projected.
+impl<'a, T> Unpin for WaitUntil<'a, T> {}
+
+impl<'a, T: Uint> Future for WaitUntil<'a, T> where SerialNumber<T>: PartialOrd {
+    type Output = ();
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = &mut *self;
+        this.watermark.poll_waiter(this.target, &mut this.id, cx)
+    }
+}
+
+impl<'a, T> Drop for WaitUntil<'a, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.watermark.deregister(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn advance() {
+        let watermark = Watermark::new(SerialNumber(10u16));
+        assert!(!watermark.advance(SerialNumber(10)));
+        assert!(!watermark.advance(SerialNumber(9)));
+        assert!(!watermark.advance(SerialNumber(10 + (1 << 15))));
+        assert!(watermark.advance(SerialNumber(11)));
+        assert_eq!(watermark.current(), SerialNumber(11));
+    }
+
+    #[test]
+    fn reached_immediately() {
+        let watermark = Watermark::new(SerialNumber(0u32));
+        block_on(watermark.wait_until(SerialNumber(0)));
+        block_on(watermark.wait_until(SerialNumber(u32::MAX)));
+    }
+
+    #[test]
+    fn wakes_reached_waiters_only() {
+        let watermark = Watermark::new(SerialNumber(0u8));
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut first = pin!(watermark.wait_until(SerialNumber(5)));
+        let mut second = pin!(watermark.wait_until(SerialNumber(10)));
+        assert_eq!(first.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(second.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(first.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(watermark.lock().waiters.len(), 2);
+
+        watermark.advance(SerialNumber(7));
+        assert_eq!(watermark.lock().waiters.len(), 1);
+        assert_eq!(first.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(second.as_mut().poll(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn drop_deregisters() {
+        let watermark = Watermark::new(SerialNumber(0u64));
+        {
+            let mut cx = Context::from_waker(Waker::noop());
+            let mut waiting = pin!(watermark.wait_until(SerialNumber(1)));
+            assert_eq!(waiting.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert!(watermark.lock().waiters.is_empty());
+    }
+
+    #[test]
+    fn acknowledgements() {
+        let acknowledgements = Acknowledgements::new(SerialNumber(u32::MAX));
+        assert!(!acknowledgements.acknowledge(SerialNumber(u32::MAX)));
+        assert!(acknowledgements.acknowledge(SerialNumber(1)));
+        assert!(!acknowledgements.acknowledge(SerialNumber(0)));
+        assert_eq!(acknowledgements.acknowledged(), SerialNumber(1));
+        block_on(acknowledgements.wait_for(SerialNumber(0)));
+    }
+
+    #[test]
+    fn in_order() {
+        let delivered = InOrderNotify::new(SerialNumber(254u8), 4);
+        assert_eq!(delivered.delivered(), SerialNumber(253));
+        assert!(delivered.deliver(SerialNumber(0)));
+        assert!(delivered.deliver(SerialNumber(255)));
+        assert!(!delivered.deliver(SerialNumber(0)));
+        assert_eq!(delivered.delivered(), SerialNumber(253));
+        assert!(delivered.deliver(SerialNumber(254)));
+        assert_eq!(delivered.delivered(), SerialNumber(0));
+        assert!(!delivered.deliver(SerialNumber(255)));
+        assert!(delivered.lock().is_empty());
+
+        // At most 3 held back
+        assert!(!delivered.deliver(SerialNumber(5)));
+        assert!(delivered.deliver(SerialNumber(4)));
+        assert!(delivered.deliver(SerialNumber(3)));
+        assert!(delivered.deliver(SerialNumber(2)));
+        assert_eq!(delivered.lock().len(), 3);
+        assert!(delivered.deliver(SerialNumber(1)));
+        assert_eq!(delivered.delivered(), SerialNumber(4));
+    }
+
+    #[test]
+    fn in_order_cross_thread() {
+        let delivered = Arc::new(InOrderNotify::new(SerialNumber(0u16), 100));
+        let receiver = {
+            let delivered = delivered.clone();
+            thread::spawn(move || {
+                for value in (0..100u16).rev() {
+                    delivered.deliver(SerialNumber(value));
+                }
+            })
+        };
+        block_on(delivered.wait_for(SerialNumber(99)));
+        receiver.join().unwrap();
+    }
+
+    #[test]
+    fn in_flight() {
+        let in_flight = InFlight::new(SerialNumber(0u16), 3);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(0)));
+        assert_eq!(in_flight.poll_acquire(&mut cx), Poll::Ready(SerialNumber(1)));
+        assert_eq!(in_flight.poll_ready(&mut cx), Poll::Ready(()));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(2)));
+        assert_eq!(in_flight.in_flight(), 3);
+        assert_eq!(in_flight.try_acquire(), None);
+
+        assert_eq!(in_flight.poll_ready(&mut cx), Poll::Pending);
+        assert_eq!(in_flight.poll_ready(&mut cx), Poll::Pending);
+        assert_eq!(in_flight.acknowledged.lock().waiters.len(), 1);
+
+        assert!(!in_flight.acknowledge(SerialNumber(3)));
+        assert!(in_flight.acknowledge(SerialNumber(1)));
+        assert!(in_flight.acknowledged.lock().waiters.is_empty());
+        assert_eq!(in_flight.in_flight(), 1);
+        assert_eq!(in_flight.acknowledged(), SerialNumber(1));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(3)));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(4)));
+        assert_eq!(in_flight.try_acquire(), None);
+    }
+
+    #[test]
+    fn acquire_drop_deregisters() {
+        let in_flight = InFlight::new(SerialNumber(0u8), 1);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(0)));
+        {
+            let mut acquire = pin!(in_flight.acquire());
+            assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(in_flight.acknowledged.lock().waiters.len(), 1);
+        }
+        assert!(in_flight.acknowledged.lock().waiters.is_empty());
+
+        // Woken, but another producer took the room in the meantime
+        let mut acquire = pin!(in_flight.acquire());
+        assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Pending);
+        assert!(in_flight.acknowledge(SerialNumber(0)));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(1)));
+        assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(in_flight.acknowledged.lock().waiters.len(), 1);
+        assert!(in_flight.acknowledge(SerialNumber(1)));
+        assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Ready(SerialNumber(2)));
+        assert!(in_flight.acknowledged.lock().waiters.is_empty());
+    }
+
+    #[test]
+    fn poll_ready_bounded() {
+        let in_flight = InFlight::new(SerialNumber(0u32), 2);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let other = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(0)));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(1)));
+        for _ in 0..100 {
+            let waker = waker.clone();
+            assert_eq!(in_flight.poll_ready(&mut Context::from_waker(&waker)), Poll::Pending);
+            assert_eq!(in_flight.poll_acquire(&mut Context::from_waker(&waker)), Poll::Pending);
+            assert_eq!(in_flight.poll_ready(&mut Context::from_waker(&other)), Poll::Pending);
+        }
+        assert_eq!(in_flight.acknowledged.lock().waiters.len(), 2);
+
+        // Woken and removed, so polling registers the task once again
+        assert!(in_flight.acknowledge(SerialNumber(0)));
+        assert_eq!(in_flight.try_acquire(), Some(SerialNumber(2)));
+        for _ in 0..100 {
+            assert_eq!(in_flight.poll_ready(&mut Context::from_waker(&waker)), Poll::Pending);
+        }
+        assert_eq!(in_flight.acknowledged.lock().waiters.len(), 1);
+        assert!(in_flight.acknowledge(SerialNumber(1)));
+        assert_eq!(in_flight.poll_ready(&mut Context::from_waker(&waker)), Poll::Ready(()));
+        assert!(in_flight.acknowledged.lock().waiters.is_empty());
+    }
+
+    #[test]
+    fn in_flight_empty_window() {
+        let in_flight = InFlight::new(SerialNumber(0u8), 0);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(in_flight.try_acquire(), None);
+        assert_eq!(in_flight.poll_ready(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn in_flight_cross_thread() {
+        let in_flight = Arc::new(InFlight::new(SerialNumber(u32::MAX - 10), 4));
+        let receiver = {
+            let in_flight = in_flight.clone();
+            thread::spawn(move || {
+                while in_flight.acknowledged() != SerialNumber(89) {
+                    let next = *in_flight.lock();
+                    in_flight.acknowledge(SerialNumber(next.0.wrapping_sub(1)));
+                    thread::yield_now();
+                }
+            })
+        };
+        for value in (u32::MAX - 10..=u32::MAX).chain(0..90) {
+            assert_eq!(block_on(in_flight.acquire()), SerialNumber(value));
+        }
+        receiver.join().unwrap();
+    }
+
+    #[test]
+    fn cross_thread() {
+        let watermark = Arc::new(Watermark::new(SerialNumber(u16::MAX - 2)));
+        let producer = {
+            let watermark = watermark.clone();
+            thread::spawn(move || {
+                for value in (u16::MAX - 1..=u16::MAX).chain(0..100) {
+                    watermark.advance(SerialNumber(value));
+                }
+            })
+        };
+        block_on(watermark.wait_until(SerialNumber(50)));
+        producer.join().unwrap();
+        assert_eq!(watermark.current(), SerialNumber(99));
+    }
+}
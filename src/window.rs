@@ -0,0 +1,325 @@
+//! Flow-control windows over wrapping byte offsets as used by TCP and QUIC
+//! streams, and the send sequence space of TCP-like transports.
+
+use std::cmp::Ordering;
+
+use {SerialNumber, SerialRange};
+use uint::Uint;
+
+/// A flow-control window in bytes (sender side).
+///
+/// The sender may send bytes up to (but excluding) the *limit*, which the
+/// receiver advertises as an offset and a window size relative to that
+/// offset. Offsets are serial numbers, so the window keeps working after the
+/// offset space wrapped, e.g. for 32-bit TCP sequence numbers.
+///
+/// Updates that would move the limit backwards (i.e. shrink the window) are
+/// ignored, as recommended by
+/// [chapter 4.2.2.16 of RFC 1122](https://tools.ietf.org/html/rfc1122#page-97).
+///
+/// # Examples
+///
+/// ```
+/// use sna::SerialNumber;
+/// use sna::window::ByteWindow;
+///
+/// let mut window = ByteWindow::new(SerialNumber(u32::MAX - 99), 1000);
+///
+/// assert_eq!(window.consume(600), Some(SerialNumber(u32::MAX - 99)));
+/// assert!(!window.can_send(600));
+/// assert_eq!(window.available(), 400);
+///
+/// assert!(window.advertise(SerialNumber(500u32), 1000));
+/// assert_eq!(window.limit(), SerialNumber(1500u32));
+/// assert!(window.can_send(1000));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ByteWindow<T = u32> {
+    offset: SerialNumber<T>,
+    limit: SerialNumber<T>,
+}
+
+impl<T: Uint> ByteWindow<T> where SerialNumber<T>: PartialOrd {
+    /// Create a window where `offset` is the next byte to be sent and
+    /// `window` bytes may be sent from there on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` exceeds half of the offset space, as offsets
+    /// within the window could not be ordered any more.
+    #[inline]
+    pub fn new(offset: SerialNumber<T>, window: T) -> Self {
+        assert!(window <= T::HALF, "window exceeds half of the offset space");
+        ByteWindow { offset, limit: SerialNumber(offset.0.wrapping_add(window)) }
+    }
+
+    /// Return the offset of the next byte to be sent.
+    #[inline]
+    pub fn offset(&self) -> SerialNumber<T> {
+        self.offset
+    }
+
+    /// Return the offset up to which (exclusive) bytes may be sent.
+    #[inline]
+    pub fn limit(&self) -> SerialNumber<T> {
+        self.limit
+    }
+
+    /// Return the amount of bytes that may currently be sent.
+    #[inline]
+    pub fn available(&self) -> T {
+        self.limit.0.wrapping_sub(self.offset.0)
+    }
+
+    /// Return whether `n` bytes may currently be sent.
+    #[inline]
+    pub fn can_send(&self, n: T) -> bool {
+        n <= self.available()
+    }
+
+    /// Consume `n` bytes of the window.
+    ///
+    /// Returns the offset of the first consumed byte or `None` if less than
+    /// `n` bytes are available, in which case nothing is consumed.
+    pub fn consume(&mut self, n: T) -> Option<SerialNumber<T>> {
+        if self.can_send(n) {
+            let offset = self.offset;
+            self.offset = SerialNumber(offset.0.wrapping_add(n));
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
+    /// Apply a window update from the receiver that allows `window` bytes
+    /// to be sent starting at `offset` (e.g. the acknowledgement number and
+    /// window of a TCP segment or a QUIC `MAX_STREAM_DATA` frame with
+    /// `window` relative to `offset`).
+    ///
+    /// Returns `false` and ignores the update if it would not move the
+    /// limit forward or if the new limit would be too far ahead of the
+    /// offset of the next byte to be sent.
+    pub fn advertise(&mut self, offset: SerialNumber<T>, window: T) -> bool {
+        let limit = SerialNumber(offset.0.wrapping_add(window));
+        let ahead = limit.0.wrapping_sub(self.offset.0);
+        if window <= T::HALF && ahead <= T::HALF
+            && limit.partial_cmp(&self.limit) == Some(Ordering::Greater) {
+            self.limit = limit;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The outcome of applying an acknowledgement to a `SendWindow`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ack<T> {
+    /// The acknowledgement covers the given range of serial numbers that
+    /// had not been acknowledged before.
+    New(SerialRange<T>),
+    /// The acknowledgement equals the oldest unacknowledged serial number,
+    /// e.g. a window update or a duplicate acknowledgement.
+    Duplicate,
+    /// The acknowledgement precedes the oldest unacknowledged serial number
+    /// and has been ignored.
+    Old,
+    /// The acknowledgement covers serial numbers that have not been sent,
+    /// yet, and has been ignored.
+    Unsent,
+}
+
+/// The send sequence space of a TCP-like transport (sender side), see
+/// [chapter 3.3.1 of RFC 9293](https://www.rfc-editor.org/rfc/rfc9293#section-3.3.1).
+///
+/// Tracks the oldest unacknowledged serial number (`SND.UNA`), the next
+/// serial number to be sent (`SND.NXT`) and the send window (`SND.WND`)
+/// relative to `SND.UNA`. An acknowledgement is acceptable if
+/// `SND.UNA < ACK <= SND.NXT`.
+///
+/// # Examples
+///
+/// ```
+/// use sna::{SerialNumber, SerialRange};
+/// use sna::window::{Ack, SendWindow};
+///
+/// let iss = SerialNumber(u32::MAX - 99);
+/// let mut window = SendWindow::new(iss, 1000);
+///
+/// let sent = window.send(300).unwrap();
+/// assert_eq!(sent, SerialRange::new(iss, SerialNumber(200)));
+/// assert_eq!(window.usable(), 700);
+///
+/// assert_eq!(window.acknowledge(SerialNumber(100)), Ack::New(SerialRange::new(iss, SerialNumber(100))));
+/// assert_eq!(window.acknowledge(SerialNumber(100)), Ack::Duplicate);
+/// assert_eq!(window.acknowledge(SerialNumber(50)), Ack::Old);
+/// assert_eq!(window.acknowledge(SerialNumber(201)), Ack::Unsent);
+/// assert_eq!(window.in_flight(), 100);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct SendWindow<T = u32> {
+    una: SerialNumber<T>,
+    nxt: SerialNumber<T>,
+    window: T,
+}
+
+impl<T: Uint> SendWindow<T> where SerialNumber<T>: PartialOrd {
+    /// Create a window where `iss` is the initial serial number and
+    /// `window` serial numbers may be sent from there on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` exceeds half of the serial number space.
+    #[inline]
+    pub fn new(iss: SerialNumber<T>, window: T) -> Self {
+        assert!(window <= T::HALF, "window exceeds half of the serial number space");
+        SendWindow { una: iss, nxt: iss, window }
+    }
+
+    /// Return the oldest unacknowledged serial number (`SND.UNA`).
+    #[inline]
+    pub fn una(&self) -> SerialNumber<T> {
+        self.una
+    }
+
+    /// Return the next serial number to be sent (`SND.NXT`).
+    #[inline]
+    pub fn nxt(&self) -> SerialNumber<T> {
+        self.nxt
+    }
+
+    /// Return the send window (`SND.WND`).
+    #[inline]
+    pub fn window(&self) -> T {
+        self.window
+    }
+
+    /// Return the amount of serial numbers sent but not acknowledged, yet.
+    #[inline]
+    pub fn in_flight(&self) -> T {
+        self.nxt.0.wrapping_sub(self.una.0)
+    }
+
+    /// Return the amount of serial numbers that may currently be sent.
+    ///
+    /// This is zero if the window shrank below the serial numbers in
+    /// flight.
+    #[inline]
+    pub fn usable(&self) -> T {
+        let in_flight = self.in_flight();
+        if in_flight < self.window { self.window.wrapping_sub(in_flight) } else { T::ZERO }
+    }
+
+    /// Send `n` serial numbers, returning their range.
+    ///
+    /// Returns `None` if less than `n` serial numbers are usable, in which
+    /// case nothing is sent.
+    pub fn send(&mut self, n: T) -> Option<SerialRange<T>> {
+        if n <= self.usable() {
+            let start = self.nxt;
+            self.nxt = SerialNumber(start.0.wrapping_add(n));
+            Some(SerialRange::new(start, self.nxt))
+        } else {
+            None
+        }
+    }
+
+    /// Apply the acknowledgement `ack`, i.e. all serial numbers preceding
+    /// it have been received.
+    pub fn acknowledge(&mut self, ack: SerialNumber<T>) -> Ack<T> {
+        if ack == self.una {
+            return Ack::Duplicate;
+        }
+        if ack.0.wrapping_sub(self.una.0) <= self.in_flight() {
+            let acknowledged = SerialRange::new(self.una, ack);
+            self.una = ack;
+            Ack::New(acknowledged)
+        } else if ack.partial_cmp(&self.una) == Some(Ordering::Less) {
+            Ack::Old
+        } else {
+            Ack::Unsent
+        }
+    }
+
+    /// Apply a window update from the receiver relative to the oldest
+    /// unacknowledged serial number.
+    ///
+    /// Returns `false` and ignores the update if `window` exceeds half of
+    /// the serial number space.
+    #[inline]
+    pub fn set_window(&mut self, window: T) -> bool {
+        if window <= T::HALF {
+            self.window = window;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consume() {
+        let mut window = ByteWindow::new(SerialNumber(250u8), 10);
+        assert_eq!(window.consume(11), None);
+        assert_eq!(window.consume(6), Some(SerialNumber(250)));
+        assert_eq!(window.offset(), SerialNumber(0));
+        assert_eq!(window.consume(4), Some(SerialNumber(0)));
+        assert_eq!(window.available(), 0);
+        assert!(window.can_send(0));
+        assert!(!window.can_send(1));
+    }
+
+    #[test]
+    fn advertise() {
+        let mut window = ByteWindow::new(SerialNumber(250u8), 10);
+        assert_eq!(window.consume(8), Some(SerialNumber(250)));
+
+        // Shrinking and stale updates are ignored
+        assert!(!window.advertise(SerialNumber(255), 0));
+        assert!(!window.advertise(SerialNumber(240), 20));
+        assert_eq!(window.limit(), SerialNumber(4));
+
+        assert!(window.advertise(SerialNumber(2), 100));
+        assert_eq!(window.available(), 100);
+
+        // The limit may not move more than half of the space ahead
+        assert!(!window.advertise(SerialNumber(2), 129));
+        assert!(!window.advertise(SerialNumber(100), 100));
+        assert_eq!(window.limit(), SerialNumber(102));
+    }
+
+    #[test]
+    fn send_window() {
+        let mut window = SendWindow::new(SerialNumber(250u8), 20);
+        assert_eq!(window.send(21), None);
+        assert_eq!(window.send(10), Some(SerialRange::new(SerialNumber(250), SerialNumber(4))));
+        assert_eq!(window.send(10).map(|range| range.len()), Some(10));
+        assert_eq!(window.usable(), 0);
+
+        // Acknowledge across the wrap
+        assert_eq!(window.acknowledge(SerialNumber(2)),
+                   Ack::New(SerialRange::new(SerialNumber(250), SerialNumber(2))));
+        assert_eq!(window.acknowledge(SerialNumber(255)), Ack::Old);
+        assert_eq!(window.acknowledge(SerialNumber(15)), Ack::Unsent);
+        assert_eq!(window.una(), SerialNumber(2));
+        assert_eq!(window.in_flight(), 12);
+
+        // Shrinking the window below the serial numbers in flight
+        assert!(window.set_window(5));
+        assert_eq!(window.usable(), 0);
+        assert!(!window.set_window(129));
+        assert_eq!(window.acknowledge(window.nxt()),
+                   Ack::New(SerialRange::new(SerialNumber(2), SerialNumber(14))));
+        assert_eq!(window.usable(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn window_too_large() {
+        ByteWindow::new(SerialNumber(0u16), 32769);
+    }
+}
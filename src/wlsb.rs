@@ -0,0 +1,327 @@
+//! Window-based Least Significant Bits (W-LSB) encoding as used by
+//! RObust Header Compression, see
+//! [chapter 4.5 of RFC 3095](https://tools.ietf.org/html/rfc3095#section-4.5).
+//!
+//! A compressor only transmits the `k` least significant bits of a value.
+//! The decompressor reconstructs the full value by picking the only value
+//! inside the *interpretation interval*
+//!
+//! ```text
+//! f(v_ref, k) = [v_ref - p, v_ref + (2^k - 1) - p]
+//! ```
+//!
+//! whose `k` least significant bits match. All arithmetic wraps modulo the
+//! largest representable number of the underlying type, so reconstruction
+//! works across the wrap point of the serial number space.
+
+#[cfg(feature = "alloc")]
+use std::cmp::Ordering;
+
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+
+use SerialNumber;
+use uint::Uint;
+
+/// The shift parameter `p` of the interpretation interval.
+///
+/// A positive `p` allows values *below* the reference to be decoded, a
+/// negative `p` moves the interval *above* the reference (e.g. `-1` for
+/// strictly increasing values).
+#[derive(Clone, Copy, Debug)]
+pub enum Shift {
+    /// A constant `p` that does not depend on the number of bits `k`.
+    Fixed(i64),
+    /// A `p` computed from the number of bits `k`.
+    Variable(fn(u32) -> i64),
+}
+
+impl Shift {
+    /// Return `p` for `k` least significant bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sna::wlsb::Shift;
+    ///
+    /// assert_eq!(Shift::Fixed(-1).offset(4), -1);
+    /// assert_eq!(Shift::Variable(|k| (1 << k) / 4 - 1).offset(4), 3);
+    /// ```
+    #[inline]
+    pub fn offset(&self, k: u32) -> i64 {
+        match *self {
+            Shift::Fixed(p) => p,
+            Shift::Variable(f) => f(k),
+        }
+    }
+}
+
+/// The compressor side of W-LSB encoding.
+///
+/// Keeps a sliding window of reference values that the decompressor may
+/// currently be using and picks the number of bits `k` so that the value
+/// can be decoded against *any* of them.
+///
+/// Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct WlsbEncoder<T> {
+    shift: Shift,
+    capacity: usize,
+    window: VecDeque<SerialNumber<T>>,
+}
+
+/// The decompressor side of W-LSB encoding.
+///
+/// Reconstructs values relative to the last correctly decoded reference.
+#[derive(Clone, Copy, Debug)]
+pub struct WlsbDecoder<T> {
+    shift: Shift,
+    reference: SerialNumber<T>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Uint> WlsbEncoder<T> {
+    /// Create an encoder that keeps at most `capacity` reference
+    /// values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    #[inline]
+    pub fn new(capacity: usize, shift: Shift) -> Self {
+        assert!(capacity > 0, "window capacity must be greater than zero");
+        WlsbEncoder {
+            shift,
+            capacity,
+            window: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Add a candidate reference value, evicting the oldest one if
+    /// the window is full.
+    #[inline]
+    pub fn push(&mut self, reference: SerialNumber<T>) {
+        if self.window.len() == self.capacity {
+            self.window.pop_front();
+        }
+        self.window.push_back(reference);
+    }
+
+    /// Remove all reference values that precede `reference`, e.g.
+    /// once the decompressor acknowledged it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use sna::wlsb::{Shift, WlsbEncoder};
+    ///
+    /// let mut encoder = WlsbEncoder::new(4, Shift::Fixed(0));
+    /// encoder.push(SerialNumber(10u8));
+    /// encoder.push(SerialNumber(20u8));
+    /// assert_eq!(encoder.bits_required(SerialNumber(21u8)), 4);
+    ///
+    /// encoder.acknowledge(SerialNumber(20u8));
+    /// assert_eq!(encoder.bits_required(SerialNumber(21u8)), 1);
+    /// ```
+    pub fn acknowledge(&mut self, reference: SerialNumber<T>)
+        where SerialNumber<T>: PartialOrd
+    {
+        self.window.retain(|candidate| candidate.partial_cmp(&reference) != Some(Ordering::Less));
+    }
+
+    /// Return the current reference values, oldest first.
+    #[inline]
+    pub fn references(&self) -> &VecDeque<SerialNumber<T>> {
+        &self.window
+    }
+
+    /// Return the minimal number of least significant bits `k`
+    /// needed so that `value` can be decoded against every
+    /// reference in the window.
+    ///
+    /// If the window is empty, all bits are required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use sna::wlsb::{Shift, WlsbEncoder};
+    ///
+    /// let mut encoder = WlsbEncoder::new(1, Shift::Fixed(-1));
+    /// assert_eq!(encoder.bits_required(SerialNumber(1u16)), 16);
+    ///
+    /// encoder.push(SerialNumber(65535u16));
+    /// assert_eq!(encoder.bits_required(SerialNumber(0u16)), 0);
+    /// assert_eq!(encoder.bits_required(SerialNumber(2u16)), 2);
+    /// ```
+    pub fn bits_required(&self, value: SerialNumber<T>) -> u32 {
+        if self.window.is_empty() {
+            return T::BITS;
+        }
+        self.window.iter()
+            .map(|reference| {
+                (0..T::BITS)
+                    .find(|&k| {
+                        let lower = reference.0.wrapping_sub(T::from_i64(self.shift.offset(k)));
+                        value.0.wrapping_sub(lower) <= T::mask(k)
+                    })
+                    .unwrap_or(T::BITS)
+            })
+            .max()
+            .unwrap_or(T::BITS)
+    }
+
+    /// Encode `value` into its `k` least significant bits, returning
+    /// the bits and `k`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use sna::wlsb::{Shift, WlsbEncoder};
+    ///
+    /// let mut encoder = WlsbEncoder::new(2, Shift::Fixed(0));
+    /// encoder.push(SerialNumber(250u8));
+    /// assert_eq!(encoder.encode(SerialNumber(3u8)), (0b0011, 4));
+    /// ```
+    #[inline]
+    pub fn encode(&self, value: SerialNumber<T>) -> (T, u32) {
+        let k = self.bits_required(value);
+        (value.0 & T::mask(k), k)
+    }
+}
+
+impl<T: Uint> WlsbDecoder<T> {
+    /// Create a decoder with an initial reference value.
+    #[inline]
+    pub fn new(reference: SerialNumber<T>, shift: Shift) -> Self {
+        WlsbDecoder { shift, reference }
+    }
+
+    /// Return the current reference value.
+    #[inline]
+    pub fn reference(&self) -> SerialNumber<T> {
+        self.reference
+    }
+
+    /// Replace the reference value, e.g. after a decoded value has
+    /// been verified.
+    #[inline]
+    pub fn update(&mut self, reference: SerialNumber<T>) {
+        self.reference = reference;
+    }
+
+    /// Return the first and the last value of the interpretation
+    /// interval for `k` least significant bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` exceeds the number of bits of this type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use sna::wlsb::{Shift, WlsbDecoder};
+    ///
+    /// let decoder = WlsbDecoder::new(SerialNumber(2u8), Shift::Fixed(4));
+    /// assert_eq!(decoder.interval(4), (SerialNumber(254), SerialNumber(13)));
+    /// ```
+    pub fn interval(&self, k: u32) -> (SerialNumber<T>, SerialNumber<T>) {
+        assert!(k <= T::BITS, "k exceeds the number of bits");
+        let lower = self.reference.0.wrapping_sub(T::from_i64(self.shift.offset(k)));
+        (SerialNumber(lower), SerialNumber(lower.wrapping_add(T::mask(k))))
+    }
+
+    /// Reconstruct a value from its `k` least significant bits.
+    ///
+    /// Bits of `lsbs` above `k` are ignored. The reference value is
+    /// not updated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` exceeds the number of bits of this type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sna::SerialNumber;
+    /// use sna::wlsb::{Shift, WlsbDecoder};
+    ///
+    /// let decoder = WlsbDecoder::new(SerialNumber(250u8), Shift::Fixed(0));
+    /// assert_eq!(decoder.decode(0b0011, 4), SerialNumber(3u8));
+    /// ```
+    pub fn decode(&self, lsbs: T, k: u32) -> SerialNumber<T> {
+        let (lower, _) = self.interval(k);
+        let mask = T::mask(k);
+        SerialNumber(lower.0.wrapping_add((lsbs & mask).wrapping_sub(lower.0) & mask))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn roundtrip_across_wrap() {
+        let mut encoder = WlsbEncoder::new(4, Shift::Fixed(1));
+        let mut decoder = WlsbDecoder::new(SerialNumber(65530u16), Shift::Fixed(1));
+        encoder.push(SerialNumber(65530u16));
+        for value in (65531u32..65600).map(|value| SerialNumber(value as u16)) {
+            let (lsbs, k) = encoder.encode(value);
+            assert!(k < 16);
+            assert_eq!(decoder.decode(lsbs, k), value);
+            encoder.push(value);
+            decoder.update(value);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn every_reference_decodes() {
+        let mut encoder = WlsbEncoder::new(3, Shift::Fixed(0));
+        for reference in &[250u8, 254, 1] {
+            encoder.push(SerialNumber(*reference));
+        }
+        let value = SerialNumber(9u8);
+        let (lsbs, k) = encoder.encode(value);
+        for reference in encoder.references() {
+            assert_eq!(WlsbDecoder::new(*reference, Shift::Fixed(0)).decode(lsbs, k), value);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn window_eviction() {
+        let mut encoder = WlsbEncoder::new(2, Shift::Fixed(0));
+        encoder.push(SerialNumber(0u32));
+        encoder.push(SerialNumber(100u32));
+        encoder.push(SerialNumber(101u32));
+        assert_eq!(encoder.references().len(), 2);
+        assert_eq!(encoder.bits_required(SerialNumber(102u32)), 2);
+    }
+
+    #[test]
+    fn values_below_reference() {
+        let decoder = WlsbDecoder::new(SerialNumber(1u64), Shift::Fixed(8));
+        assert_eq!(decoder.interval(4), (SerialNumber(u64::MAX - 6), SerialNumber(8)));
+        assert_eq!(decoder.decode(0xe, 4), SerialNumber(u64::MAX - 1));
+    }
+
+    #[test]
+    fn variable_shift() {
+        let shift = Shift::Variable(|k| if k > 2 { (1 << (k - 2)) - 1 } else { 0 });
+        let decoder = WlsbDecoder::new(SerialNumber(100u16), shift);
+        assert_eq!(decoder.interval(6), (SerialNumber(85), SerialNumber(148)));
+        assert_eq!(decoder.decode(90 & 0x3f, 6), SerialNumber(90));
+    }
+
+    #[test]
+    fn all_bits() {
+        let decoder = WlsbDecoder::new(SerialNumber(7u8), Shift::Fixed(-1));
+        assert_eq!(decoder.decode(200, 8), SerialNumber(200));
+    }
+}